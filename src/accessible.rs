@@ -0,0 +1,141 @@
+use std::io::{stdin, stdout, BufRead, Result, Write};
+
+use crate::database::Database;
+use crate::project::*;
+use crate::theme::theme;
+
+const HELP: &str = "Commands: projects, open N, tasks, show N, add TEXT, next N, prev N, \
+                    delete N, help, quit";
+
+fn task_line(number: usize, task: &Task) -> String {
+    let mut line = format!(
+        "{}. {}, {}",
+        number,
+        task.description(),
+        theme().state_label(task.state())
+    );
+    if let Some(assignee) = task.assignee() {
+        line.push_str(&format!(", assigned to {}", assignee));
+    }
+    line
+}
+
+fn list_projects(db: &Database) {
+    println!("{} projects", db.project_count());
+    for (i, project) in db.projects().enumerate() {
+        println!(
+            "{}. {}, {} tasks, {} done",
+            i + 1,
+            project.title(),
+            project.task_count(),
+            project.task_state_count(State::DONE)
+        );
+    }
+}
+
+fn list_tasks(db: &Database, project: usize) {
+    let project = db.project(project);
+    println!("{}: {} tasks", project.title(), project.task_count());
+    for (i, task) in project.tasks.iter().enumerate() {
+        println!("{}", task_line(i + 1, task));
+    }
+}
+
+fn show_task(task: &Task) {
+    println!("Description: {}", task.description());
+    println!("State: {}", theme().state_label(task.state()));
+    if let Some(assignee) = task.assignee() {
+        println!("Assignee: {}", assignee);
+    }
+    if let Some(points) = task.points() {
+        println!("Points: {}", points);
+    }
+    for line in task.notes().lines() {
+        println!("Note: {}", line);
+    }
+    for event in task.history().iter() {
+        if let Event::Comment { data, .. } = event {
+            println!("Comment: {}", data);
+        }
+    }
+}
+
+fn index(argument: &str, len: usize) -> Option<usize> {
+    match argument.trim().parse::<usize>() {
+        Ok(n) if n >= 1 && n <= len => Some(n - 1),
+        _ => None,
+    }
+}
+
+pub fn run(db: &mut Database) -> Result<()> {
+    let mut project: Option<usize> = None;
+    println!("{}", HELP);
+    list_projects(db);
+
+    loop {
+        print!("> ");
+        stdout().flush()?;
+
+        let mut line = String::new();
+        if stdin().lock().read_line(&mut line)? == 0 {
+            break;
+        }
+        let line = line.trim();
+        let (command, argument) = match line.find(' ') {
+            Some(i) => (&line[..i], line[i + 1..].trim()),
+            None => (line, ""),
+        };
+        let task_count = project.map(|p| db.project(p).task_count()).unwrap_or(0);
+
+        match (command, project) {
+            ("quit", _) | ("q", _) => break,
+            ("help", _) => println!("{}", HELP),
+            ("projects", _) => list_projects(db),
+            ("open", _) => match index(argument, db.project_count() as usize) {
+                Some(p) => {
+                    project = Some(p);
+                    list_tasks(db, p);
+                }
+                None => println!("No such project: {}", argument),
+            },
+            (_, None) if command != "" => println!("Open a project first"),
+            ("tasks", Some(p)) => list_tasks(db, p),
+            ("show", Some(p)) => match index(argument, task_count) {
+                Some(t) => show_task(db.task(p, t)),
+                None => println!("No such task: {}", argument),
+            },
+            ("add", Some(p)) if !argument.is_empty() => {
+                db.add_task(p, Task::new(argument.to_string()))?;
+                println!("Added task: {}", argument);
+            }
+            ("next", Some(p)) | ("prev", Some(p)) => match index(argument, task_count) {
+                Some(t) => {
+                    let task = db.task(p, t);
+                    let description = task.description();
+                    let state = match command {
+                        "next" => task.state().next(),
+                        _ => task.state().previous(),
+                    };
+                    db.set_task_state(p, t, state)?;
+                    println!(
+                        "Task {} moved to {}",
+                        description,
+                        theme().state_label(state)
+                    );
+                }
+                None => println!("No such task: {}", argument),
+            },
+            ("delete", Some(p)) => match index(argument, task_count) {
+                Some(t) => {
+                    let description = db.task(p, t).description();
+                    db.remove_task(p, t)?;
+                    println!("Deleted task: {}", description);
+                }
+                None => println!("No such task: {}", argument),
+            },
+            ("", _) => {}
+            _ => println!("Unknown command: {}. Type help for a list.", line),
+        }
+    }
+    Ok(())
+}
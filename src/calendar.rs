@@ -0,0 +1,103 @@
+use chrono::prelude::{Datelike, NaiveDate};
+use chrono::Duration;
+use std::collections::HashMap;
+use termion::color::Rgb;
+
+use crate::formatted_string::FormattedString;
+use crate::theme::theme;
+
+static YELLOW: Rgb = Rgb(241, 196, 15);
+static GREEN: Rgb = Rgb(46, 204, 113);
+static GRAY: Rgb = Rgb(127, 140, 141);
+
+const CELL_WIDTH: usize = 11;
+const WEEKDAYS: [&str; 7] = ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"];
+
+#[derive(Default, Clone, Copy)]
+pub struct Day {
+    pub due: usize,
+    pub done: usize,
+}
+
+pub fn first_of_month(date: NaiveDate) -> NaiveDate {
+    NaiveDate::from_ymd(date.year(), date.month(), 1)
+}
+
+pub fn shift_month(date: NaiveDate, months: i32) -> NaiveDate {
+    let index = date.year() * 12 + date.month0() as i32 + months;
+    let (year, month) = (index.div_euclid(12), index.rem_euclid(12) as u32 + 1);
+    (1..=date.day())
+        .rev()
+        .find_map(|day| NaiveDate::from_ymd_opt(year, month, day))
+        .unwrap()
+}
+
+pub fn weeks(date: NaiveDate) -> Vec<Vec<Option<NaiveDate>>> {
+    let first = first_of_month(date);
+    let mut day = first - Duration::days(first.weekday().num_days_from_monday() as i64);
+    let mut weeks = vec![];
+
+    while weeks.is_empty() || day.month() == date.month() {
+        let week = (0..7)
+            .map(|i| day + Duration::days(i))
+            .map(|d| match d.month() == date.month() {
+                true => Some(d),
+                false => None,
+            })
+            .collect();
+        weeks.push(week);
+        day = day + Duration::weeks(1);
+    }
+    weeks
+}
+
+fn cell(date: NaiveDate, day: Day, selected: bool) -> String {
+    let (due_mark, done_mark) = if theme().ascii {
+        ("!", "+")
+    } else {
+        ("⚑", "✓")
+    };
+    let mut content = format!("{:>2}", date.day());
+    if day.due > 0 {
+        let due = format!(" {}{}", due_mark, day.due);
+        content.push_str(&FormattedString::from(&due).fg(YELLOW).to_string());
+    }
+    if day.done > 0 {
+        let done = format!(" {}{}", done_mark, day.done);
+        content.push_str(&FormattedString::from(&done).fg(GREEN).to_string());
+    }
+    let cell = FormattedString::from(&content).left(CELL_WIDTH);
+    match selected {
+        true => cell.focused().to_string(),
+        false => cell.to_string(),
+    }
+}
+
+pub fn render(selected: NaiveDate, days: &HashMap<NaiveDate, Day>) -> Vec<String> {
+    let mut lines = vec![WEEKDAYS
+        .iter()
+        .map(|name| {
+            FormattedString::from(name)
+                .left(CELL_WIDTH)
+                .fg(GRAY)
+                .to_string()
+        })
+        .collect::<Vec<String>>()
+        .join(" ")];
+
+    for week in weeks(selected) {
+        let cells: Vec<String> = week
+            .iter()
+            .map(|date| match date {
+                Some(date) => {
+                    let day = days.get(date).cloned().unwrap_or_default();
+                    cell(*date, day, *date == selected)
+                }
+                None => " ".repeat(CELL_WIDTH),
+            })
+            .collect();
+        lines.push(cells.join(" "));
+        lines.push(String::from(""));
+    }
+    lines
+}
@@ -0,0 +1,85 @@
+use termion::color::Rgb;
+use unicode_width::UnicodeWidthStr;
+
+use crate::database::Database;
+use crate::formatted_string::FormattedString;
+use crate::locale::{fill, locale};
+use crate::project::*;
+use crate::theme::theme;
+
+static GREEN: Rgb = Rgb(46, 204, 113);
+static CONFETTI: [Rgb; 5] = [
+    Rgb(231, 76, 60),
+    Rgb(241, 196, 15),
+    Rgb(46, 204, 113),
+    Rgb(52, 152, 219),
+    Rgb(214, 162, 232),
+];
+
+pub enum Celebration {
+    Task(String),
+    Project(String),
+}
+
+fn completed(task: &Task) -> bool {
+    match task.events.last() {
+        Some(Event::State { data, .. }) => *data == State::DONE,
+        _ => false,
+    }
+}
+
+pub fn detect(db: &Database, touched: &[String]) -> Option<Celebration> {
+    let mut celebration = None;
+    for project in db.projects() {
+        let done = project
+            .tasks
+            .iter()
+            .find(|task| touched.contains(&task.id) && completed(task));
+        if let Some(task) = done {
+            if project.tasks.iter().all(|task| task.state() == State::DONE) {
+                return Some(Celebration::Project(project.title().to_string()));
+            }
+            celebration = Some(Celebration::Task(task.description()));
+        }
+    }
+    celebration
+}
+
+impl Celebration {
+    pub fn message(&self) -> String {
+        match self {
+            Celebration::Task(description) => fill(&locale().celebrate_task, description),
+            Celebration::Project(description) => fill(&locale().celebrate_project, description),
+        }
+    }
+
+    pub fn banner(&self, width: u16) -> String {
+        let message = self.message();
+        match self {
+            Celebration::Task(_) => FormattedString::from(&format!("✔ {}", message))
+                .fg(GREEN)
+                .to_string(),
+            Celebration::Project(_) => {
+                let piece = if theme().ascii { "*" } else { "✦" };
+                let text = format!(" {} ", message);
+                let side =
+                    (width as usize).saturating_sub(UnicodeWidthStr::width(text.as_str())) / 2;
+                let confetti = |offset: usize| -> String {
+                    (0..side)
+                        .map(|i| {
+                            FormattedString::from(piece)
+                                .fg(CONFETTI[(i + offset) % CONFETTI.len()])
+                                .to_string()
+                        })
+                        .collect()
+                };
+                format!(
+                    "{}{}{}",
+                    confetti(0),
+                    FormattedString::from(&text).fg(GREEN),
+                    confetti(side)
+                )
+            }
+        }
+    }
+}
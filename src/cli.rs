@@ -0,0 +1,767 @@
+use chrono::prelude::{Local, NaiveTime, Utc};
+use chrono::Duration;
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::io::{stdin, stdout, Error, ErrorKind, Read, Result, Write};
+use std::process::{Command, Stdio};
+
+use crate::accessible;
+use crate::config::Config;
+use crate::database::{Database, ImportSummary};
+use crate::diff;
+use crate::filter;
+use crate::formatted_string::FormattedString;
+use crate::project::*;
+use crate::query::Query;
+use crate::serve;
+use crate::stats;
+use crate::stress;
+use crate::theme::theme;
+use crate::timeline;
+
+const INBOX: &str = "Inbox";
+
+pub fn run(args: &[String], config: &Config, db: &mut Database) -> Result<()> {
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    let result = dispatch(&args, config, db);
+    match db.take_rejected() {
+        Some(rejected) => result.and(Err(rejected.into())),
+        None => result,
+    }
+}
+
+fn dispatch(args: &[&str], config: &Config, db: &mut Database) -> Result<()> {
+    match args {
+        ["--accessible"] => accessible::run(db),
+        ["report", "cycle-time"] => report_cycle_time(db),
+        ["report", "cfd"] => report_cumulative_flow(db),
+        ["report", "velocity"] => report_velocity(db),
+        ["daily", "--project", name, "off"] => {
+            let project = db.find_project(name)?;
+            db.set_project_daily(project, None)
+        }
+        ["daily", "--project", name, time] => {
+            let project = db.find_project(name)?;
+            match NaiveTime::parse_from_str(time, "%H:%M") {
+                Ok(time) => db.set_project_daily(project, Some(time)),
+                Err(_) => Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    format!("Invalid time: {} (expected HH:MM)", time),
+                )),
+            }
+        }
+        ["export", "--events"] => export_events(db),
+        ["export", "--project", name] => {
+            println!("{}", db.export_project(db.find_project(name)?)?);
+            Ok(())
+        }
+        ["export", "--project", name, "--output", path]
+        | ["export", "--output", path, "--project", name] => {
+            fs::write(path, db.export_project(db.find_project(name)?)? + "\n")
+        }
+        ["config", "export"] => {
+            println!("{}", Config::export_bundle()?);
+            Ok(())
+        }
+        ["config", "export", "--output", path] => fs::write(path, Config::export_bundle()? + "\n"),
+        ["config", "import", path] => import_config(db, path),
+        ["stress", options @ ..] => stress::run(&stress::Options::parse(options)?),
+        ["merge", path] => merge(db, path),
+        ["diff", snapshot] => diff(db, snapshot),
+        ["serve"] => serve::run(serve::DEFAULT_PORT),
+        ["serve", "--port", port] => match port.parse::<u16>() {
+            Ok(port) => serve::run(port),
+            Err(_) => Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("Invalid port: {}", port),
+            )),
+        },
+        ["report"] => report_standup(db, Dialect::Plain),
+        ["report", "--format", format] => report_standup(db, dialect(format)?),
+        ["report", "digest"] => report_digest(db, false, Dialect::Plain),
+        ["report", "digest", "--weekly"] => report_digest(db, true, Dialect::Plain),
+        ["report", "digest", "--format", format] => report_digest(db, false, dialect(format)?),
+        ["report", "digest", "--weekly", "--format", format]
+        | ["report", "digest", "--format", format, "--weekly"] => {
+            report_digest(db, true, dialect(format)?)
+        }
+        ["report", "stale"] => report_stale(db, 14),
+        ["report", "stale", "--days", days] => match days.parse::<i64>() {
+            Ok(days) if days >= 0 => report_stale(db, days),
+            _ => Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("Invalid number of days: {}", days),
+            )),
+        },
+        ["report", "email", options @ ..] => report_email(db, config, options),
+        ["report", "billing"] => report_billing(db, false),
+        ["report", "billing", "--csv"] => report_billing(db, true),
+        ["rate", "--project", name, rate] => {
+            let project = db.find_project(name)?;
+            match rate.parse::<f64>() {
+                Ok(rate) if rate >= 0.0 => db.set_project_rate(project, rate),
+                _ => Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    format!("Invalid rate: {}", rate),
+                )),
+            }
+        }
+        ["list"] => list(db, &Query::All),
+        ["list", "--where", query] => list(db, &Query::parse(query, config)?),
+        ["list", "--plain"] => list_plain(db, &Query::All),
+        ["list", "--plain", "--where", query] | ["list", "--where", query, "--plain"] => {
+            list_plain(db, &Query::parse(query, config)?)
+        }
+        ["import", "--lines", path, "--project", name]
+        | ["import", "--project", name, "--lines", path] => import_lines(db, name, path),
+        ["import", "--json", path] => import_json(db, None, path),
+        ["import", "--json", path, "--project", name]
+        | ["import", "--project", name, "--json", path] => import_json(db, Some(name), path),
+        ["add", "--project", name, "--stdin"] => import_lines(db, name, "-"),
+        ["add", "--project", name, description @ ..] if !description.is_empty() => {
+            let project = db.find_project(name)?;
+            db.add_task(project, Task::new(description.join(" ")))
+                .map(|_| ())
+        }
+        ["move", "--state", state, "--from", from, "--to", to] => {
+            let state = filter::parse_state(state).ok_or_else(|| {
+                Error::new(ErrorKind::InvalidInput, format!("Unknown state: {}", state))
+            })?;
+            let (from, to) = (db.find_project(from)?, db.find_project(to)?);
+            let moved = db.transfer_tasks(from, to, state)?;
+            println!(
+                "Moved {} tasks from {} to {}",
+                moved,
+                db.project(from).title(),
+                db.project(to).title()
+            );
+            Ok(())
+        }
+        ["search", query @ ..] if !query.is_empty() => search(db, &query.join(" ")),
+        ["quick", description @ ..] => quick(db, description),
+        _ => Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!("Unknown command: {}", args.join(" ")),
+        )),
+    }
+}
+
+fn report_cycle_time(db: &Database) -> Result<()> {
+    for project in db.projects() {
+        println!("{}", project.title());
+        for line in stats::cycle_time_report(project) {
+            println!("  {}", line);
+        }
+        println!();
+    }
+    Ok(())
+}
+
+fn report_cumulative_flow(db: &Database) -> Result<()> {
+    println!("project,week,todo,ongoing,waiting,done");
+    for project in db.projects() {
+        for point in stats::cumulative_flow(project) {
+            println!(
+                "{},{},{},{},{},{}",
+                project.id,
+                point.week.format("%Y-%m-%d"),
+                point.todo,
+                point.ongoing,
+                point.waiting,
+                point.done
+            );
+        }
+    }
+    Ok(())
+}
+
+fn report_velocity(db: &Database) -> Result<()> {
+    for project in db.projects() {
+        println!("{}", project.title());
+        for line in stats::velocity_report(project) {
+            println!("  {}", line);
+        }
+        println!();
+    }
+    Ok(())
+}
+
+fn diff(db: &Database, snapshot: &str) -> Result<()> {
+    let entries = db.diff(snapshot)?;
+    for line in diff::lines(&entries, use_color()) {
+        println!("{}", line);
+    }
+    println!("{}", diff::totals(&entries));
+    Ok(())
+}
+
+fn report_stale(db: &Database, days: i64) -> Result<()> {
+    let now = Utc::now();
+    for project in db.projects() {
+        let stale = stats::stale_tasks(project, now, days);
+        if stale.is_empty() {
+            continue;
+        }
+        println!("{}", project.title());
+        for (task, age) in stale {
+            println!(
+                "  {:>4}d  {:<8} {:<8} {}",
+                age,
+                task.shortcode(),
+                format!("{:?}", task.state()),
+                task.description()
+            );
+        }
+        println!();
+    }
+    Ok(())
+}
+
+fn export_events(db: &Database) -> Result<()> {
+    for project in db.projects() {
+        for task in project.tasks.iter().chain(project.archive.iter()) {
+            for event in task.events.iter() {
+                let mut line = serde_json::to_value(event)?;
+                if let Some(fields) = line.as_object_mut() {
+                    fields.insert(String::from("project_id"), project.id.clone().into());
+                    fields.insert(String::from("task_id"), task.id.clone().into());
+                }
+                println!("{}", line);
+            }
+        }
+    }
+    Ok(())
+}
+
+#[derive(Clone, Copy)]
+enum Dialect {
+    Plain,
+    Slack,
+    Matrix,
+}
+
+type Section = (&'static str, Vec<String>);
+
+fn dialect(format: &str) -> Result<Dialect> {
+    match format {
+        "plain" => Ok(Dialect::Plain),
+        "slack" => Ok(Dialect::Slack),
+        "matrix" => Ok(Dialect::Matrix),
+        _ => Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!(
+                "Unknown format: {} (expected plain, slack or matrix)",
+                format
+            ),
+        )),
+    }
+}
+
+fn escape(text: &str, dialect: Dialect) -> String {
+    match dialect {
+        Dialect::Plain => text.to_string(),
+        Dialect::Slack => text
+            .replace('&', "&amp;")
+            .replace('<', "&lt;")
+            .replace('>', "&gt;"),
+        Dialect::Matrix => text
+            .chars()
+            .map(|c| match c {
+                '\\' | '*' | '_' | '`' | '[' | ']' | '<' | '>' | '#' => format!("\\{}", c),
+                c => c.to_string(),
+            })
+            .collect(),
+    }
+}
+
+fn render_sections(sections: Vec<Section>, dialect: Dialect) -> Vec<String> {
+    let mut lines = vec![];
+    for (title, items) in sections {
+        let title = format!("{} ({})", title, items.len());
+        lines.push(match dialect {
+            Dialect::Plain => title,
+            Dialect::Slack => format!("*{}*", title),
+            Dialect::Matrix => format!("**{}**", title),
+        });
+        let bullet = match dialect {
+            Dialect::Slack => "•",
+            _ => "-",
+        };
+        if items.is_empty() {
+            lines.push(format!("{} nothing", bullet));
+        }
+        for item in items {
+            lines.push(format!("{} {}", bullet, escape(&item, dialect)));
+        }
+        lines.push(String::from(""));
+    }
+    lines
+}
+
+fn digest(db: &Database, days: i64) -> Vec<Section> {
+    let today = Local::now().date().naive_local();
+    let soon = today + Duration::days(days);
+    let since = today - Duration::days(days);
+    let (mut overdue, mut due_soon, mut completed) = (vec![], vec![], vec![]);
+
+    for project in db.projects() {
+        for task in project.tasks.iter() {
+            let line = format!("[{}] {}", project.title(), task.description());
+            match (task.state(), task.due()) {
+                (State::DONE, _) => {
+                    let done = stats::completed_at(task)
+                        .map(|date_time| date_time.with_timezone(&Local).date().naive_local());
+                    if done.map(|done| done > since).unwrap_or(false) {
+                        completed.push(line);
+                    }
+                }
+                (_, Some(due)) if due < today => {
+                    overdue.push(format!("{} (due {})", line, due.format("%Y-%m-%d")))
+                }
+                (_, Some(due)) if due <= soon => {
+                    due_soon.push(format!("{} (due {})", line, due.format("%Y-%m-%d")))
+                }
+                _ => {}
+            }
+        }
+    }
+
+    vec![
+        ("Overdue", overdue),
+        ("Due soon", due_soon),
+        ("Completed", completed),
+    ]
+}
+
+fn standup(db: &Database) -> Vec<Section> {
+    let since = Utc::now() - Duration::days(1);
+    let (mut done, mut ongoing, mut waiting) = (vec![], vec![], vec![]);
+
+    for project in db.projects() {
+        for task in project.tasks.iter() {
+            let line = format!("[{}] {}", project.title(), task.description());
+            match task.state() {
+                State::DONE => {
+                    if stats::completed_at(task)
+                        .map(|at| at > since)
+                        .unwrap_or(false)
+                    {
+                        done.push(line);
+                    }
+                }
+                State::ONGOING => ongoing.push(line),
+                State::WAITING => match task.waiting_on() {
+                    Some((who, _)) if !who.is_empty() => {
+                        waiting.push(format!("{} (waiting on {})", line, who))
+                    }
+                    _ => waiting.push(line),
+                },
+                State::TODO => {}
+            }
+        }
+    }
+
+    vec![
+        ("Done", done),
+        ("In progress", ongoing),
+        ("Blocked", waiting),
+    ]
+}
+
+fn report_standup(db: &Database, dialect: Dialect) -> Result<()> {
+    for line in render_sections(standup(db), dialect) {
+        println!("{}", line);
+    }
+    Ok(())
+}
+
+fn report_digest(db: &Database, weekly: bool, dialect: Dialect) -> Result<()> {
+    let days = if weekly { 7 } else { 1 };
+    for line in render_sections(digest(db, days), dialect) {
+        println!("{}", line);
+    }
+    Ok(())
+}
+
+fn report_email(db: &Database, config: &Config, options: &[&str]) -> Result<()> {
+    let (mut days, mut to, mut eml) = (1, config.email.clone(), None);
+    let mut options = options.iter();
+    while let Some(option) = options.next() {
+        match (*option, options.clone().next()) {
+            ("--weekly", _) => days = 7,
+            ("--to", Some(address)) => {
+                to = Some(address.to_string());
+                options.next();
+            }
+            ("--eml", Some(path)) => {
+                eml = Some(path.to_string());
+                options.next();
+            }
+            _ => {
+                return Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    format!("Unknown option: {}", option),
+                ))
+            }
+        }
+    }
+    let to = to.ok_or_else(|| {
+        Error::new(
+            ErrorKind::InvalidInput,
+            "No recipient, use --to or set email in config.toml",
+        )
+    })?;
+    let from = match (&config.email, config.me(), config.hostname()) {
+        (Some(email), _, _) => email.clone(),
+        (None, Some(user), Some(host)) => format!("{}@{}", user, host),
+        _ => {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "No sender, set email in config.toml",
+            ))
+        }
+    };
+
+    let title = match days {
+        1 => "Daily",
+        _ => "Weekly",
+    };
+    let message = format!(
+        "From: {}\r\nTo: {}\r\nSubject: {} tasks digest for {}\r\nDate: {}\r\nMIME-Version: 1.0\r\nContent-Type: text/plain; charset=utf-8\r\nContent-Transfer-Encoding: 8bit\r\n\r\n{}",
+        from,
+        to,
+        title,
+        Local::now().format("%Y-%m-%d"),
+        Local::now().to_rfc2822(),
+        render_sections(digest(db, days), Dialect::Plain).join("\r\n")
+    );
+
+    match eml {
+        Some(path) => fs::write(path, message),
+        None => {
+            let mut sendmail = Command::new("sendmail")
+                .arg("-t")
+                .stdin(Stdio::piped())
+                .spawn()?;
+            sendmail
+                .stdin
+                .take()
+                .unwrap()
+                .write_all(message.as_bytes())?;
+            let status = sendmail.wait()?;
+            match status.success() {
+                true => Ok(()),
+                false => Err(Error::new(
+                    ErrorKind::Other,
+                    format!("sendmail failed with {}", status),
+                )),
+            }
+        }
+    }
+}
+
+fn csv_field(field: &str) -> String {
+    if field.contains(',') || field.contains('"') || field.contains('\n') {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+fn report_billing(db: &Database, csv: bool) -> Result<()> {
+    let projects = db.projects().filter(|p| p.rate.is_some());
+    if csv {
+        println!("project,task,hours,rate,amount");
+        for project in projects {
+            for item in stats::billable(project) {
+                println!(
+                    "{},{},{:.2},{:.2},{:.2}",
+                    csv_field(project.title()),
+                    csv_field(&item.description),
+                    item.hours,
+                    project.rate.unwrap_or(0.0),
+                    item.amount
+                );
+            }
+        }
+    } else {
+        for project in projects {
+            for line in stats::billing_markdown(project) {
+                println!("{}", line);
+            }
+            println!();
+        }
+    }
+    Ok(())
+}
+
+fn inbox(db: &mut Database) -> Result<usize> {
+    match db.find_project(INBOX) {
+        Ok(project) => Ok(project),
+        Err(_) => {
+            db.add_project(Project::new(String::from(INBOX)))?;
+            Ok(db.project_count() as usize - 1)
+        }
+    }
+}
+
+fn quick(db: &mut Database, description: &[&str]) -> Result<()> {
+    let description = match description {
+        [] => {
+            print!("{}> ", INBOX);
+            stdout().flush()?;
+            let mut line = String::new();
+            stdin().read_line(&mut line)?;
+            line.trim().to_string()
+        }
+        words => words.join(" "),
+    };
+    if description.is_empty() {
+        return Ok(());
+    }
+    let project = inbox(db)?;
+    db.add_task(project, Task::new(description)).map(|_| ())
+}
+
+fn search(db: &Database, query: &str) -> Result<()> {
+    for hit in db.search(query) {
+        println!("{:>4}  {}", hit.score, task_line(db, hit.project, hit.task));
+    }
+    Ok(())
+}
+
+fn list(db: &Database, query: &Query) -> Result<()> {
+    for (project, task) in db.matching_tasks(query) {
+        println!("{}", task_line(db, project, task));
+    }
+    Ok(())
+}
+
+fn use_color() -> bool {
+    env::var_os("NO_COLOR").is_none() && termion::is_tty(&stdout())
+}
+
+fn list_plain(db: &Database, query: &Query) -> Result<()> {
+    let color = use_color();
+    let header = [
+        "PROJECT",
+        "ID",
+        "STATE",
+        "ASSIGNEE",
+        "CREATED",
+        "COMPLETED",
+        "DESCRIPTION",
+    ];
+    let mut rows = vec![(
+        None,
+        header.iter().map(|h| h.to_string()).collect::<Vec<_>>(),
+    )];
+    for (project, task) in db.matching_tasks(query) {
+        let task = db.task(project, task);
+        rows.push((
+            Some(task.state()),
+            vec![
+                db.project(project).title().to_string(),
+                task.shortcode(),
+                format!("{:?}", task.state()),
+                task.assignee().unwrap_or_default(),
+                timeline::relative(task.created_at),
+                stats::completed_at(task)
+                    .map(timeline::relative)
+                    .unwrap_or_default(),
+                task.description(),
+            ],
+        ));
+    }
+
+    let widths: Vec<usize> = (0..header.len())
+        .map(|column| {
+            rows.iter()
+                .map(|(_, cells)| cells[column].chars().count())
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
+
+    for (state, cells) in rows {
+        let mut line: Vec<String> = cells
+            .iter()
+            .zip(widths.iter())
+            .map(|(cell, width)| format!("{:<width$}", cell, width = width))
+            .collect();
+        if let (true, Some(state)) = (color, state) {
+            line[2] = FormattedString::from(&line[2])
+                .fg(theme().state_color(state))
+                .to_string();
+        }
+        println!("{}", line.join("  ").trim_end());
+    }
+    Ok(())
+}
+
+fn task_line(db: &Database, project: usize, task: usize) -> String {
+    let task = db.task(project, task);
+    format!(
+        "{:<16} {} {:<8} {}",
+        db.project(project).title(),
+        task.shortcode(),
+        format!("{:?}", task.state()),
+        task.description()
+    )
+}
+
+#[derive(Deserialize)]
+struct ImportedTask {
+    id: Option<String>,
+    description: String,
+    project: Option<String>,
+    state: Option<State>,
+    assignee: Option<String>,
+    sprint: Option<String>,
+    points: Option<u32>,
+    notes: Option<String>,
+}
+
+fn read_input(path: &str) -> Result<String> {
+    if path == "-" {
+        let mut content = String::new();
+        stdin().read_to_string(&mut content)?;
+        Ok(content)
+    } else {
+        fs::read_to_string(path)
+    }
+}
+
+fn import_lines(db: &mut Database, project: &str, path: &str) -> Result<()> {
+    let project = db.find_project(project)?;
+    let tasks: Vec<Task> = task_lines(&read_input(path)?)
+        .into_iter()
+        .map(Task::new)
+        .collect();
+    let summary = db.import_tasks(project, tasks)?;
+    report_import(db, &summary);
+    print_import_totals(db, &[summary]);
+    Ok(())
+}
+
+fn import_config(db: &Database, path: &str) -> Result<()> {
+    let imported = Config::import_bundle(&fs::read_to_string(path)?, db.dry_run())?;
+    let verb = match db.dry_run() {
+        true => "Would import",
+        false => "Imported",
+    };
+    for name in imported.iter() {
+        println!("{} {}", verb, name);
+    }
+    Ok(())
+}
+
+fn merge(db: &mut Database, path: &str) -> Result<()> {
+    let (project, summary) = db.merge_project(path)?;
+    println!("{}", project);
+    report_import(db, &summary);
+    print_import_totals(db, &[summary]);
+    Ok(())
+}
+
+fn report_import(db: &Database, summary: &ImportSummary) {
+    if db.dry_run() {
+        for description in summary.added.iter() {
+            println!("+ {}", description);
+        }
+        for description in summary.merged.iter() {
+            println!("~ {}", description);
+        }
+        for description in summary.skipped.iter() {
+            println!("= {}", description);
+        }
+    }
+}
+
+fn print_import_totals(db: &Database, summaries: &[ImportSummary]) {
+    let count = |f: fn(&ImportSummary) -> usize| summaries.iter().map(f).sum::<usize>();
+    println!(
+        "{} {} tasks, merged {}, skipped {} duplicates",
+        match db.dry_run() {
+            true => "Would import",
+            false => "Imported",
+        },
+        count(|s| s.added.len()),
+        count(|s| s.merged.len()),
+        count(|s| s.skipped.len())
+    );
+}
+
+fn import_json(db: &mut Database, project: Option<&str>, path: &str) -> Result<()> {
+    let imported: Vec<ImportedTask> = serde_json::from_str(&read_input(path)?)?;
+    let mut tasks: BTreeMap<usize, Vec<Task>> = BTreeMap::new();
+
+    for item in imported {
+        let project = match item.project.as_ref().map(String::as_str).or(project) {
+            Some(name) => db.find_project(name)?,
+            None => {
+                return Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    format!("No project given for \"{}\"", item.description),
+                ))
+            }
+        };
+
+        let mut task = Task::new(item.description);
+        if let Some(id) = item.id {
+            task.id = id;
+        }
+        let now = Utc::now();
+        if let Some(state) = item.state {
+            task.events.push(Event::State {
+                data: state,
+                date_time: now,
+                actor: None,
+                host: None,
+            });
+        }
+        if let Some(assignee) = item.assignee {
+            task.events.push(Event::Assignee {
+                data: Some(assignee),
+                date_time: now,
+                actor: None,
+                host: None,
+            });
+        }
+        if let Some(sprint) = item.sprint {
+            task.events.push(Event::Sprint {
+                data: Some(sprint),
+                date_time: now,
+                actor: None,
+                host: None,
+            });
+        }
+        if let Some(points) = item.points {
+            task.events.push(Event::Points {
+                data: points,
+                date_time: now,
+                actor: None,
+                host: None,
+            });
+        }
+        if let Some(notes) = item.notes.filter(|notes| !notes.is_empty()) {
+            task.events.push(Event::Notes {
+                data: notes,
+                date_time: now,
+                actor: None,
+                host: None,
+            });
+        }
+        tasks.entry(project).or_insert_with(Vec::new).push(task);
+    }
+
+    let mut summaries = vec![];
+    for (project, tasks) in tasks {
+        let summary = db.import_tasks(project, tasks)?;
+        report_import(db, &summary);
+        summaries.push(summary);
+    }
+    print_import_totals(db, &summaries);
+    Ok(())
+}
@@ -0,0 +1,335 @@
+use std::io::{Error, ErrorKind, Result};
+use std::sync::{Arc, Mutex, RwLock};
+use termion::color::Rgb;
+use unicode_width::UnicodeWidthStr;
+
+use crate::formatted_string::{FormattedLine, FormattedString};
+use crate::locale::locale;
+use crate::project::{narrow, state_width, terminal_width, State, Task};
+use crate::theme::theme;
+
+static COLUMNS: RwLock<Option<Arc<Columns>>> = RwLock::new(None);
+static FITTED: Mutex<Vec<(TaskColumn, usize)>> = Mutex::new(Vec::new());
+
+static BLUE: Rgb = Rgb(52, 152, 219);
+static GRAY: Rgb = Rgb(127, 140, 141);
+
+const BAR_WIDTH: usize = 16;
+
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum TaskColumn {
+    State,
+    Progress,
+    Description,
+    Assignee,
+    Created,
+    Id,
+    Points,
+    Sprint,
+    Milestone,
+    Due,
+}
+
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ProjectColumn {
+    Tasks,
+    Todo,
+    Ongoing,
+    Waiting,
+    Done,
+    Complete,
+    Remaining,
+    Bar,
+    Description,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct Columns {
+    pub projects: Vec<ProjectColumn>,
+    pub tasks: Vec<TaskColumn>,
+    pub compact: bool,
+}
+
+impl Default for Columns {
+    fn default() -> Columns {
+        Columns {
+            projects: vec![
+                ProjectColumn::Tasks,
+                ProjectColumn::Todo,
+                ProjectColumn::Ongoing,
+                ProjectColumn::Waiting,
+                ProjectColumn::Done,
+                ProjectColumn::Complete,
+                ProjectColumn::Remaining,
+                ProjectColumn::Description,
+            ],
+            tasks: vec![
+                TaskColumn::State,
+                TaskColumn::Progress,
+                TaskColumn::Description,
+                TaskColumn::Assignee,
+                TaskColumn::Created,
+            ],
+            compact: false,
+        }
+    }
+}
+
+impl TaskColumn {
+    pub fn label(self) -> String {
+        match self {
+            TaskColumn::State => locale().header_state.clone(),
+            TaskColumn::Progress => locale().header_progress.clone(),
+            TaskColumn::Description => locale().header_description.clone(),
+            TaskColumn::Assignee => locale().header_assignee.clone(),
+            TaskColumn::Created => locale().header_created_at.clone(),
+            TaskColumn::Id => locale().header_id.clone(),
+            TaskColumn::Points => locale().header_points.clone(),
+            TaskColumn::Sprint => locale().header_sprint.clone(),
+            TaskColumn::Milestone => locale().header_milestone.clone(),
+            TaskColumn::Due => locale().header_due.clone(),
+        }
+    }
+
+    fn bounds(self) -> (usize, usize) {
+        match self {
+            TaskColumn::State | TaskColumn::Description => (0, 0),
+            TaskColumn::Progress => (4, 10),
+            TaskColumn::Assignee => (4, 20),
+            TaskColumn::Created => (4, 24),
+            TaskColumn::Id => (2, 8),
+            TaskColumn::Points => (2, 6),
+            TaskColumn::Sprint => (4, 16),
+            TaskColumn::Milestone => (4, 16),
+            TaskColumn::Due => (4, 10),
+        }
+    }
+
+    fn width(self) -> Option<usize> {
+        let (min, max) = self.bounds();
+        let fitted = FITTED
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|(column, _)| *column == self)
+            .map(|(_, width)| *width);
+
+        match self {
+            TaskColumn::State => Some(state_width()),
+            TaskColumn::Description => None,
+            _ => Some(fitted.unwrap_or(max).max(min).min(max)),
+        }
+    }
+}
+
+impl ProjectColumn {
+    pub fn label(self) -> String {
+        match self {
+            ProjectColumn::Tasks => locale().header_tasks.clone(),
+            ProjectColumn::Todo => locale().header_todo.clone(),
+            ProjectColumn::Ongoing => locale().header_ongoing.clone(),
+            ProjectColumn::Waiting => locale().header_waiting.clone(),
+            ProjectColumn::Done => locale().header_done.clone(),
+            ProjectColumn::Complete => locale().header_complete.clone(),
+            ProjectColumn::Remaining => locale().header_remaining.clone(),
+            ProjectColumn::Bar => locale().header_progress.clone(),
+            ProjectColumn::Description => locale().header_description.clone(),
+        }
+    }
+
+    fn width(self) -> Option<usize> {
+        match self {
+            ProjectColumn::Description => None,
+            ProjectColumn::Bar => Some(BAR_WIDTH),
+            _ => Some(UnicodeWidthStr::width(self.label().as_str()).max(4)),
+        }
+    }
+
+    fn counts(self) -> bool {
+        match self {
+            ProjectColumn::Tasks
+            | ProjectColumn::Todo
+            | ProjectColumn::Ongoing
+            | ProjectColumn::Waiting
+            | ProjectColumn::Done => true,
+            _ => false,
+        }
+    }
+}
+
+pub fn state_bar(counts: &[(State, usize)], width: usize) -> FormattedString {
+    let (filled, empty) = if theme().ascii {
+        ("#", "-")
+    } else {
+        ("█", "░")
+    };
+    let total: usize = counts.iter().map(|(_, count)| count).sum();
+    if total == 0 {
+        return FormattedString::from(&empty.repeat(width)).fg(GRAY);
+    }
+
+    let mut bar = String::new();
+    let mut drawn = 0;
+    let mut seen = 0;
+    for (state, count) in counts {
+        seen += count;
+        let end = (seen * width + total / 2) / total;
+        let cells = end.saturating_sub(drawn);
+        drawn += cells;
+        bar.push_str(
+            &FormattedString::from(&filled.repeat(cells))
+                .fg(theme().state_color(*state))
+                .to_string(),
+        );
+    }
+    FormattedString::from(&bar)
+}
+
+pub fn fit_tasks(tasks: &[&Task]) {
+    let fitted = columns()
+        .tasks
+        .iter()
+        .map(|column| {
+            let content = tasks
+                .iter()
+                .map(|task| UnicodeWidthStr::width(task.cell(*column).as_str()))
+                .max()
+                .unwrap_or(0);
+            (
+                *column,
+                content.max(UnicodeWidthStr::width(column.label().as_str())),
+            )
+        })
+        .collect();
+    *FITTED.lock().unwrap() = fitted;
+}
+
+fn fit(widths: Vec<Option<usize>>) -> Vec<usize> {
+    let fixed: usize = widths.iter().filter_map(|width| *width).sum();
+    let dividers = 5 + 3 * widths.len().saturating_sub(1);
+    let scrollbar = 1;
+    let remaining = terminal_width().saturating_sub(fixed + dividers + scrollbar);
+    widths
+        .into_iter()
+        .map(|width| width.unwrap_or(remaining))
+        .collect()
+}
+
+impl Columns {
+    pub fn check(&self) -> Result<()> {
+        if self.projects.is_empty() || self.tasks.is_empty() {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                "config.toml: columns can't be empty",
+            ));
+        }
+        Ok(())
+    }
+
+    pub fn tasks(&self) -> Vec<TaskColumn> {
+        self.tasks
+            .iter()
+            .cloned()
+            .filter(|column| !(narrow() && *column == TaskColumn::Created))
+            .collect()
+    }
+
+    pub fn task_widths(&self) -> Vec<usize> {
+        fit(self.tasks().into_iter().map(TaskColumn::width).collect())
+    }
+
+    pub fn projects(&self) -> Vec<ProjectColumn> {
+        if !self.compact {
+            return self.projects.clone();
+        }
+        let mut projects = vec![];
+        for column in self.projects.iter() {
+            let column = match column.counts() {
+                true => ProjectColumn::Bar,
+                false => *column,
+            };
+            if !projects.contains(&column) {
+                projects.push(column);
+            }
+        }
+        projects
+    }
+
+    pub fn project_widths(&self) -> Vec<usize> {
+        fit(self
+            .projects()
+            .into_iter()
+            .map(ProjectColumn::width)
+            .collect())
+    }
+
+    pub fn description_width(&self) -> usize {
+        self.tasks()
+            .into_iter()
+            .zip(self.task_widths())
+            .find(|(column, _)| *column == TaskColumn::Description)
+            .map(|(_, width)| width)
+            .unwrap_or(0)
+    }
+}
+
+fn div() -> FormattedString {
+    FormattedString::from(theme().vertical()).fg(BLUE)
+}
+
+fn row(cells: Vec<FormattedString>) -> String {
+    let mut line = FormattedLine::new().push(div().left(2));
+    for (i, cell) in cells.into_iter().enumerate() {
+        if i > 0 {
+            line = line.push(div().center(3));
+        }
+        line = line.push(cell);
+    }
+    line.to_string()
+}
+
+pub fn task_row<F: Fn(TaskColumn, usize) -> FormattedString>(cell: F) -> String {
+    let columns = columns();
+    row(columns
+        .tasks()
+        .into_iter()
+        .zip(columns.task_widths())
+        .map(|(column, width)| cell(column, width))
+        .collect())
+}
+
+pub fn project_row<F: Fn(ProjectColumn, usize) -> FormattedString>(cell: F) -> String {
+    let columns = columns();
+    row(columns
+        .projects()
+        .into_iter()
+        .zip(columns.project_widths())
+        .map(|(column, width)| cell(column, width))
+        .collect())
+}
+
+pub fn dividers(widths: &[usize]) -> Vec<u16> {
+    let mut dividers = vec![0];
+    let mut position = 2;
+    for width in widths.iter().take(widths.len().saturating_sub(1)) {
+        position += width;
+        dividers.push(position as u16 + 1);
+        position += 3;
+    }
+    dividers
+}
+
+pub fn init(columns: Columns) {
+    *COLUMNS.write().unwrap() = Some(Arc::new(columns));
+}
+
+pub fn columns() -> Arc<Columns> {
+    if let Some(columns) = &*COLUMNS.read().unwrap() {
+        return columns.clone();
+    }
+    init(Columns::default());
+    columns()
+}
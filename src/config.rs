@@ -0,0 +1,308 @@
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::io::{Error, ErrorKind, Result};
+use std::path::Path;
+use std::time::SystemTime;
+
+use crate::columns::Columns;
+use crate::locale::Locale;
+use crate::mqtt::Mqtt;
+use crate::query::Query;
+use crate::theme::Theme;
+
+const CONFIG_FILE: &str = "config.toml";
+
+pub fn expand_home(path: &str) -> String {
+    match (path.strip_prefix("~/"), dirs::home_dir()) {
+        (Some(rest), Some(home)) => home.join(rest).to_string_lossy().into_owned(),
+        _ => path.to_string(),
+    }
+}
+const BUNDLE_VERSION: u32 = 1;
+
+const BUILT_IN_KEYS: &str =
+    "\n \"%&*+-/:<>@|123456789ABCDEGHIJKLMOQRSTVWXYZabcdefghijklmopqrstuvwxyz";
+
+#[derive(Serialize, Deserialize)]
+struct Bundle {
+    version: u32,
+    files: BTreeMap<String, String>,
+}
+
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Safety {
+    Fast,
+    Normal,
+    Paranoid,
+}
+
+impl Default for Safety {
+    fn default() -> Safety {
+        Safety::Normal
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Operation {
+    Delete,
+    DeleteTask,
+    Bulk,
+    Regress,
+    Advance,
+}
+
+#[derive(Deserialize, Default, Clone, Debug)]
+#[serde(default)]
+pub struct Confirm {
+    pub mode: Safety,
+    pub delete: Option<bool>,
+    pub delete_task: Option<bool>,
+    pub bulk: Option<bool>,
+    pub regress: Option<bool>,
+    pub advance: Option<bool>,
+}
+
+impl Confirm {
+    pub fn requires(&self, operation: Operation) -> bool {
+        let setting = match operation {
+            Operation::Delete => self.delete,
+            Operation::DeleteTask => self.delete_task,
+            Operation::Bulk => self.bulk,
+            Operation::Regress => self.regress,
+            Operation::Advance => self.advance,
+        };
+        setting.unwrap_or_else(|| match self.mode {
+            Safety::Fast => false,
+            Safety::Paranoid => true,
+            Safety::Normal => match operation {
+                Operation::Delete | Operation::Bulk => true,
+                _ => false,
+            },
+        })
+    }
+}
+
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Alert {
+    None,
+    Bell,
+    Notify,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct Celebrate {
+    pub enabled: bool,
+    pub alert: Alert,
+}
+
+impl Default for Celebrate {
+    fn default() -> Celebrate {
+        Celebrate {
+            enabled: true,
+            alert: Alert::None,
+        }
+    }
+}
+
+#[derive(Deserialize, Default, Clone, Debug)]
+#[serde(default)]
+pub struct Wip {
+    pub limit: Option<usize>,
+}
+
+#[derive(Deserialize, Default, Clone, Debug)]
+#[serde(default)]
+pub struct Idle {
+    pub minutes: Option<u64>,
+}
+
+impl Idle {
+    pub fn threshold(&self) -> Option<std::time::Duration> {
+        self.minutes
+            .filter(|minutes| *minutes > 0)
+            .map(|minutes| std::time::Duration::from_secs(minutes * 60))
+    }
+}
+
+#[derive(Deserialize, Default, Clone, Debug)]
+#[serde(default)]
+pub struct Archive {
+    pub done_after_days: Option<u32>,
+}
+
+#[derive(Deserialize, Default, Clone, Debug)]
+#[serde(default)]
+pub struct Config {
+    pub storage: Option<String>,
+    pub user: Option<String>,
+    pub host: Option<String>,
+    pub email: Option<String>,
+    pub views: BTreeMap<String, String>,
+    pub bindings: BTreeMap<String, String>,
+    pub locale: Locale,
+    pub theme: Theme,
+    pub columns: Columns,
+    pub archive: Archive,
+    pub confirm: Confirm,
+    pub wip: Wip,
+    pub idle: Idle,
+    pub celebrate: Celebrate,
+    pub mqtt: Option<Mqtt>,
+    pub mouse: bool,
+}
+
+impl Config {
+    fn storage() -> Result<String> {
+        match dirs::home_dir() {
+            Some(path) => Ok(format!(
+                "{}{}",
+                path.to_str().unwrap(),
+                "/.tasks/config.toml"
+            )),
+            None => Err(Error::new(
+                ErrorKind::Other,
+                "Couldn't resolve your home directory",
+            )),
+        }
+    }
+
+    pub fn load() -> Result<Config> {
+        match fs::read_to_string(Config::storage()?) {
+            Ok(content) => Config::parse(&content),
+            Err(ref e) if e.kind() == ErrorKind::NotFound => Ok(Config::default()),
+            Err(e) => Err(e),
+        }
+    }
+
+    pub fn modified() -> Option<SystemTime> {
+        let storage = Config::storage().ok()?;
+        fs::metadata(storage).and_then(|m| m.modified()).ok()
+    }
+
+    fn parse(content: &str) -> Result<Config> {
+        let config: Config = toml::from_str(content)
+            .map_err(|e| Error::new(ErrorKind::InvalidData, format!("config.toml: {}", e)))?;
+        config.theme.check()?;
+        config.columns.check()?;
+        Ok(config)
+    }
+
+    pub fn export_bundle() -> Result<String> {
+        let mut files = BTreeMap::new();
+        match fs::read_to_string(Config::storage()?) {
+            Ok(content) => {
+                files.insert(String::from(CONFIG_FILE), content);
+            }
+            Err(ref e) if e.kind() == ErrorKind::NotFound => {}
+            Err(e) => return Err(e),
+        }
+        let bundle = Bundle {
+            version: BUNDLE_VERSION,
+            files: files,
+        };
+        Ok(serde_json::to_string_pretty(&bundle)?)
+    }
+
+    pub fn import_bundle(content: &str, dry_run: bool) -> Result<Vec<String>> {
+        let bundle: Bundle = serde_json::from_str(content)?;
+        if bundle.version > BUNDLE_VERSION {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!("Unsupported bundle version: {}", bundle.version),
+            ));
+        }
+        let mut imported = vec![];
+        for (name, content) in bundle.files.iter() {
+            match name.as_str() {
+                CONFIG_FILE => {
+                    let config = Config::parse(content)?;
+                    config.views()?;
+                    config.bindings()?;
+                }
+                _ => {
+                    return Err(Error::new(
+                        ErrorKind::InvalidData,
+                        format!("Unknown file in bundle: {}", name),
+                    ))
+                }
+            }
+            imported.push(name.clone());
+        }
+        if dry_run {
+            return Ok(imported);
+        }
+        let storage = Config::storage()?;
+        if let Some(content) = bundle.files.get(CONFIG_FILE) {
+            if fs::metadata(&storage).is_ok() {
+                fs::copy(&storage, format!("{}.bak", storage))?;
+            }
+            fs::write(&storage, content)?;
+        }
+        Ok(imported)
+    }
+
+    pub fn exists() -> bool {
+        Config::storage()
+            .map(|storage| fs::metadata(storage).is_ok())
+            .unwrap_or(false)
+    }
+
+    pub fn write(content: &str) -> Result<()> {
+        let storage = Config::storage()?;
+        if let Some(parent) = Path::new(&storage).parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(storage, content)
+    }
+
+    pub fn database(&self) -> Option<String> {
+        self.storage.as_ref().map(|path| expand_home(path))
+    }
+
+    pub fn me(&self) -> Option<String> {
+        self.user.clone().or_else(|| env::var("USER").ok())
+    }
+
+    pub fn views(&self) -> Result<Vec<(String, Query)>> {
+        self.views
+            .iter()
+            .map(|(name, query)| match Query::parse(query, self) {
+                Ok(query) => Ok((name.clone(), query)),
+                Err(e) => Err(Error::new(e.kind(), format!("view {}: {}", name, e))),
+            })
+            .collect()
+    }
+
+    pub fn bindings(&self) -> Result<Vec<(char, String)>> {
+        self.bindings
+            .iter()
+            .map(|(key, command)| {
+                let mut chars = key.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(c), None) if BUILT_IN_KEYS.contains(c) => Err(Error::new(
+                        ErrorKind::InvalidData,
+                        format!("config.toml: binding {} is already a built-in key", key),
+                    )),
+                    (Some(c), None) => Ok((c, command.clone())),
+                    _ => Err(Error::new(
+                        ErrorKind::InvalidData,
+                        format!("config.toml: binding {} must be a single key", key),
+                    )),
+                }
+            })
+            .collect()
+    }
+
+    pub fn hostname(&self) -> Option<String> {
+        self.host
+            .clone()
+            .or_else(|| env::var("HOSTNAME").ok())
+            .or_else(|| fs::read_to_string("/etc/hostname").ok())
+            .map(|host| host.trim().to_string())
+            .filter(|host| !host.is_empty())
+    }
+}
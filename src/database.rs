@@ -1,15 +1,196 @@
+use crate::calendar::Day;
+use crate::diff;
+use crate::filter;
+use crate::filter::Filter;
+use crate::jobs::{Done, Jobs};
+use crate::locale::locale;
+use crate::mqtt::Mqtt;
 use crate::project::*;
-use chrono::prelude::Utc;
+use crate::query::Query;
+use crate::search::{Hit, Index};
+use crate::stats;
+use chrono::prelude::{DateTime, Local, NaiveDate, NaiveTime, Utc};
+use chrono::Duration;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt;
 use std::fs;
 use std::io::{Error, ErrorKind, Result};
 use std::slice::Iter;
+use std::sync::OnceLock;
+use std::time::Instant;
 
 pub struct Database {
     projects: Vec<Project>,
+    actor: Option<String>,
+    host: Option<String>,
+    index: Index,
+    saved: Vec<Project>,
+    history: VecDeque<Change>,
+    touched: Vec<String>,
+    mqtt: Option<Mqtt>,
+    jobs: Option<Jobs>,
+    dry_run: bool,
+    last_save: Option<std::time::Duration>,
+    wip_limit: Option<usize>,
+    rejected: Option<EventError>,
+    snapshot: Option<String>,
+}
+
+const HISTORY_LIMIT: usize = 100;
+const HISTORY_EVENT_LIMIT: usize = 200_000;
+const CLOCK_TOLERANCE_SECONDS: i64 = 60;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum EventError {
+    EmptyDescription,
+    OutOfOrder,
+    Unchanged,
+    Uncorrectable,
+    ReadOnly,
+}
+
+impl fmt::Display for EventError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let message = match self {
+            EventError::EmptyDescription => &locale().event_empty_description,
+            EventError::OutOfOrder => &locale().event_out_of_order,
+            EventError::Unchanged => &locale().event_unchanged,
+            EventError::Uncorrectable => &locale().event_uncorrectable,
+            EventError::ReadOnly => &locale().event_read_only,
+        };
+        write!(f, "{}", message)
+    }
+}
+
+impl std::error::Error for EventError {}
+
+impl From<EventError> for Error {
+    fn from(error: EventError) -> Error {
+        Error::new(ErrorKind::InvalidInput, error)
+    }
+}
+
+fn unique_key(projects: &[Project], key: &str) -> String {
+    let taken = |candidate: &str| projects.iter().any(|p| p.key == candidate);
+    if !taken(key) {
+        return key.to_string();
+    }
+    (2..)
+        .map(|n| format!("{}{}", key, n))
+        .find(|candidate| !taken(candidate))
+        .unwrap()
+}
+
+fn correctable(task: &Task, index: usize) -> bool {
+    match task.events.get(index) {
+        None | Some(Event::Retract { .. }) | Some(Event::Amend { .. }) => false,
+        Some(_) => task.corrections().get(&index).map_or(true, Option::is_some),
+    }
+}
+
+fn validate(task: &Task, event: &Event) -> std::result::Result<(), EventError> {
+    let now = Utc::now();
+    let tolerance = Duration::seconds(CLOCK_TOLERANCE_SECONDS);
+    let last = task.events.iter().map(Event::date_time).max();
+    if event.date_time() > now + tolerance
+        || last.map_or(false, |last| event.date_time() < last.min(now) - tolerance)
+    {
+        return Err(EventError::OutOfOrder);
+    }
+    validate_content(task, event)
+}
+
+fn validate_content(task: &Task, event: &Event) -> std::result::Result<(), EventError> {
+    match event {
+        Event::Description { data, .. } if data.trim().is_empty() => {
+            Err(EventError::EmptyDescription)
+        }
+        Event::State { data, .. } if *data == task.state() => Err(EventError::Unchanged),
+        Event::Retract { data, .. } if !correctable(task, *data) => Err(EventError::Uncorrectable),
+        Event::Amend { data, .. } if !correctable(task, data.index) => {
+            Err(EventError::Uncorrectable)
+        }
+        _ => Ok(()),
+    }
+}
+
+fn validate_imported(task: &Task) -> std::result::Result<(), EventError> {
+    let latest = Utc::now() + Duration::seconds(CLOCK_TOLERANCE_SECONDS);
+    let mut replay = task.clone();
+    replay.events.clear();
+    for event in task.events.iter() {
+        if event.date_time() > latest {
+            return Err(EventError::OutOfOrder);
+        }
+        match validate_content(&replay, event) {
+            Ok(()) | Err(EventError::Unchanged) => replay.events.push(event.clone()),
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(())
+}
+
+struct Change {
+    count: usize,
+    projects: Vec<(usize, Project)>,
+}
+
+impl Change {
+    fn restore(&self, projects: &mut Vec<Project>) {
+        projects.truncate(self.count);
+        for (i, project) in self.projects.iter() {
+            match projects.get_mut(*i) {
+                Some(current) => *current = project.clone(),
+                None => projects.push(project.clone()),
+            }
+        }
+    }
+
+    fn events(&self) -> usize {
+        self.projects
+            .iter()
+            .flat_map(|(_, project)| project.tasks.iter().chain(project.archive.iter()))
+            .map(|task| task.events.len())
+            .sum()
+    }
+}
+
+#[derive(Default)]
+pub struct ImportSummary {
+    pub added: Vec<String>,
+    pub merged: Vec<String>,
+    pub skipped: Vec<String>,
+}
+
+fn event_key(event: &Event) -> (String, String, DateTime<Utc>) {
+    let value = serde_json::to_value(event).unwrap_or_default();
+    (
+        value["type"].to_string(),
+        value["data"].to_string(),
+        event.date_time(),
+    )
+}
+
+pub struct Activity {
+    pub project: usize,
+    pub task: usize,
+    pub date_time: DateTime<Utc>,
+    pub event: Option<Event>,
+}
+
+static STORAGE: OnceLock<String> = OnceLock::new();
+
+pub fn init(storage: Option<String>) {
+    if let Some(storage) = storage {
+        STORAGE.set(storage).ok();
+    }
 }
 
 impl Database {
-    fn storage() -> Result<String> {
+    pub fn storage() -> Result<String> {
+        if let Some(storage) = STORAGE.get() {
+            return Ok(storage.clone());
+        }
         match dirs::home_dir() {
             Some(path) => Ok(format!(
                 "{}{}",
@@ -23,15 +204,350 @@ impl Database {
         }
     }
 
-    pub fn load() -> Result<Database> {
+    pub fn diff(&self, snapshot: &str) -> Result<Vec<diff::Entry>> {
+        let json_data = fs::read_to_string(snapshot)?;
+        let before: Vec<Project> = serde_json::from_str(json_data.as_str())?;
+        Ok(diff::compare(&before, &self.projects))
+    }
+
+    pub fn open(dry_run: bool) -> Result<Database> {
+        let json_data = fs::read_to_string(Database::storage()?)?;
+        let projects: Vec<Project> = serde_json::from_str(json_data.as_str())?;
+        Database::with_projects(projects, dry_run)
+    }
+
+    pub fn read_only() -> Result<Database> {
         let json_data = fs::read_to_string(Database::storage()?)?;
-        let projects = serde_json::from_str(json_data.as_str())?;
-        Ok(Database { projects: projects })
+        let projects: Vec<Project> = serde_json::from_str(json_data.as_str())?;
+        Ok(Database::build(projects, true))
+    }
+
+    pub fn in_memory(projects: Vec<Project>) -> Result<Database> {
+        Database::with_projects(projects, true)
+    }
+
+    pub fn snapshot(projects: Vec<Project>, label: String) -> Database {
+        let mut database = Database::build(projects, true);
+        database.snapshot = Some(label);
+        database
+    }
+
+    fn with_projects(projects: Vec<Project>, dry_run: bool) -> Result<Database> {
+        let mut database = Database::build(projects, dry_run);
+        database.rollover_sprints()?;
+        database.reset_daily()?;
+        Ok(database)
+    }
+
+    fn build(mut projects: Vec<Project>, dry_run: bool) -> Database {
+        for i in 0..projects.len() {
+            if projects[i].key.is_empty() {
+                projects[i].key = unique_key(&projects, &projects[i].key());
+            }
+            projects[i].number_tasks();
+        }
+        Database {
+            index: Index::build(&projects),
+            saved: projects.clone(),
+            history: VecDeque::new(),
+            touched: vec![],
+            mqtt: None,
+            jobs: None,
+            dry_run: dry_run,
+            last_save: None,
+            wip_limit: None,
+            rejected: None,
+            snapshot: None,
+            projects: projects,
+            actor: None,
+            host: None,
+        }
+    }
+
+    fn rollover_sprints(&mut self) -> Result<()> {
+        let mut changed = false;
+        for project in self.projects.iter_mut() {
+            changed |= project.rollover_sprints();
+        }
+        if changed {
+            self.save()?;
+            self.history.clear();
+        }
+        Ok(())
+    }
+
+    pub fn reset_daily(&mut self) -> Result<bool> {
+        if self.snapshot.is_some() {
+            return Ok(false);
+        }
+        let now = Local::now();
+        let touched = self.touched.len();
+        let mut changed = false;
+        for project in 0..self.projects.len() {
+            if !self.projects[project].start_day(now) {
+                continue;
+            }
+            changed = true;
+            for task in 0..self.projects[project].tasks.len() {
+                if self.projects[project].tasks[task].state() != State::TODO {
+                    self.push_event(
+                        project,
+                        task,
+                        Event::State {
+                            data: State::TODO,
+                            date_time: Utc::now(),
+                            actor: None,
+                            host: None,
+                        },
+                    )
+                    .ok();
+                }
+            }
+            self.projects[project].sort_tasks();
+        }
+        self.touched.truncate(touched);
+        if changed {
+            self.save()?;
+            self.history.clear();
+        }
+        Ok(changed)
+    }
+
+    pub fn set_project_daily(&mut self, project: usize, reset_at: Option<NaiveTime>) -> Result<()> {
+        self.projects[project].daily = reset_at.map(Daily::new);
+        self.save()
+    }
+
+    pub fn archive_done(&mut self, days: u32) -> Result<Vec<(usize, String)>> {
+        let cutoff = Utc::now() - Duration::days(days as i64);
+        let mut archived = vec![];
+        for (p, project) in self.projects.iter_mut().enumerate() {
+            let before = project.archive.len();
+            project.archive_done(cutoff);
+            archived.extend(
+                project.archive[before..]
+                    .iter()
+                    .map(|task| (p, task.description())),
+            );
+        }
+        if !archived.is_empty() {
+            self.save()?;
+        }
+        Ok(archived)
+    }
+
+    pub fn snapshot_label(&self) -> Option<&str> {
+        self.snapshot.as_deref()
+    }
+
+    pub fn dry_run(&self) -> bool {
+        self.dry_run
+    }
+
+    pub fn set_origin(&mut self, actor: Option<String>, host: Option<String>) {
+        self.actor = actor;
+        self.host = host;
+    }
+
+    pub fn set_mqtt(&mut self, mqtt: Option<Mqtt>) {
+        self.mqtt = mqtt;
+    }
+
+    pub fn start_jobs(&mut self) {
+        self.jobs = Some(Jobs::start());
+    }
+
+    pub fn take_finished(&mut self) -> Vec<Done> {
+        match &mut self.jobs {
+            Some(jobs) => jobs.finished(),
+            None => vec![],
+        }
+    }
+
+    pub fn jobs_running(&self) -> bool {
+        self.jobs.as_ref().map_or(false, |jobs| jobs.running() > 0)
+    }
+
+    pub fn spinner(&mut self) -> Option<&'static str> {
+        self.jobs.as_mut().and_then(|jobs| jobs.spinner())
+    }
+
+    pub fn set_wip_limit(&mut self, limit: Option<usize>) {
+        self.wip_limit = limit.filter(|limit| *limit > 0);
+    }
+
+    fn enforce_wip_limit(&mut self, project: usize, task: usize) {
+        let limit = match self.wip_limit {
+            Some(limit) => limit,
+            None => return,
+        };
+        let started = &self.projects[project].tasks[task].id;
+        let mut ongoing: Vec<(usize, usize, DateTime<Utc>)> = vec![];
+        for (p, other) in self.projects.iter().enumerate() {
+            for (t, task) in other.tasks.iter().enumerate() {
+                if task.state() == State::ONGOING && task.id != *started {
+                    let since = task.state_changes().last().map(|(_, since)| *since);
+                    ongoing.push((p, t, since.unwrap_or(task.created_at)));
+                }
+            }
+        }
+        ongoing.sort_by_key(|(_, _, since)| *since);
+
+        let excess = (ongoing.len() + 1).saturating_sub(limit);
+        for (p, t, _) in ongoing.into_iter().take(excess) {
+            self.push_event(
+                p,
+                t,
+                Event::State {
+                    data: State::TODO,
+                    date_time: Utc::now(),
+                    actor: None,
+                    host: None,
+                },
+            )
+            .ok();
+        }
+    }
+
+    fn push_event(
+        &mut self,
+        project: usize,
+        task: usize,
+        mut event: Event,
+    ) -> std::result::Result<(), EventError> {
+        if let Err(e) = validate(&self.projects[project].tasks[task], &event) {
+            tracing::warn!(error = %e, event = ?event, "rejected event");
+            self.rejected = Some(e);
+            return Err(e);
+        }
+        event.set_origin(self.actor.clone(), self.host.clone());
+        tracing::info!(task = %self.projects[project].tasks[task].id, event = ?event, "event");
+        let state_change = match event {
+            Event::State { .. } => true,
+            _ => false,
+        };
+        self.projects[project].tasks[task].events.push(event);
+        self.touched
+            .push(self.projects[project].tasks[task].id.clone());
+        if state_change {
+            self.publish_state(project, task);
+        }
+        Ok(())
+    }
+
+    fn publish_state(&mut self, project: usize, task: usize) {
+        if let Some(mqtt) = self.mqtt.clone() {
+            let project = &self.projects[project];
+            let task = &project.tasks[task];
+            let payload = serde_json::json!({
+                "project_id": project.id,
+                "project": project.title(),
+                "task_id": task.id,
+                "task": task.description(),
+                "state": task.state(),
+                "project_done": project.tasks.iter().all(|t| t.state() == State::DONE),
+            });
+            let payload = payload.to_string();
+            match &mut self.jobs {
+                Some(jobs) => jobs.spawn("publish", move || mqtt.publish(&payload)),
+                None => {
+                    if let Err(e) = mqtt.publish(&payload) {
+                        tracing::warn!(error = %e, "MQTT publish failed");
+                    }
+                }
+            }
+        }
+    }
+
+    pub fn take_rejected(&mut self) -> Option<EventError> {
+        self.rejected.take()
+    }
+
+    pub fn take_touched(&mut self) -> Vec<String> {
+        std::mem::replace(&mut self.touched, vec![])
     }
 
     pub fn save(&mut self) -> Result<()> {
+        if self.snapshot.is_some() {
+            self.projects = self.saved.clone();
+            self.touched.clear();
+            self.rejected = Some(EventError::ReadOnly);
+            return Ok(());
+        }
+        let count = self.saved.len();
+        let mut change = Change {
+            count: count,
+            projects: vec![],
+        };
+        for i in 0..count.max(self.projects.len()) {
+            match (self.saved.get(i), self.projects.get(i)) {
+                (Some(saved), Some(project)) if saved == project => {}
+                (Some(_), Some(project)) => {
+                    let previous = std::mem::replace(&mut self.saved[i], project.clone());
+                    change.projects.push((i, previous));
+                }
+                (Some(saved), None) => change.projects.push((i, saved.clone())),
+                (None, Some(project)) => self.saved.push(project.clone()),
+                (None, None) => {}
+            }
+        }
+        self.saved.truncate(self.projects.len());
+        if count != self.projects.len() || !change.projects.is_empty() {
+            self.history.push_back(change);
+        }
+        let mut events: usize = self.history.iter().map(Change::events).sum();
+        while self.history.len() > HISTORY_LIMIT
+            || (events > HISTORY_EVENT_LIMIT && self.history.len() > 1)
+        {
+            if let Some(dropped) = self.history.pop_front() {
+                events -= dropped.events();
+            }
+        }
+        self.write()
+    }
+
+    pub fn undo(&mut self) -> Result<bool> {
+        match self.history.pop_back() {
+            Some(change) => {
+                change.restore(&mut self.projects);
+                change.restore(&mut self.saved);
+                self.write()?;
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    fn write(&mut self) -> Result<()> {
+        let started = Instant::now();
+        self.index = Index::build(&self.projects);
+        if self.dry_run {
+            return Ok(());
+        }
         let content = serde_json::to_string(&self.projects)?;
-        fs::write(Database::storage()?, content)
+        let storage = Database::storage()?;
+        if let Some(jobs) = &mut self.jobs {
+            jobs.spawn("save", move || {
+                let started = Instant::now();
+                fs::write(storage, content)?;
+                tracing::info!(elapsed_us = started.elapsed().as_micros() as u64, "saved");
+                Ok(())
+            });
+            self.last_save = Some(started.elapsed());
+            return Ok(());
+        }
+        let result = fs::write(storage, content);
+        let elapsed = started.elapsed();
+        match &result {
+            Ok(()) => tracing::info!(elapsed_us = elapsed.as_micros() as u64, "saved"),
+            Err(e) => tracing::error!(error = %e, "save failed"),
+        }
+        self.last_save = Some(elapsed);
+        result
+    }
+
+    pub fn last_save(&self) -> Option<std::time::Duration> {
+        self.last_save
     }
 
     pub fn set_task_state(
@@ -40,33 +556,762 @@ impl Database {
         task: usize,
         state: State,
     ) -> Result<Option<usize>> {
-        if state != self.projects[project].tasks[task].state() {
-            self.projects[project].tasks[task]
-                .events
-                .push(Event::State {
+        if state == self.projects[project].tasks[task].state() {
+            return Ok(None);
+        }
+        let pushed = self.push_event(
+            project,
+            task,
+            Event::State {
+                data: state,
+                date_time: Utc::now(),
+                actor: None,
+                host: None,
+            },
+        );
+        if pushed.is_err() {
+            return Ok(None);
+        }
+        if state == State::ONGOING {
+            self.enforce_wip_limit(project, task);
+        }
+        let task_id = self.projects[project].tasks[task].id.clone();
+        for project in self.projects.iter_mut() {
+            project.sort_tasks();
+        }
+        self.save()?;
+        Ok(self.projects[project].task_position(task_id))
+    }
+
+    pub fn pause_ongoing(&mut self, since: DateTime<Utc>) -> Result<Vec<(String, usize)>> {
+        let mut paused = vec![];
+        for p in 0..self.projects.len() {
+            for t in 0..self.projects[p].tasks.len() {
+                let task = &self.projects[p].tasks[t];
+                if task.state() != State::ONGOING {
+                    continue;
+                }
+                let last = task.events.iter().map(Event::date_time).max();
+                let count = task.events.len();
+                let pushed = self.push_event(
+                    p,
+                    t,
+                    Event::State {
+                        data: State::TODO,
+                        date_time: last.map_or(since, |last| last.max(since)),
+                        actor: None,
+                        host: None,
+                    },
+                );
+                if pushed.is_ok() {
+                    paused.push((self.projects[p].tasks[t].id.clone(), count));
+                }
+            }
+        }
+        if !paused.is_empty() {
+            for project in self.projects.iter_mut() {
+                project.sort_tasks();
+            }
+            self.save()?;
+        }
+        Ok(paused)
+    }
+
+    pub fn resume_paused(&mut self, paused: &[(String, usize)], keep: bool) -> Result<()> {
+        let mut resumed = 0;
+        for (task_id, index) in paused {
+            let (project, task) = match self.find_task(task_id) {
+                Some(found) => found,
+                None => continue,
+            };
+            if self.projects[project].tasks[task].state() != State::TODO {
+                continue;
+            }
+            let event = match keep {
+                true => Event::Retract {
+                    data: *index,
+                    date_time: Utc::now(),
+                    actor: None,
+                    host: None,
+                },
+                false => Event::State {
+                    data: State::ONGOING,
+                    date_time: Utc::now(),
+                    actor: None,
+                    host: None,
+                },
+            };
+            if self.push_event(project, task, event).is_ok() {
+                resumed += 1;
+            }
+        }
+        if resumed == 0 {
+            return Ok(());
+        }
+        for project in self.projects.iter_mut() {
+            project.sort_tasks();
+        }
+        self.save()
+    }
+
+    pub fn set_task_waiting(
+        &mut self,
+        project: usize,
+        task: usize,
+        waiting_on: String,
+    ) -> Result<Option<usize>> {
+        let mut accepted = self
+            .push_event(
+                project,
+                task,
+                Event::Waiting {
+                    data: waiting_on,
+                    date_time: Utc::now(),
+                    actor: None,
+                    host: None,
+                },
+            )
+            .is_ok();
+        if self.projects[project].tasks[task].state() != State::WAITING {
+            accepted |= self
+                .push_event(
+                    project,
+                    task,
+                    Event::State {
+                        data: State::WAITING,
+                        date_time: Utc::now(),
+                        actor: None,
+                        host: None,
+                    },
+                )
+                .is_ok();
+        }
+        if !accepted {
+            return Ok(None);
+        }
+        let task_id = self.projects[project].tasks[task].id.clone();
+        self.projects[project].sort_tasks();
+        self.save()?;
+        Ok(self.projects[project].task_position(task_id))
+    }
+
+    pub fn waiting_tasks(&self) -> Vec<(usize, usize)> {
+        let mut waiting: Vec<(usize, usize, DateTime<Utc>)> = self
+            .projects
+            .iter()
+            .enumerate()
+            .flat_map(|(p, project)| {
+                project
+                    .tasks
+                    .iter()
+                    .enumerate()
+                    .filter_map(move |(t, task)| task.waiting_on().map(|(_, since)| (p, t, since)))
+            })
+            .collect();
+        waiting.sort_by_key(|(_, _, since)| *since);
+        waiting.into_iter().map(|(p, t, _)| (p, t)).collect()
+    }
+
+    pub fn set_all_task_states(&mut self, project: usize, state: State) -> Result<usize> {
+        let stale: Vec<usize> = (0..self.projects[project].tasks.len())
+            .filter(|task| self.projects[project].tasks[*task].state() != state)
+            .collect();
+        let mut count = 0;
+        for task in stale.iter() {
+            let pushed = self.push_event(
+                project,
+                *task,
+                Event::State {
                     data: state,
                     date_time: Utc::now(),
-                });
-            let task_id = self.projects[project].tasks[task].id.clone();
+                    actor: None,
+                    host: None,
+                },
+            );
+            if pushed.is_ok() {
+                count += 1;
+            }
+        }
+        if count > 0 {
             self.projects[project].sort_tasks();
             self.save()?;
-            Ok(self.projects[project].task_position(task_id))
-        } else {
-            Ok(None)
         }
+        Ok(count)
+    }
+
+    pub fn set_task_points(&mut self, project: usize, task: usize, points: u32) -> Result<()> {
+        self.push_event(
+            project,
+            task,
+            Event::Points {
+                data: points,
+                date_time: Utc::now(),
+                actor: None,
+                host: None,
+            },
+        )
+        .map_or(Ok(()), |()| self.save())
+    }
+
+    pub fn set_task_estimate(&mut self, project: usize, task: usize, minutes: u32) -> Result<()> {
+        self.push_event(
+            project,
+            task,
+            Event::Estimate {
+                data: minutes,
+                date_time: Utc::now(),
+                actor: None,
+                host: None,
+            },
+        )
+        .map_or(Ok(()), |()| self.save())
+    }
+
+    pub fn set_task_due(
+        &mut self,
+        project: usize,
+        task: usize,
+        due: Option<NaiveDate>,
+    ) -> Result<()> {
+        self.push_event(
+            project,
+            task,
+            Event::Due {
+                data: due,
+                date_time: Utc::now(),
+                actor: None,
+                host: None,
+            },
+        )
+        .map_or(Ok(()), |()| self.save())
+    }
+
+    pub fn set_task_start(
+        &mut self,
+        project: usize,
+        task: usize,
+        start: Option<NaiveDate>,
+    ) -> Result<()> {
+        self.push_event(
+            project,
+            task,
+            Event::Start {
+                data: start,
+                date_time: Utc::now(),
+                actor: None,
+                host: None,
+            },
+        )
+        .map_or(Ok(()), |()| self.save())
+    }
+
+    pub fn set_project_rate(&mut self, project: usize, rate: f64) -> Result<()> {
+        self.projects[project].rate = Some(rate);
+        self.save()
+    }
+
+    pub fn add_sprint(&mut self, project: usize, sprint: Sprint) -> Result<()> {
+        self.projects[project]
+            .sprints
+            .retain(|s| s.name != sprint.name);
+        self.projects[project].sprints.push(sprint);
+        self.projects[project].sprints.sort_by_key(|s| s.start);
+        self.save()
+    }
+
+    pub fn set_task_sprint(
+        &mut self,
+        project: usize,
+        task: usize,
+        sprint: Option<String>,
+    ) -> Result<()> {
+        self.push_event(
+            project,
+            task,
+            Event::Sprint {
+                data: sprint,
+                date_time: Utc::now(),
+                actor: None,
+                host: None,
+            },
+        )
+        .map_or(Ok(()), |()| self.save())
+    }
+
+    pub fn add_milestone(&mut self, project: usize, milestone: Milestone) -> Result<()> {
+        self.projects[project]
+            .milestones
+            .retain(|m| m.name != milestone.name);
+        self.projects[project].milestones.push(milestone);
+        self.projects[project].milestones.sort_by_key(|m| m.target);
+        self.save()
+    }
+
+    pub fn set_task_milestone(
+        &mut self,
+        project: usize,
+        task: usize,
+        milestone: Option<String>,
+    ) -> Result<()> {
+        self.push_event(
+            project,
+            task,
+            Event::Milestone {
+                data: milestone,
+                date_time: Utc::now(),
+                actor: None,
+                host: None,
+            },
+        )
+        .map_or(Ok(()), |()| self.save())
+    }
+
+    pub fn set_task_assignee(
+        &mut self,
+        project: usize,
+        task: usize,
+        assignee: Option<String>,
+    ) -> Result<()> {
+        self.push_event(
+            project,
+            task,
+            Event::Assignee {
+                data: assignee,
+                date_time: Utc::now(),
+                actor: None,
+                host: None,
+            },
+        )
+        .map_or(Ok(()), |()| self.save())
+    }
+
+    pub fn add_task_attachment(
+        &mut self,
+        project: usize,
+        task: usize,
+        attachment: String,
+    ) -> Result<()> {
+        self.push_event(
+            project,
+            task,
+            Event::Attachment {
+                data: attachment,
+                date_time: Utc::now(),
+                actor: None,
+                host: None,
+            },
+        )
+        .map_or(Ok(()), |()| self.save())
+    }
+
+    pub fn set_task_branch(&mut self, project: usize, task: usize, branch: String) -> Result<()> {
+        self.push_event(
+            project,
+            task,
+            Event::Branch {
+                data: branch,
+                date_time: Utc::now(),
+                actor: None,
+                host: None,
+            },
+        )
+        .map_or(Ok(()), |()| self.save())
+    }
+
+    pub fn set_task_progress(&mut self, project: usize, task: usize, progress: u8) -> Result<()> {
+        self.push_event(
+            project,
+            task,
+            Event::Progress {
+                data: progress.min(100),
+                date_time: Utc::now(),
+                actor: None,
+                host: None,
+            },
+        )
+        .map_or(Ok(()), |()| self.save())
+    }
+
+    pub fn set_task_checklist(
+        &mut self,
+        project: usize,
+        task: usize,
+        checklist: Vec<ChecklistItem>,
+    ) -> Result<()> {
+        self.push_event(
+            project,
+            task,
+            Event::Checklist {
+                data: checklist,
+                date_time: Utc::now(),
+                actor: None,
+                host: None,
+            },
+        )
+        .map_or(Ok(()), |()| self.save())
+    }
+
+    pub fn set_task_someday(&mut self, project: usize, task: usize, someday: bool) -> Result<()> {
+        self.push_event(
+            project,
+            task,
+            Event::Someday {
+                data: someday,
+                date_time: Utc::now(),
+                actor: None,
+                host: None,
+            },
+        )
+        .map_or(Ok(()), |()| self.save())
+    }
+
+    pub fn set_task_notes(&mut self, project: usize, task: usize, notes: String) -> Result<()> {
+        self.push_event(
+            project,
+            task,
+            Event::Notes {
+                data: notes,
+                date_time: Utc::now(),
+                actor: None,
+                host: None,
+            },
+        )
+        .map_or(Ok(()), |()| self.save())
     }
 
-    pub fn add_project(&mut self, project: Project) -> Result<()> {
+    pub fn retract_task_event(
+        &mut self,
+        project: usize,
+        task: usize,
+        index: usize,
+    ) -> Result<Option<usize>> {
+        self.push_event(
+            project,
+            task,
+            Event::Retract {
+                data: index,
+                date_time: Utc::now(),
+                actor: None,
+                host: None,
+            },
+        )
+        .map_or(Ok(None), |()| self.resort_task(project, task))
+    }
+
+    pub fn amend_task_event(
+        &mut self,
+        project: usize,
+        task: usize,
+        index: usize,
+        replacement: Event,
+    ) -> Result<Option<usize>> {
+        self.push_event(
+            project,
+            task,
+            Event::Amend {
+                data: Amendment {
+                    index: index,
+                    event: Box::new(replacement),
+                },
+                date_time: Utc::now(),
+                actor: None,
+                host: None,
+            },
+        )
+        .map_or(Ok(None), |()| self.resort_task(project, task))
+    }
+
+    fn resort_task(&mut self, project: usize, task: usize) -> Result<Option<usize>> {
+        let task_id = self.projects[project].tasks[task].id.clone();
+        self.projects[project].sort_tasks();
+        self.save()?;
+        Ok(self.projects[project].task_position(task_id))
+    }
+
+    pub fn add_project(&mut self, mut project: Project) -> Result<()> {
+        project.key = unique_key(&self.projects, &project.key());
+        project.number_tasks();
         self.projects.push(project);
         self.save()
     }
 
     pub fn add_task(&mut self, project: usize, task: Task) -> Result<Option<usize>> {
         let task_id = task.id.clone();
-        self.projects[project].tasks.push(task);
+        self.add_tasks(project, vec![task])?;
+        Ok(self.projects[project].task_position(task_id))
+    }
+
+    pub fn add_tasks(&mut self, project: usize, tasks: Vec<Task>) -> Result<()> {
+        for mut task in tasks {
+            if task.description().trim().is_empty() {
+                self.rejected = Some(EventError::EmptyDescription);
+                continue;
+            }
+            task.set_origin(self.actor.clone(), self.host.clone());
+            self.projects[project].tasks.push(task);
+        }
+        self.projects[project].sort_tasks();
+        self.save()
+    }
+
+    pub fn split_task(
+        &mut self,
+        project: usize,
+        task: usize,
+        descriptions: &[String],
+        link: bool,
+    ) -> Result<usize> {
+        let origin = self.reference(project, task);
+        let origin = match link {
+            true => Some(origin.as_str()),
+            false => None,
+        };
+        let original = &self.projects[project].tasks[task];
+        let (first, rest) = match descriptions.split_first() {
+            Some(split) => split,
+            None => return Ok(0),
+        };
+        let first = original.with_contexts(first);
+        let mut parts: Vec<Task> = rest
+            .iter()
+            .map(|description| original.split_off(description, origin))
+            .collect();
+        if original.description() != first {
+            self.push_event(
+                project,
+                task,
+                Event::Description {
+                    data: first,
+                    date_time: Utc::now(),
+                    actor: None,
+                    host: None,
+                },
+            )
+            .ok();
+        }
+
+        self.projects[project].rank_tasks();
+        let rank = self.projects[project].tasks[task].rank.unwrap_or_default();
+        for other in self.projects[project].tasks.iter_mut() {
+            if other.rank.map_or(false, |other| other > rank) {
+                other.rank = other.rank.map(|other| other + parts.len());
+            }
+        }
+        for (i, part) in parts.iter_mut().enumerate() {
+            part.rank = Some(rank + 1 + i);
+            part.set_origin(self.actor.clone(), self.host.clone());
+        }
+        let count = descriptions.len();
+        self.projects[project].tasks.extend(parts);
         self.projects[project].sort_tasks();
         self.save()?;
-        Ok(self.projects[project].task_position(task_id))
+        Ok(count)
+    }
+
+    pub fn merge_tasks(
+        &mut self,
+        project: usize,
+        task: usize,
+        duplicates: &[String],
+    ) -> Result<Option<usize>> {
+        let target = self.projects[project].tasks[task].id.clone();
+        let original = self.projects[project].tasks[task].clone();
+        let mut descriptions = vec![original.description()];
+        let mut notes = vec![original.notes()];
+        for id in duplicates.iter().filter(|id| **id != target) {
+            if let Some((p, t)) = self.find_task(id) {
+                let origin = self.reference(p, t);
+                let duplicate = self.projects[p].tasks.remove(t);
+                descriptions.push(duplicate.description());
+                notes.push(duplicate.notes());
+                let (p, t) = self.find_task(&target).unwrap();
+                self.projects[p].tasks[t].absorb(&duplicate, &origin);
+            }
+        }
+        let (project, task) = self.find_task(&target).unwrap();
+        let now = Utc::now();
+        let merged = self.projects[project].tasks[task].clone();
+        if merged.description() != descriptions.join(" / ") {
+            self.push_event(
+                project,
+                task,
+                Event::Description {
+                    data: descriptions.join(" / "),
+                    date_time: now,
+                    actor: None,
+                    host: None,
+                },
+            )
+            .ok();
+        }
+        notes.retain(|notes| !notes.trim().is_empty());
+        if merged.notes() != notes.join("\n\n") {
+            self.push_event(
+                project,
+                task,
+                Event::Notes {
+                    data: notes.join("\n\n"),
+                    date_time: now,
+                    actor: None,
+                    host: None,
+                },
+            )
+            .ok();
+        }
+        self.resort_task(project, task)
+    }
+
+    pub fn import_tasks(&mut self, project: usize, tasks: Vec<Task>) -> Result<ImportSummary> {
+        let mut summary = ImportSummary::default();
+        let mut added: Vec<Task> = vec![];
+
+        for mut task in tasks {
+            let description = task.description();
+            if let Err(e) = validate_imported(&task) {
+                tracing::warn!(error = %e, task = %task.id, "rejected import");
+                self.rejected = Some(e);
+                summary.skipped.push(description);
+                continue;
+            }
+            if let Some((p, t)) = self.find_task(&task.id) {
+                let known: HashSet<(String, String, DateTime<Utc>)> = self.projects[p].tasks[t]
+                    .history()
+                    .iter()
+                    .map(event_key)
+                    .collect();
+                let new: Vec<Event> = task
+                    .history()
+                    .iter()
+                    .filter(|event| !known.contains(&event_key(event)))
+                    .cloned()
+                    .collect();
+                if new.is_empty() {
+                    summary.skipped.push(description);
+                    continue;
+                }
+                let existing = &mut self.projects[p].tasks[t];
+                existing.events.extend(new);
+                existing.sort_events();
+                self.touched.push(existing.id.clone());
+                self.projects[p].sort_tasks();
+                summary.merged.push(description);
+            } else if self.projects[project]
+                .tasks
+                .iter()
+                .chain(self.projects[project].archive.iter())
+                .chain(added.iter())
+                .any(|other| other.id == task.id || other.description() == description)
+            {
+                summary.skipped.push(description);
+            } else {
+                task.set_origin(self.actor.clone(), self.host.clone());
+                task.rank = None;
+                task.number = None;
+                summary.added.push(description);
+                added.push(task);
+            }
+        }
+
+        if summary.added.is_empty() && summary.merged.is_empty() {
+            return Ok(summary);
+        }
+        self.projects[project].tasks.extend(added);
+        self.projects[project].sort_tasks();
+        self.save()?;
+        Ok(summary)
+    }
+
+    pub fn export_project(&self, project: usize) -> Result<String> {
+        Ok(serde_json::to_string_pretty(&self.projects[project])?)
+    }
+
+    pub fn merge_project(&mut self, path: &str) -> Result<(String, ImportSummary)> {
+        let json_data = fs::read_to_string(path)?;
+        let mut project: Project = serde_json::from_str(json_data.as_str())?;
+        let description = project.title().to_string();
+        match self.projects.iter().position(|p| p.id == project.id) {
+            Some(existing) => {
+                let tasks = project.tasks.drain(..).collect();
+                Ok((description, self.import_tasks(existing, tasks)?))
+            }
+            None => {
+                let summary = ImportSummary {
+                    added: project.tasks.iter().map(Task::description).collect(),
+                    ..ImportSummary::default()
+                };
+                self.add_project(project)?;
+                Ok((description, summary))
+            }
+        }
+    }
+
+    pub fn set_project_description(&mut self, project: usize, description: String) -> Result<()> {
+        self.projects[project].description = description;
+        self.save()
+    }
+
+    pub fn rename_tasks(&mut self, renames: Vec<(usize, usize, String)>) -> Result<usize> {
+        let mut count = 0;
+        for (project, task, description) in renames {
+            let pushed = self.push_event(
+                project,
+                task,
+                Event::Description {
+                    data: description,
+                    date_time: Utc::now(),
+                    actor: None,
+                    host: None,
+                },
+            );
+            if pushed.is_ok() {
+                count += 1;
+            }
+        }
+        if count > 0 {
+            self.save()?;
+        }
+        Ok(count)
+    }
+
+    pub fn transfer_tasks(&mut self, from: usize, to: usize, state: State) -> Result<usize> {
+        if from == to {
+            return Ok(0);
+        }
+        self.projects[from].rank_tasks();
+        let now = Utc::now();
+        for task in 0..self.projects[from].tasks.len() {
+            if self.projects[from].tasks[task].state() == state {
+                let origin = self.reference(from, task);
+                self.push_event(
+                    from,
+                    task,
+                    Event::Comment {
+                        data: format!("Moved from {}", origin),
+                        date_time: now,
+                        actor: None,
+                        host: None,
+                    },
+                )
+                .ok();
+            }
+        }
+        let (mut moved, kept): (Vec<Task>, Vec<Task>) = self.projects[from]
+            .tasks
+            .drain(..)
+            .partition(|task| task.state() == state);
+        self.projects[from].tasks = kept;
+        moved.sort_by_key(|task| task.rank);
+        for task in moved.iter_mut() {
+            task.rank = None;
+            task.number = None;
+        }
+        let count = moved.len();
+        self.projects[to].tasks.extend(moved);
+        self.projects[to].sort_tasks();
+        if count > 0 {
+            self.save()?;
+        }
+        Ok(count)
     }
 
     pub fn remove_project(&mut self, project: usize) -> Result<()> {
@@ -84,29 +1329,335 @@ impl Database {
         self.save()
     }
 
+    pub fn move_project(&mut self, from: usize, to: usize) {
+        let project = self.projects.remove(from);
+        self.projects.insert(to, project);
+    }
+
+    pub fn sort_projects(&mut self, order: ProjectOrder) -> Result<()> {
+        match order {
+            ProjectOrder::Alphabetical => {
+                self.projects.sort_by_key(|p| p.description.to_lowercase())
+            }
+            ProjectOrder::OpenTasks => self
+                .projects
+                .sort_by_key(|p| std::cmp::Reverse(p.open_task_count())),
+            ProjectOrder::Activity => self
+                .projects
+                .sort_by_key(|p| std::cmp::Reverse(p.last_activity())),
+        }
+        self.save()
+    }
+
     pub fn swap_tasks(&mut self, project: usize, first: usize, second: usize) -> Result<()> {
-        self.projects[project].tasks.swap(first, second);
+        self.projects[project].rank_tasks();
+        let tasks = &mut self.projects[project].tasks;
+        let rank = tasks[first].rank;
+        tasks[first].rank = tasks[second].rank;
+        tasks[second].rank = rank;
         self.projects[project].sort_tasks();
         self.save()
     }
 
+    pub fn move_task(
+        &mut self,
+        project: usize,
+        task: usize,
+        target: usize,
+    ) -> Result<Option<usize>> {
+        self.projects[project].rank_tasks();
+        let tasks = &mut self.projects[project].tasks;
+        let mut order: Vec<usize> = (0..tasks.len()).collect();
+        order.sort_by_key(|i| tasks[*i].rank);
+        let from = order.iter().position(|i| *i == task).unwrap();
+        let to = order.iter().position(|i| *i == target).unwrap();
+        let moved = order.remove(from);
+        order.insert(to, moved);
+        for (rank, i) in order.iter().enumerate() {
+            tasks[*i].rank = Some(rank);
+        }
+        self.resort_task(project, task)
+    }
+
     pub fn projects(&self) -> Iter<Project> {
         self.projects.iter()
     }
 
-    pub fn tasks(&self, project: usize) -> Iter<Task> {
-        self.projects[project].tasks.iter()
+    fn matching_visible(&self, project: usize, filter: &Filter) -> Vec<usize> {
+        self.projects[project]
+            .tasks
+            .iter()
+            .enumerate()
+            .filter(|(_, task)| filter.matches(task))
+            .map(|(i, _)| i)
+            .collect()
+    }
+
+    pub fn visible_tasks(&self, project: usize, filter: &Filter) -> Vec<usize> {
+        let tasks = &self.projects[project].tasks;
+        let visible = self.matching_visible(project, filter);
+        let limit = match filter::done_limit() {
+            Some(limit) => limit,
+            None => return visible,
+        };
+
+        let mut done: Vec<usize> = visible
+            .iter()
+            .cloned()
+            .filter(|t| tasks[*t].state() == State::DONE)
+            .collect();
+        done.sort_by_key(|t| std::cmp::Reverse(stats::completed_at(&tasks[*t])));
+        done.truncate(limit);
+        visible
+            .into_iter()
+            .filter(|t| tasks[*t].state() != State::DONE || done.contains(t))
+            .collect()
+    }
+
+    pub fn hidden_done_count(&self, project: usize, filter: &Filter) -> usize {
+        self.matching_visible(project, filter).len() - self.visible_tasks(project, filter).len()
+    }
+
+    fn dated_tasks(&self) -> Vec<(usize, usize, Option<NaiveDate>, Option<NaiveDate>)> {
+        self.projects
+            .iter()
+            .enumerate()
+            .flat_map(|(p, project)| {
+                project.tasks.iter().enumerate().map(move |(t, task)| {
+                    let done = stats::completed_at(task)
+                        .map(|date_time| date_time.with_timezone(&Local).date().naive_local());
+                    (p, t, task.due(), done)
+                })
+            })
+            .collect()
+    }
+
+    pub fn calendar(&self) -> HashMap<NaiveDate, Day> {
+        let mut days: HashMap<NaiveDate, Day> = HashMap::new();
+        for (_, _, due, done) in self.dated_tasks() {
+            if let Some(due) = due {
+                days.entry(due).or_default().due += 1;
+            }
+            if let Some(done) = done {
+                days.entry(done).or_default().done += 1;
+            }
+        }
+        days
+    }
+
+    pub fn tasks_on(&self, date: NaiveDate) -> Vec<(usize, usize)> {
+        self.dated_tasks()
+            .into_iter()
+            .filter(|(_, _, due, done)| *due == Some(date) || *done == Some(date))
+            .map(|(p, t, _, _)| (p, t))
+            .collect()
+    }
+
+    pub fn activity(&self, limit: usize) -> Vec<Activity> {
+        let mut activity: Vec<Activity> = vec![];
+        for (p, project) in self.projects.iter().enumerate() {
+            for (t, task) in project.tasks.iter().enumerate() {
+                activity.extend(task.events.iter().map(|event| Activity {
+                    project: p,
+                    task: t,
+                    date_time: event.date_time(),
+                    event: Some(event.clone()),
+                }));
+                activity.push(Activity {
+                    project: p,
+                    task: t,
+                    date_time: task.created_at,
+                    event: None,
+                });
+            }
+        }
+        activity.sort_by(|a, b| b.date_time.cmp(&a.date_time));
+        activity.truncate(limit);
+        activity
+    }
+
+    pub fn project_activity(&self, project: usize, limit: usize) -> Vec<Activity> {
+        let mut activity = self.activity(usize::MAX);
+        activity.retain(|entry| entry.project == project);
+        activity.truncate(limit);
+        activity
+    }
+
+    pub fn matching_tasks(&self, query: &Query) -> Vec<(usize, usize)> {
+        self.projects
+            .iter()
+            .enumerate()
+            .flat_map(|(p, project)| {
+                project
+                    .tasks
+                    .iter()
+                    .enumerate()
+                    .filter(move |(_, task)| query.matches(project, task))
+                    .map(move |(t, _)| (p, t))
+            })
+            .collect()
+    }
+
+    pub fn search(&self, query: &str) -> Vec<Hit> {
+        self.index.search(query)
+    }
+
+    pub fn find_project(&self, name: &str) -> Result<usize> {
+        self.projects
+            .iter()
+            .position(|p| {
+                p.description.eq_ignore_ascii_case(name)
+                    || p.title().eq_ignore_ascii_case(name)
+                    || p.id == name
+            })
+            .ok_or_else(|| {
+                Error::new(
+                    ErrorKind::InvalidInput,
+                    format!("Unknown project: {}", name),
+                )
+            })
+    }
+
+    pub fn reference(&self, project: usize, task: usize) -> String {
+        let project = &self.projects[project];
+        format!("#{}-{}", project.key(), project.task_number(task))
+    }
+
+    pub fn resolve_reference(&self, key: &str, number: usize) -> Option<(usize, usize)> {
+        let project = self.projects.iter().position(|p| p.key == key)?;
+        Some((project, self.projects[project].task_by_number(number)?))
+    }
+
+    pub fn outgoing_links(&self, project: usize, task: usize) -> Vec<(usize, usize)> {
+        self.task(project, task)
+            .references()
+            .iter()
+            .filter_map(|(key, number)| self.resolve_reference(key, *number))
+            .filter(|link| *link != (project, task))
+            .collect()
+    }
+
+    pub fn incoming_links(&self, project: usize, task: usize) -> Vec<(usize, usize)> {
+        let target = (
+            self.projects[project].key(),
+            self.projects[project].task_number(task),
+        );
+        let mut links = vec![];
+        for (p, other) in self.projects.iter().enumerate() {
+            for (t, candidate) in other.tasks.iter().enumerate() {
+                if (p, t) != (project, task) && candidate.references().contains(&target) {
+                    links.push((p, t));
+                }
+            }
+        }
+        links
+    }
+
+    pub fn contexts(&self) -> Vec<String> {
+        let mut contexts: Vec<String> = self
+            .projects
+            .iter()
+            .flat_map(|project| project.tasks.iter())
+            .flat_map(|task| task.contexts())
+            .collect();
+        contexts.sort();
+        contexts.dedup();
+        contexts
+    }
+
+    pub fn find_task(&self, task_id: &str) -> Option<(usize, usize)> {
+        self.projects
+            .iter()
+            .enumerate()
+            .find_map(|(p, project)| Some((p, project.task_position(task_id.to_string())?)))
     }
 
     pub fn project_count(&self) -> u16 {
         self.projects.len() as u16
     }
 
-    pub fn task_count(&self, project: usize) -> u16 {
-        self.projects[project].tasks.len() as u16
+    pub fn project(&self, project: usize) -> &Project {
+        &self.projects[project]
+    }
+
+    pub fn task(&self, project: usize, task: usize) -> &Task {
+        &self.projects[project].tasks[task]
     }
 
     pub fn task_state(&self, project: usize, task: usize) -> State {
         self.projects[project].tasks[task].state()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn comment(data: &str, minutes: i64) -> Event {
+        Event::Comment {
+            data: data.to_string(),
+            date_time: Utc::now() - Duration::minutes(minutes),
+            actor: None,
+            host: None,
+        }
+    }
+
+    #[test]
+    fn import_keeps_every_new_comment() {
+        let mut project = Project::new(String::from("Web"));
+        let mut task = Task::new(String::from("task"));
+        let created = Utc::now() - Duration::minutes(60);
+        task.created_at = created;
+        task.events = vec![
+            Event::State {
+                data: State::TODO,
+                date_time: created,
+                actor: None,
+                host: None,
+            },
+            Event::Description {
+                data: String::from("task"),
+                date_time: created,
+                actor: None,
+                host: None,
+            },
+            comment("local", 40),
+        ];
+        let mut incoming = task.clone();
+        project.tasks.push(task);
+        let mut db = Database::in_memory(vec![project]).unwrap();
+
+        incoming.events.push(comment("first", 30));
+        incoming.events.push(comment("second", 20));
+        let summary = db.import_tasks(0, vec![incoming]).unwrap();
+
+        assert_eq!(summary.merged.len(), 1);
+        let comments: Vec<String> = db
+            .task(0, 0)
+            .history()
+            .iter()
+            .filter_map(|event| match event {
+                Event::Comment { data, .. } => Some(data.clone()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(comments, vec!["local", "first", "second"]);
+    }
+
+    #[test]
+    fn undo_keeps_only_changed_projects() {
+        let mut web = Project::new(String::from("Web"));
+        web.tasks.push(Task::new(String::from("deploy")));
+        let home = Project::new(String::from("Home"));
+        let mut db = Database::in_memory(vec![web, home]).unwrap();
+
+        db.set_task_points(0, 0, 3).unwrap();
+        assert_eq!(db.history.len(), 1);
+        assert_eq!(db.history[0].projects.len(), 1);
+        assert_eq!(db.history[0].projects[0].0, 0);
+
+        assert!(db.undo().unwrap());
+        assert_eq!(db.task(0, 0).events.len(), 2);
+        assert!(!db.undo().unwrap());
+    }
+}
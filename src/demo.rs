@@ -0,0 +1,360 @@
+use chrono::prelude::{DateTime, NaiveDate, NaiveTime, Utc};
+use chrono::Duration;
+
+use crate::habits::{self, Frequency, Habit, Habits};
+use crate::project::*;
+
+const TEAM: [&str; 3] = ["alice", "bob", "carol"];
+
+struct Builder {
+    task: Task,
+    clock: DateTime<Utc>,
+    actor: Option<String>,
+}
+
+impl Builder {
+    fn new(description: &str, days_ago: i64) -> Builder {
+        let created = Utc::now() - Duration::days(days_ago);
+        let mut task = Task::new(description.to_string());
+        task.created_at = created;
+        task.events = vec![];
+        let builder = Builder {
+            task: task,
+            clock: created,
+            actor: None,
+        };
+        builder
+            .push(|d, a| Event::State {
+                data: State::TODO,
+                date_time: d,
+                actor: a,
+                host: None,
+            })
+            .push(|d, a| Event::Description {
+                data: description.to_string(),
+                date_time: d,
+                actor: a,
+                host: None,
+            })
+    }
+
+    fn push(mut self, event: impl FnOnce(DateTime<Utc>, Option<String>) -> Event) -> Builder {
+        self.task.events.push(event(self.clock, self.actor.clone()));
+        self
+    }
+
+    fn by(mut self, actor: &str) -> Builder {
+        self.actor = Some(actor.to_string());
+        self
+    }
+
+    fn after(mut self, hours: i64) -> Builder {
+        self.clock = (self.clock + Duration::hours(hours)).min(Utc::now());
+        self
+    }
+
+    fn state(self, state: State) -> Builder {
+        self.push(|d, a| Event::State {
+            data: state,
+            date_time: d,
+            actor: a,
+            host: None,
+        })
+    }
+
+    fn comment(self, text: &str) -> Builder {
+        self.push(|d, a| Event::Comment {
+            data: text.to_string(),
+            date_time: d,
+            actor: a,
+            host: None,
+        })
+    }
+
+    fn points(self, points: u32) -> Builder {
+        self.push(|d, a| Event::Points {
+            data: points,
+            date_time: d,
+            actor: a,
+            host: None,
+        })
+    }
+
+    fn estimate(self, minutes: u32) -> Builder {
+        self.push(|d, a| Event::Estimate {
+            data: minutes,
+            date_time: d,
+            actor: a,
+            host: None,
+        })
+    }
+
+    fn assignee(self, assignee: &str) -> Builder {
+        self.push(|d, a| Event::Assignee {
+            data: Some(assignee.to_string()),
+            date_time: d,
+            actor: a,
+            host: None,
+        })
+    }
+
+    fn due(self, days: i64) -> Builder {
+        self.push(|d, a| Event::Due {
+            data: Some(day(days)),
+            date_time: d,
+            actor: a,
+            host: None,
+        })
+    }
+
+    fn sprint(self, sprint: &str) -> Builder {
+        self.push(|d, a| Event::Sprint {
+            data: Some(sprint.to_string()),
+            date_time: d,
+            actor: a,
+            host: None,
+        })
+    }
+
+    fn milestone(self, milestone: &str) -> Builder {
+        self.push(|d, a| Event::Milestone {
+            data: Some(milestone.to_string()),
+            date_time: d,
+            actor: a,
+            host: None,
+        })
+    }
+
+    fn progress(self, progress: u8) -> Builder {
+        self.push(|d, a| Event::Progress {
+            data: progress,
+            date_time: d,
+            actor: a,
+            host: None,
+        })
+    }
+
+    fn checklist(self, items: &[(&str, bool)]) -> Builder {
+        let items = items
+            .iter()
+            .map(|(text, done)| ChecklistItem {
+                text: text.to_string(),
+                done: *done,
+            })
+            .collect();
+        self.push(|d, a| Event::Checklist {
+            data: items,
+            date_time: d,
+            actor: a,
+            host: None,
+        })
+    }
+
+    fn notes(self, notes: &str) -> Builder {
+        self.push(|d, a| Event::Notes {
+            data: notes.to_string(),
+            date_time: d,
+            actor: a,
+            host: None,
+        })
+    }
+
+    fn waiting(self, on: &str) -> Builder {
+        self.state(State::WAITING).push(|d, a| Event::Waiting {
+            data: on.to_string(),
+            date_time: d,
+            actor: a,
+            host: None,
+        })
+    }
+
+    fn someday(self) -> Builder {
+        self.push(|d, a| Event::Someday {
+            data: true,
+            date_time: d,
+            actor: a,
+            host: None,
+        })
+    }
+
+    fn build(self) -> Task {
+        self.task
+    }
+}
+
+fn day(offset: i64) -> NaiveDate {
+    habits::today() + Duration::days(offset)
+}
+
+fn website() -> Project {
+    let mut project = Project::new(String::from(
+        "Website relaunch\nRedesign the marketing site and move it to the new CMS.",
+    ));
+    project.notes = String::from("Staging lives at https://staging.example.com");
+    project.rate = Some(85.0);
+    project.sprints = vec![
+        Sprint {
+            name: String::from("Sprint 1"),
+            start: day(-20),
+            end: day(-7),
+        },
+        Sprint {
+            name: String::from("Sprint 2"),
+            start: day(-6),
+            end: day(7),
+        },
+    ];
+    project.milestones = vec![Milestone {
+        name: String::from("Launch"),
+        target: day(18),
+    }];
+    project.tasks = vec![
+        Builder::new("Audit the current content", 30)
+            .by(TEAM[0])
+            .points(3)
+            .sprint("Sprint 1")
+            .after(20)
+            .state(State::ONGOING)
+            .after(70)
+            .comment("Found 40 pages we can retire")
+            .state(State::DONE)
+            .build(),
+        Builder::new("Draft the new information architecture", 26)
+            .by(TEAM[1])
+            .points(5)
+            .estimate(480)
+            .sprint("Sprint 1")
+            .assignee(TEAM[1])
+            .after(30)
+            .state(State::ONGOING)
+            .after(100)
+            .state(State::DONE)
+            .build(),
+        Builder::new("Design the landing page #WEBSITE-2", 12)
+            .by(TEAM[2])
+            .points(8)
+            .estimate(960)
+            .sprint("Sprint 2")
+            .milestone("Launch")
+            .assignee(TEAM[2])
+            .after(24)
+            .state(State::ONGOING)
+            .progress(60)
+            .checklist(&[
+                ("Hero section", true),
+                ("Pricing table", true),
+                ("Testimonials", false),
+            ])
+            .comment("**Feedback** from marketing is in the shared folder")
+            .build(),
+        Builder::new("Set up redirects for retired pages", 9)
+            .by(TEAM[0])
+            .points(2)
+            .sprint("Sprint 2")
+            .milestone("Launch")
+            .due(3)
+            .build(),
+        Builder::new("Get legal sign-off on the privacy page", 8)
+            .by(TEAM[1])
+            .sprint("Sprint 2")
+            .milestone("Launch")
+            .after(10)
+            .waiting("Legal team")
+            .build(),
+        Builder::new("Write launch announcement @home", 4)
+            .by(TEAM[2])
+            .milestone("Launch")
+            .due(15)
+            .notes("- Mention the faster pages\n- Link the new docs")
+            .build(),
+        Builder::new("Fix broken contact form https://example.com/contact", 2)
+            .by(TEAM[0])
+            .points(1)
+            .due(-1)
+            .comment("Reported by two customers")
+            .build(),
+    ];
+    project.archive = vec![Builder::new("Pick a CMS", 45)
+        .by(TEAM[1])
+        .after(48)
+        .state(State::ONGOING)
+        .after(120)
+        .state(State::DONE)
+        .build()];
+    project
+}
+
+fn mobile() -> Project {
+    let mut project = Project::new(String::from("Mobile app"));
+    project.tasks = vec![
+        Builder::new("Crash on startup with an empty cache", 15)
+            .by(TEAM[1])
+            .assignee(TEAM[1])
+            .points(3)
+            .after(4)
+            .state(State::ONGOING)
+            .after(30)
+            .state(State::DONE)
+            .build(),
+        Builder::new("Offline mode for the task list", 11)
+            .by(TEAM[0])
+            .assignee(TEAM[0])
+            .points(13)
+            .estimate(1440)
+            .after(48)
+            .state(State::ONGOING)
+            .progress(30)
+            .build(),
+        Builder::new("Submit build to the app store", 6)
+            .by(TEAM[2])
+            .due(5)
+            .after(30)
+            .waiting("App review")
+            .build(),
+        Builder::new("Dark mode", 3).by(TEAM[2]).points(5).build(),
+        Builder::new("Tablet layout", 20).someday().build(),
+    ];
+    project
+}
+
+fn household() -> Project {
+    let mut project = Project::new(String::from("Household"));
+    project.daily = Some(Daily::new(NaiveTime::from_hms(6, 0, 0)));
+    project.tasks = vec![
+        Builder::new("Water the plants @home", 1).build(),
+        Builder::new("Take out the recycling @home", 1)
+            .after(10)
+            .state(State::DONE)
+            .build(),
+        Builder::new("Renew passport", 10)
+            .due(25)
+            .checklist(&[("Photos", false), ("Application form", true)])
+            .build(),
+        Builder::new("Learn to bake sourdough", 40)
+            .someday()
+            .build(),
+    ];
+    project
+}
+
+pub fn projects() -> Vec<Project> {
+    let mut projects = vec![website(), mobile(), household()];
+    for project in projects.iter_mut() {
+        project.rank_tasks();
+        project.sort_tasks();
+    }
+    projects
+}
+
+pub fn habits() -> Habits {
+    let mut exercise = Habit::new(String::from("Exercise"), Frequency::Daily);
+    exercise.done = (1..14).filter(|d| d % 3 != 0).map(|d| day(-d)).collect();
+    let mut review = Habit::new(String::from("Weekly review"), Frequency::Weekly);
+    review.done = vec![day(-7), day(-14), day(-21)];
+    let mut reading = Habit::new(String::from("Read 20 pages"), Frequency::Daily);
+    reading.done = (0..6).map(|d| day(-d)).collect();
+    Habits {
+        habits: vec![exercise, review, reading],
+        ephemeral: true,
+    }
+}
@@ -0,0 +1,96 @@
+use chrono::Duration;
+use termion::color::Rgb;
+
+use crate::formatted_string::FormattedString;
+use crate::locale::{fill, locale};
+use crate::markdown;
+use crate::project::*;
+use crate::theme::theme;
+use crate::timeline::*;
+
+static BLUE: Rgb = Rgb(52, 152, 219);
+static RED: Rgb = Rgb(192, 57, 43);
+static GREEN: Rgb = Rgb(46, 204, 113);
+
+pub enum DetailRow {
+    Checklist(ChecklistItem),
+    Note(usize, String),
+    Estimate(String),
+    Event(TimelineEntry),
+}
+
+pub fn detail_rows(task: &Task) -> Vec<DetailRow> {
+    let mut rows: Vec<DetailRow> = task
+        .checklist()
+        .into_iter()
+        .map(DetailRow::Checklist)
+        .collect();
+
+    rows.extend(
+        task.notes()
+            .lines()
+            .enumerate()
+            .map(|(i, line)| DetailRow::Note(i, line.to_string())),
+    );
+    if let Some(estimate) = task.estimate() {
+        rows.push(DetailRow::Estimate(estimate_line(estimate, task.tracked())));
+    }
+    rows.extend(timeline(task).into_iter().map(DetailRow::Event));
+    rows
+}
+
+fn estimate_line(estimate: Duration, tracked: Duration) -> String {
+    let summary = fill(
+        &fill(&locale().estimate, &humanize(estimate)),
+        &humanize(tracked),
+    );
+    let difference = if tracked > estimate {
+        FormattedString::from(&fill(
+            &locale().over_estimate,
+            &humanize(tracked - estimate),
+        ))
+        .fg(RED)
+    } else {
+        FormattedString::from(&fill(
+            &locale().under_estimate,
+            &humanize(estimate - tracked),
+        ))
+        .fg(GREEN)
+    };
+    format!("{} ({})", summary, difference)
+}
+
+fn div() -> FormattedString {
+    FormattedString::from(theme().vertical()).fg(BLUE)
+}
+
+impl Listable for DetailRow {
+    fn view(&self) -> String {
+        match self {
+            DetailRow::Checklist(item) => item.view(),
+            DetailRow::Event(entry) => entry.view(),
+            DetailRow::Estimate(line) => format!(
+                "{div_left}{label}{div}{line}",
+                label = FormattedString::from(&locale().header_estimate)
+                    .right(10)
+                    .fg(BLUE),
+                line = line,
+                div_left = div().left(2),
+                div = div().center(3)
+            ),
+            DetailRow::Note(i, line) => format!(
+                "{div_left}{label}{div}{line}",
+                label = FormattedString::from(&if *i == 0 {
+                    locale().header_notes.clone()
+                } else {
+                    String::new()
+                })
+                .right(10)
+                .fg(BLUE),
+                line = markdown::render_line(line),
+                div_left = div().left(2),
+                div = div().center(3)
+            ),
+        }
+    }
+}
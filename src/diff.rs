@@ -0,0 +1,156 @@
+use std::collections::HashMap;
+use termion::color::Rgb;
+
+use crate::formatted_string::FormattedString;
+use crate::locale::{fill, locale};
+use crate::project::*;
+
+static GREEN: Rgb = Rgb(46, 204, 113);
+static RED: Rgb = Rgb(231, 76, 60);
+static YELLOW: Rgb = Rgb(241, 196, 15);
+
+pub enum Change {
+    Added,
+    Removed,
+    Changed(Vec<String>),
+}
+
+pub struct Entry {
+    pub project: String,
+    pub description: String,
+    pub change: Change,
+}
+
+struct Located<'a> {
+    project: &'a str,
+    task: &'a Task,
+    archived: bool,
+}
+
+fn locate(projects: &[Project]) -> HashMap<&str, Located> {
+    let mut tasks = HashMap::new();
+    for project in projects {
+        let live = project.tasks.iter().map(|task| (task, false));
+        let archived = project.archive.iter().map(|task| (task, true));
+        for (task, archived) in live.chain(archived) {
+            tasks.insert(
+                task.id.as_str(),
+                Located {
+                    project: project.title(),
+                    task: task,
+                    archived: archived,
+                },
+            );
+        }
+    }
+    tasks
+}
+
+fn field<T: PartialEq>(name: &str, before: T, after: T, show: fn(T) -> String) -> Option<String> {
+    match before == after {
+        true => None,
+        false => Some(format!("{}: {} → {}", name, show(before), show(after))),
+    }
+}
+
+fn optional<T: ToString>(value: Option<T>) -> String {
+    value
+        .map(|v| v.to_string())
+        .unwrap_or_else(|| String::from("-"))
+}
+
+fn changes(before: &Located, after: &Located) -> Vec<String> {
+    let (old, new) = (before.task, after.task);
+    let mut fields = vec![
+        field("project", before.project, after.project, String::from),
+        field("description", old.description(), new.description(), |d| d),
+        field("state", old.state(), new.state(), |s| format!("{:?}", s)),
+        field("assignee", old.assignee(), new.assignee(), optional),
+        field("due", old.due(), new.due(), optional),
+        field("points", old.points(), new.points(), optional),
+        field("archived", before.archived, after.archived, |a| {
+            a.to_string()
+        }),
+    ];
+    if old.notes() != new.notes() {
+        fields.push(Some(String::from("notes edited")));
+    }
+    let mut fields: Vec<String> = fields.drain(..).filter_map(|f| f).collect();
+    if fields.is_empty() && old.events.len() != new.events.len() {
+        fields.push(format!(
+            "{} new events",
+            new.events.len() as i64 - old.events.len() as i64
+        ));
+    }
+    fields
+}
+
+pub fn compare(before: &[Project], after: &[Project]) -> Vec<Entry> {
+    let old = locate(before);
+    let new = locate(after);
+    let mut entries = vec![];
+
+    for project in after {
+        for task in project.tasks.iter().chain(project.archive.iter()) {
+            let entry = |change| Entry {
+                project: project.title().to_string(),
+                description: task.description(),
+                change: change,
+            };
+            match old.get(task.id.as_str()) {
+                None => entries.push(entry(Change::Added)),
+                Some(previous) => {
+                    let fields = changes(previous, &new[task.id.as_str()]);
+                    if !fields.is_empty() {
+                        entries.push(entry(Change::Changed(fields)));
+                    }
+                }
+            }
+        }
+    }
+    for project in before {
+        for task in project.tasks.iter().chain(project.archive.iter()) {
+            if !new.contains_key(task.id.as_str()) {
+                entries.push(Entry {
+                    project: project.title().to_string(),
+                    description: task.description(),
+                    change: Change::Removed,
+                });
+            }
+        }
+    }
+    entries
+}
+
+pub fn lines(entries: &[Entry], color: bool) -> Vec<String> {
+    let mut lines = vec![];
+    for entry in entries {
+        let (mark, tint) = match entry.change {
+            Change::Added => ("+", GREEN),
+            Change::Removed => ("-", RED),
+            Change::Changed(_) => ("~", YELLOW),
+        };
+        let line = format!("{} [{}] {}", mark, entry.project, entry.description);
+        lines.push(match color {
+            true => FormattedString::from(&line).fg(tint).to_string(),
+            false => line,
+        });
+        if let Change::Changed(fields) = &entry.change {
+            lines.extend(fields.iter().map(|field| format!("    {}", field)));
+        }
+    }
+    lines
+}
+
+pub fn totals(entries: &[Entry]) -> String {
+    let (mut added, mut removed, mut changed) = (0, 0, 0);
+    for entry in entries {
+        match entry.change {
+            Change::Added => added += 1,
+            Change::Removed => removed += 1,
+            Change::Changed(_) => changed += 1,
+        }
+    }
+    let template = fill(&locale().diff_totals, &added.to_string());
+    fill(&fill(&template, &removed.to_string()), &changed.to_string())
+}
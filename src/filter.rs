@@ -0,0 +1,289 @@
+use chrono::prelude::Datelike;
+use chrono::prelude::Local;
+use chrono::prelude::NaiveDate;
+use chrono::Duration;
+use std::fmt;
+use std::fmt::Display;
+use std::fmt::Formatter;
+use std::sync::atomic::{AtomicBool, Ordering};
+use termion::color::Rgb;
+
+use crate::columns::{project_row, state_bar, ProjectColumn};
+use crate::config::Config;
+use crate::database::Database;
+use crate::formatted_string::FormattedString;
+use crate::project::{Listable, State, Task};
+use crate::query::Query;
+use crate::stats;
+use crate::theme::theme;
+
+static ALL_DONE: AtomicBool = AtomicBool::new(false);
+
+static PINK: Rgb = Rgb(200, 0, 150);
+static PURPLE: Rgb = Rgb(214, 162, 232);
+
+#[derive(Clone, Debug, Default)]
+pub struct Filter {
+    pub sprint: Option<String>,
+    pub assignee: Option<String>,
+    pub state: Option<State>,
+    pub open: bool,
+    pub someday: bool,
+    pub context: Option<String>,
+    pub range: Option<DateRange>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DateField {
+    Created,
+    Completed,
+}
+
+#[derive(Clone, Debug)]
+pub struct DateRange {
+    pub field: DateField,
+    pub from: Option<NaiveDate>,
+    pub to: Option<NaiveDate>,
+    label: String,
+}
+
+pub struct View {
+    pub name: String,
+    pub tasks: Vec<(usize, usize)>,
+    states: Vec<State>,
+}
+
+impl Filter {
+    pub fn parse(input: &str, config: &Config) -> Filter {
+        let mut filter = Filter::default();
+        for token in input.split_whitespace() {
+            match token.find(':').map(|i| token.split_at(i)) {
+                Some(("sprint", value)) => filter.sprint = Some(value[1..].to_string()),
+                Some(("assignee", value)) => filter.assignee = Some(value[1..].to_string()),
+                Some(("state", value)) => filter.state = parse_state(&value[1..]),
+                Some(("created", value)) => {
+                    filter.range = DateRange::parse(DateField::Created, &value[1..])
+                }
+                Some(("completed", value)) => {
+                    filter.range = DateRange::parse(DateField::Completed, &value[1..])
+                }
+                _ if token == "me" => filter.assignee = config.me(),
+                _ if token.len() > 1 && token.starts_with('@') => {
+                    filter.context = Some(token[1..].to_lowercase())
+                }
+                _ if token == "open" => filter.open = true,
+                _ if token == "someday" => filter.someday = true,
+                _ => {}
+            }
+        }
+        filter
+    }
+
+    pub fn matches(&self, task: &Task) -> bool {
+        let sprint = match &self.sprint {
+            Some(sprint) => task.sprint().as_ref() == Some(sprint),
+            None => true,
+        };
+        let assignee = match &self.assignee {
+            Some(assignee) => task.assignee().as_ref() == Some(assignee),
+            None => true,
+        };
+        let state = match self.state {
+            Some(state) => task.state() == state,
+            None => true,
+        };
+        let open = !self.open || task.state() != State::DONE;
+        let someday = task.someday() == self.someday;
+        let context = match &self.context {
+            Some(context) => task.contexts().contains(context),
+            None => true,
+        };
+        let range = match &self.range {
+            Some(range) => range.matches(task),
+            None => true,
+        };
+        let scheduled = !theme().hide_scheduled || !task.scheduled();
+        sprint && assignee && state && open && someday && context && range && scheduled
+    }
+
+    pub fn summary(&self) -> Option<String> {
+        let mut terms = vec![];
+        if let Some(sprint) = &self.sprint {
+            terms.push(format!("sprint:{}", sprint));
+        }
+        if let Some(assignee) = &self.assignee {
+            terms.push(format!("assignee:{}", assignee));
+        }
+        if let Some(state) = self.state {
+            terms.push(format!("state:{:?}", state));
+        }
+        if self.open {
+            terms.push(String::from("open"));
+        }
+        if self.someday {
+            terms.push(String::from("someday"));
+        }
+        if let Some(context) = &self.context {
+            terms.push(format!("@{}", context));
+        }
+        if let Some(range) = &self.range {
+            terms.push(range.to_string());
+        }
+
+        if terms.is_empty() {
+            None
+        } else {
+            Some(terms.join(" "))
+        }
+    }
+}
+
+impl DateRange {
+    pub fn parse(field: DateField, value: &str) -> Option<DateRange> {
+        let today = Local::now().date().naive_local();
+        let week = today - Duration::days(today.weekday().num_days_from_monday() as i64);
+        let month = today.with_day(1).unwrap();
+        let last_month = (month - Duration::days(1)).with_day(1).unwrap();
+        let date = |s: &str| NaiveDate::parse_from_str(s, "%Y-%m-%d").ok();
+
+        let (from, to) = match value {
+            "today" => (Some(today), Some(today)),
+            "this-week" => (Some(week), Some(today)),
+            "last-week" => (
+                Some(week - Duration::weeks(1)),
+                Some(week - Duration::days(1)),
+            ),
+            "this-month" => (Some(month), Some(today)),
+            "last-month" => (Some(last_month), Some(month - Duration::days(1))),
+            _ => match value.find("..") {
+                Some(i) => {
+                    let (from, to) = (&value[..i], &value[i + 2..]);
+                    match (date(from), date(to)) {
+                        (None, _) if !from.is_empty() => return None,
+                        (_, None) if !to.is_empty() => return None,
+                        range => range,
+                    }
+                }
+                None => (Some(date(value)?), date(value)),
+            },
+        };
+
+        Some(DateRange {
+            field: field,
+            from: from,
+            to: to,
+            label: value.to_string(),
+        })
+    }
+
+    pub fn matches(&self, task: &Task) -> bool {
+        let date_time = match self.field {
+            DateField::Created => Some(task.created_at),
+            DateField::Completed => stats::completed_at(task),
+        };
+
+        match date_time.map(|d| d.with_timezone(&Local).date().naive_local()) {
+            Some(date) => {
+                self.from.map_or(true, |from| date >= from) && self.to.map_or(true, |to| date <= to)
+            }
+            None => false,
+        }
+    }
+}
+
+impl Display for DateRange {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        let field = match self.field {
+            DateField::Created => "created",
+            DateField::Completed => "completed",
+        };
+        let bound = |date: Option<NaiveDate>| date.map(|d| d.format("%Y-%m-%d").to_string());
+        write!(
+            f,
+            "{}:{} ({}..{})",
+            field,
+            self.label,
+            bound(self.from).unwrap_or_default(),
+            bound(self.to).unwrap_or_default()
+        )
+    }
+}
+
+pub fn all_done() -> bool {
+    ALL_DONE.load(Ordering::Relaxed)
+}
+
+pub fn set_all_done(all_done: bool) {
+    ALL_DONE.store(all_done, Ordering::Relaxed);
+}
+
+pub fn done_limit() -> Option<usize> {
+    if all_done() {
+        None
+    } else {
+        theme().done_limit
+    }
+}
+
+pub fn parse_state(value: &str) -> Option<State> {
+    match value.to_lowercase().as_str() {
+        "todo" => Some(State::TODO),
+        "ongoing" => Some(State::ONGOING),
+        "waiting" => Some(State::WAITING),
+        "done" => Some(State::DONE),
+        _ => None,
+    }
+}
+
+impl View {
+    pub fn new(name: &str, query: &Query, db: &Database) -> View {
+        View::from_tasks(name, db.matching_tasks(query), db)
+    }
+
+    pub fn from_tasks(name: &str, tasks: Vec<(usize, usize)>, db: &Database) -> View {
+        View {
+            name: name.to_string(),
+            states: tasks.iter().map(|(p, t)| db.task_state(*p, *t)).collect(),
+            tasks: tasks,
+        }
+    }
+
+    fn count(&self, state: State) -> usize {
+        self.states.iter().filter(|s| **s == state).count()
+    }
+}
+
+impl Listable for View {
+    fn view(&self) -> String {
+        project_row(|column, width| {
+            let count = |state: State| {
+                FormattedString::from(&self.count(state).to_string())
+                    .fg(theme().state_color(state))
+                    .center(width)
+            };
+            match column {
+                ProjectColumn::Tasks => FormattedString::from(&self.tasks.len().to_string())
+                    .fg(PURPLE)
+                    .center(width),
+                ProjectColumn::Todo => count(State::TODO),
+                ProjectColumn::Ongoing => count(State::ONGOING),
+                ProjectColumn::Waiting => count(State::WAITING),
+                ProjectColumn::Done => count(State::DONE),
+                ProjectColumn::Bar => {
+                    let counts: Vec<(State, usize)> =
+                        [State::DONE, State::ONGOING, State::WAITING, State::TODO]
+                            .iter()
+                            .map(|state| (*state, self.count(*state)))
+                            .collect();
+                    state_bar(&counts, width)
+                }
+                ProjectColumn::Complete | ProjectColumn::Remaining => {
+                    FormattedString::from("").center(width)
+                }
+                ProjectColumn::Description => FormattedString::from(&format!("★ {}", self.name))
+                    .left(width)
+                    .fg(PINK),
+            }
+        })
+    }
+}
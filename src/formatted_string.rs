@@ -1,20 +1,133 @@
+use std::env;
 use std::fmt;
 use std::fmt::Display;
 use std::fmt::Formatter;
+use std::sync::OnceLock;
 use termion::color;
 use termion::color::AnsiValue;
 use termion::color::Bg;
 use termion::color::Fg;
 use termion::color::Rgb;
+use termion::style;
+use unicode_width::UnicodeWidthStr;
+
+static TRUECOLOR: OnceLock<bool> = OnceLock::new();
+
+const NAMES: [&str; 16] = [
+    "black",
+    "red",
+    "green",
+    "yellow",
+    "blue",
+    "magenta",
+    "cyan",
+    "white",
+    "bright-black",
+    "bright-red",
+    "bright-green",
+    "bright-yellow",
+    "bright-blue",
+    "bright-magenta",
+    "bright-cyan",
+    "bright-white",
+];
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Color {
+    Named(u8),
+    Ansi(u8),
+    Rgb(u8, u8, u8),
+}
+
+fn truecolor() -> bool {
+    *TRUECOLOR.get_or_init(|| match env::var("COLORTERM") {
+        Ok(value) => value == "truecolor" || value == "24bit",
+        Err(_) => false,
+    })
+}
+
+fn cube(channel: u8) -> u8 {
+    ((channel as u16 * 5 + 127) / 255) as u8
+}
+
+impl Color {
+    pub fn parse(text: &str) -> Option<Color> {
+        let text = text.trim().to_lowercase();
+        if let Some(hex) = text.strip_prefix('#') {
+            if hex.len() != 6 {
+                return None;
+            }
+            let channel = |i: usize| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok();
+            return Some(Color::Rgb(channel(0)?, channel(2)?, channel(4)?));
+        }
+        if let Ok(value) = text.parse::<u8>() {
+            return Some(Color::Ansi(value));
+        }
+        NAMES
+            .iter()
+            .position(|name| *name == text)
+            .map(|i| Color::Named(i as u8))
+    }
+
+    fn downgrade(self) -> Color {
+        match self {
+            Color::Rgb(r, g, b) if !truecolor() => {
+                Color::Ansi(AnsiValue::rgb(cube(r), cube(g), cube(b)).0)
+            }
+            color => color,
+        }
+    }
+}
+
+impl From<Rgb> for Color {
+    fn from(color: Rgb) -> Color {
+        Color::Rgb(color.0, color.1, color.2)
+    }
+}
+
+impl From<AnsiValue> for Color {
+    fn from(color: AnsiValue) -> Color {
+        Color::Ansi(color.0)
+    }
+}
+
+impl color::Color for Color {
+    fn write_fg(&self, f: &mut Formatter) -> fmt::Result {
+        match self.downgrade() {
+            Color::Named(n) if n < 8 => write!(f, "\x1b[{}m", 30 + n),
+            Color::Named(n) => write!(f, "\x1b[{}m", 90 + n - 8),
+            Color::Ansi(n) => AnsiValue(n).write_fg(f),
+            Color::Rgb(r, g, b) => Rgb(r, g, b).write_fg(f),
+        }
+    }
+
+    fn write_bg(&self, f: &mut Formatter) -> fmt::Result {
+        match self.downgrade() {
+            Color::Named(n) if n < 8 => write!(f, "\x1b[{}m", 40 + n),
+            Color::Named(n) => write!(f, "\x1b[{}m", 100 + n - 8),
+            Color::Ansi(n) => AnsiValue(n).write_bg(f),
+            Color::Rgb(r, g, b) => Rgb(r, g, b).write_bg(f),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum Attribute {
+    Bold,
+    Italic,
+    Underline,
+}
 
 #[derive(Clone, Debug)]
 pub enum FormattedString {
     Raw(String),
-    ColoredFg(Box<FormattedString>, Rgb),
-    ColoredBg(Box<FormattedString>, AnsiValue),
+    ColoredFg(Box<FormattedString>, Color),
+    ColoredBg(Box<FormattedString>, Color),
     LeftAligned(Box<FormattedString>, usize),
     RightAligned(Box<FormattedString>, usize),
     CenterAligned(Box<FormattedString>, usize),
+    Styled(Box<FormattedString>, Attribute),
+    Spans(Vec<FormattedString>),
 }
 
 impl FormattedString {
@@ -22,28 +135,82 @@ impl FormattedString {
         FormattedString::Raw(content.to_string())
     }
 
-    pub fn content(&self) -> &str {
+    pub fn spans(spans: Vec<FormattedString>) -> FormattedString {
+        FormattedString::Spans(spans)
+    }
+
+    pub fn content(&self) -> String {
         match self {
-            FormattedString::Raw(content) => content,
+            FormattedString::Raw(content) => content.clone(),
             FormattedString::ColoredFg(box content, _) => content.content(),
             FormattedString::ColoredBg(box content, _) => content.content(),
             FormattedString::LeftAligned(box content, _) => content.content(),
             FormattedString::RightAligned(box content, _) => content.content(),
             FormattedString::CenterAligned(box content, _) => content.content(),
+            FormattedString::Styled(box content, _) => content.content(),
+            FormattedString::Spans(spans) => spans.iter().map(|span| span.content()).collect(),
+        }
+    }
+
+    fn has_fg(&self) -> bool {
+        match self {
+            FormattedString::ColoredFg(_, _) => true,
+            FormattedString::ColoredBg(box content, _)
+            | FormattedString::LeftAligned(box content, _)
+            | FormattedString::RightAligned(box content, _)
+            | FormattedString::CenterAligned(box content, _)
+            | FormattedString::Styled(box content, _) => content.has_fg(),
+            _ => false,
         }
     }
 
-    pub fn fg(&self, color: Rgb) -> FormattedString {
+    pub fn style(&self, attribute: Attribute) -> FormattedString {
+        FormattedString::Styled(box self.clone(), attribute)
+    }
+
+    pub fn bold(&self) -> FormattedString {
+        self.style(Attribute::Bold)
+    }
+
+    pub fn italic(&self) -> FormattedString {
+        self.style(Attribute::Italic)
+    }
+
+    pub fn underline(&self) -> FormattedString {
+        self.style(Attribute::Underline)
+    }
+
+    pub fn fg<C: Into<Color>>(&self, color: C) -> FormattedString {
+        let color = color.into();
         match self {
             FormattedString::Raw(_) => FormattedString::ColoredFg(box self.clone(), color),
             FormattedString::ColoredFg(box content, _) => {
                 FormattedString::ColoredFg(box content.clone(), color)
             }
+            FormattedString::Spans(spans) => FormattedString::Spans(
+                spans
+                    .iter()
+                    .map(|span| match span.has_fg() {
+                        true => span.clone(),
+                        false => span.fg(color),
+                    })
+                    .collect(),
+            ),
+            FormattedString::LeftAligned(box content @ FormattedString::Spans(_), width) => {
+                FormattedString::LeftAligned(box content.fg(color), *width)
+            }
+            FormattedString::RightAligned(box content @ FormattedString::Spans(_), width) => {
+                FormattedString::RightAligned(box content.fg(color), *width)
+            }
+            FormattedString::CenterAligned(box content @ FormattedString::Spans(_), width) => {
+                FormattedString::CenterAligned(box content.fg(color), *width)
+            }
             s @ _ => FormattedString::ColoredFg(box s.clone(), color),
         }
     }
 
-    pub fn bg(&self, color: AnsiValue) -> FormattedString {
+    pub fn bg<C: Into<Color>>(&self, color: C) -> FormattedString {
+        let color = color.into();
         match self {
             FormattedString::Raw(_) => FormattedString::ColoredBg(box self.clone(), color),
             FormattedString::ColoredBg(box content, _) => {
@@ -54,7 +221,7 @@ impl FormattedString {
     }
 
     pub fn focused(&self) -> FormattedString {
-        let color = AnsiValue::grayscale(6);
+        let color = Color::from(AnsiValue::grayscale(6));
         match self {
             FormattedString::Raw(_) => FormattedString::ColoredBg(box self.clone(), color),
             FormattedString::ColoredBg(box content, _) => {
@@ -71,8 +238,11 @@ impl FormattedString {
             }
             FormattedString::ColoredFg(box boxed, color) => boxed.left(width).fg(*color),
             FormattedString::ColoredBg(box boxed, color) => boxed.left(width).bg(*color),
-            FormattedString::Raw(_) => FormattedString::LeftAligned(box self.clone(), width),
-            _ => FormattedString::from(self.content()).left(width),
+            FormattedString::Styled(box boxed, attribute) => boxed.left(width).style(*attribute),
+            FormattedString::Raw(_) | FormattedString::Spans(_) => {
+                FormattedString::LeftAligned(box self.clone(), width)
+            }
+            _ => FormattedString::from(&self.content()).left(width),
         }
     }
 
@@ -83,8 +253,11 @@ impl FormattedString {
             }
             FormattedString::ColoredFg(box boxed, color) => boxed.right(width).fg(*color),
             FormattedString::ColoredBg(box boxed, color) => boxed.right(width).bg(*color),
-            FormattedString::Raw(_) => FormattedString::RightAligned(box self.clone(), width),
-            _ => FormattedString::from(self.content()).right(width),
+            FormattedString::Styled(box boxed, attribute) => boxed.right(width).style(*attribute),
+            FormattedString::Raw(_) | FormattedString::Spans(_) => {
+                FormattedString::RightAligned(box self.clone(), width)
+            }
+            _ => FormattedString::from(&self.content()).right(width),
         }
     }
 
@@ -95,28 +268,171 @@ impl FormattedString {
             }
             FormattedString::ColoredFg(box boxed, color) => boxed.center(width).fg(*color),
             FormattedString::ColoredBg(box boxed, color) => boxed.center(width).bg(*color),
-            FormattedString::Raw(_) => FormattedString::CenterAligned(box self.clone(), width),
-            _ => FormattedString::from(self.content()).center(width),
+            FormattedString::Styled(box boxed, attribute) => boxed.center(width).style(*attribute),
+            FormattedString::Raw(_) | FormattedString::Spans(_) => {
+                FormattedString::CenterAligned(box self.clone(), width)
+            }
+            _ => FormattedString::from(&self.content()).center(width),
         }
     }
 
-    pub fn concat(&self, other: &FormattedString) -> String {
-        format!("{}{}", self.to_string(), other.to_string())
+    pub fn width(&self) -> usize {
+        visible_width(&self.to_string())
+    }
+
+    pub fn truncate(&self, width: usize) -> FormattedString {
+        match self {
+            FormattedString::Raw(content) => FormattedString::Raw(clip(content, width)),
+            FormattedString::ColoredFg(box content, color) => {
+                FormattedString::ColoredFg(box content.truncate(width), *color)
+            }
+            FormattedString::ColoredBg(box content, color) => {
+                FormattedString::ColoredBg(box content.truncate(width), *color)
+            }
+            FormattedString::LeftAligned(box content, w) => {
+                FormattedString::LeftAligned(box content.truncate(width), (*w).min(width))
+            }
+            FormattedString::RightAligned(box content, w) => {
+                FormattedString::RightAligned(box content.truncate(width), (*w).min(width))
+            }
+            FormattedString::CenterAligned(box content, w) => {
+                FormattedString::CenterAligned(box content.truncate(width), (*w).min(width))
+            }
+            FormattedString::Styled(box content, attribute) => {
+                FormattedString::Styled(box content.truncate(width), *attribute)
+            }
+            FormattedString::Spans(spans) => {
+                let mut remaining = width;
+                let mut truncated = vec![];
+                for span in spans.iter() {
+                    let span = span.truncate(remaining);
+                    remaining -= span.width().min(remaining);
+                    truncated.push(span);
+                }
+                FormattedString::Spans(truncated)
+            }
+        }
     }
 }
 
+#[derive(Clone, Debug, Default)]
+pub struct FormattedLine {
+    segments: Vec<FormattedString>,
+}
+
+impl FormattedLine {
+    pub fn new() -> FormattedLine {
+        FormattedLine::default()
+    }
+
+    pub fn push(mut self, segment: FormattedString) -> FormattedLine {
+        self.segments.push(segment);
+        self
+    }
+
+    pub fn width(&self) -> usize {
+        self.segments.iter().map(FormattedString::width).sum()
+    }
+
+    pub fn truncate(&self, width: usize) -> FormattedLine {
+        if self.width() <= width {
+            return self.clone();
+        }
+        let mut line = FormattedLine::new();
+        let mut remaining = width;
+        for segment in self.segments.iter() {
+            if remaining == 0 {
+                break;
+            }
+            let segment = match segment.width() > remaining {
+                true => segment.truncate(remaining),
+                false => segment.clone(),
+            };
+            remaining -= segment.width().min(remaining);
+            line = line.push(segment);
+        }
+        line
+    }
+}
+
+impl Display for FormattedLine {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        for segment in self.segments.iter() {
+            write!(f, "{}", segment)?;
+        }
+        Ok(())
+    }
+}
+
+fn visible_width(content: &str) -> usize {
+    let mut width = 0;
+    let mut rest = content;
+    while let Some(start) = rest.find('\x1b') {
+        width += UnicodeWidthStr::width(&rest[..start]);
+        rest = &rest[start..];
+        match rest.find(|c: char| c.is_ascii_alphabetic()) {
+            Some(end) => rest = &rest[end + 1..],
+            None => rest = "",
+        }
+    }
+    width + UnicodeWidthStr::width(rest)
+}
+
+fn clip(content: &str, width: usize) -> String {
+    let mut clipped = String::new();
+    let mut used = 0;
+    let mut chars = content.chars();
+    while let Some(c) = chars.next() {
+        if c == '\x1b' {
+            clipped.push(c);
+            for c in chars.by_ref() {
+                clipped.push(c);
+                if c.is_ascii_alphabetic() {
+                    break;
+                }
+            }
+            continue;
+        }
+        let char_width = UnicodeWidthStr::width(c.to_string().as_str());
+        if used + char_width > width {
+            continue;
+        }
+        used += char_width;
+        clipped.push(c);
+    }
+    clipped
+}
+
+fn padding(content: &str, width: usize) -> usize {
+    width.saturating_sub(visible_width(content))
+}
+
+fn spaces(count: usize) -> String {
+    " ".repeat(count)
+}
+
 impl Display for FormattedString {
     fn fmt(&self, f: &mut Formatter) -> fmt::Result {
         match self {
             FormattedString::Raw(content) => write!(f, "{}", content),
             FormattedString::LeftAligned(box content, width) => {
-                write!(f, "{:<width$}", content.to_string(), width = width)
+                let content = content.to_string();
+                write!(f, "{}{}", content, spaces(padding(&content, *width)))
             }
             FormattedString::RightAligned(box content, width) => {
-                write!(f, "{:>width$}", content.to_string(), width = width)
+                let content = content.to_string();
+                write!(f, "{}{}", spaces(padding(&content, *width)), content)
             }
             FormattedString::CenterAligned(box content, width) => {
-                write!(f, "{:^width$}", content.to_string(), width = width)
+                let content = content.to_string();
+                let padding = padding(&content, *width);
+                write!(
+                    f,
+                    "{}{}{}",
+                    spaces(padding / 2),
+                    content,
+                    spaces(padding - padding / 2)
+                )
             }
             FormattedString::ColoredFg(box content, color) => write!(
                 f,
@@ -132,6 +448,21 @@ impl Display for FormattedString {
                 content.to_string(),
                 Bg(color::Reset)
             ),
+            FormattedString::Styled(box content, Attribute::Bold) => {
+                write!(f, "{}{}{}", style::Bold, content, style::NoFaint)
+            }
+            FormattedString::Styled(box content, Attribute::Italic) => {
+                write!(f, "{}{}{}", style::Italic, content, style::NoItalic)
+            }
+            FormattedString::Styled(box content, Attribute::Underline) => {
+                write!(f, "{}{}{}", style::Underline, content, style::NoUnderline)
+            }
+            FormattedString::Spans(spans) => {
+                for span in spans.iter() {
+                    write!(f, "{}", span)?;
+                }
+                Ok(())
+            }
         }
     }
 }
@@ -0,0 +1,94 @@
+use chrono::prelude::{Datelike, NaiveDate};
+use chrono::Duration;
+use termion::color::Rgb;
+use unicode_width::UnicodeWidthStr;
+
+use crate::formatted_string::FormattedString;
+use crate::project::*;
+use crate::theme::theme;
+
+static YELLOW: Rgb = Rgb(241, 196, 15);
+static GRAY: Rgb = Rgb(127, 140, 141);
+
+const LABEL_WIDTH: usize = 24;
+
+pub fn week_start(date: NaiveDate) -> NaiveDate {
+    date - Duration::days(date.weekday().num_days_from_monday() as i64)
+}
+
+fn label(text: &str) -> String {
+    let mut label = String::new();
+    for c in text.chars() {
+        if UnicodeWidthStr::width(format!("{}{}", label, c).as_str()) >= LABEL_WIDTH {
+            break;
+        }
+        label.push(c);
+    }
+    FormattedString::from(&label).left(LABEL_WIDTH).to_string()
+}
+
+fn scale(from: NaiveDate, days: usize) -> String {
+    let mut line = " ".repeat(LABEL_WIDTH);
+    let mut day = 0;
+    while day + 7 <= days {
+        let date = from + Duration::days(day as i64);
+        line.push_str(&format!("{:<7}", date.format("%m-%d").to_string()));
+        day += 7;
+    }
+    FormattedString::from(&line).fg(GRAY).to_string()
+}
+
+fn bar(
+    task: &Task,
+    start: NaiveDate,
+    due: NaiveDate,
+    from: NaiveDate,
+    today: NaiveDate,
+    days: usize,
+) -> String {
+    let (filled, empty, marker) = if theme().ascii {
+        ("=", " ", "|")
+    } else {
+        ("█", "·", "│")
+    };
+    let color = theme().state_color(task.state());
+    (0..days)
+        .map(|i| {
+            let date = from + Duration::days(i as i64);
+            if date >= start && date <= due {
+                FormattedString::from(filled).fg(color).to_string()
+            } else if date == today {
+                FormattedString::from(marker).fg(YELLOW).to_string()
+            } else if date.weekday().num_days_from_monday() == 0 {
+                FormattedString::from(empty).fg(GRAY).to_string()
+            } else {
+                String::from(" ")
+            }
+        })
+        .collect()
+}
+
+pub fn render(project: &Project, from: NaiveDate, today: NaiveDate, width: usize) -> Vec<String> {
+    let days = width.saturating_sub(LABEL_WIDTH).max(7) / 7 * 7;
+    let mut lines = vec![scale(from, days)];
+
+    let mut tasks: Vec<(&Task, NaiveDate, NaiveDate)> = project
+        .tasks
+        .iter()
+        .filter_map(|task| match (task.start(), task.due()) {
+            (Some(start), Some(due)) => Some((task, start.min(due), due.max(start))),
+            (None, Some(due)) => Some((task, due, due)),
+            _ => None,
+        })
+        .collect();
+    tasks.sort_by_key(|(_, start, _)| *start);
+
+    for (task, start, due) in tasks {
+        lines.push(format!(
+            "{}{}",
+            label(&task.description()),
+            bar(task, start, due, from, today, days)
+        ));
+    }
+    lines
+}
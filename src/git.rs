@@ -0,0 +1,107 @@
+use chrono::prelude::{DateTime, FixedOffset};
+use std::io::{Error, ErrorKind, Result};
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+use crate::project::Task;
+
+pub fn branch_name(task: &Task) -> String {
+    let slug = task
+        .description()
+        .to_lowercase()
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<&str>>()
+        .join("-");
+    let slug: String = slug.chars().take(40).collect();
+    let slug = slug.trim_end_matches('-');
+
+    match slug.is_empty() {
+        true => task.shortcode(),
+        false => format!("{}-{}", task.shortcode(), slug),
+    }
+}
+
+fn git(args: &[&str]) -> Result<bool> {
+    Command::new("git")
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .map(|status| status.success())
+}
+
+fn git_output(directory: &Path, args: &[&str]) -> Result<String> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(directory)
+        .args(args)
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()?;
+    match output.status.success() {
+        true => Ok(String::from_utf8_lossy(&output.stdout).into_owned()),
+        false => Err(Error::new(
+            ErrorKind::Other,
+            format!("git {} failed", args.join(" ")),
+        )),
+    }
+}
+
+pub struct Commit {
+    pub hash: String,
+    pub date: DateTime<FixedOffset>,
+    pub subject: String,
+}
+
+pub fn file_log(path: &Path, limit: usize) -> Vec<Commit> {
+    let (directory, file) = match (path.parent(), path.file_name()) {
+        (Some(directory), Some(file)) => (directory, file.to_string_lossy()),
+        _ => return vec![],
+    };
+    let limit = limit.to_string();
+    let args = ["log", "-n", &limit, "--format=%H%x1f%cI%x1f%s", "--", &file];
+    let log = git_output(directory, &args).unwrap_or_default();
+    log.lines()
+        .filter_map(|line| {
+            let mut fields = line.splitn(3, '\u{1f}');
+            let hash = fields.next()?.to_string();
+            let date = DateTime::parse_from_rfc3339(fields.next()?).ok()?;
+            Some(Commit {
+                hash: hash,
+                date: date,
+                subject: fields.next().unwrap_or_default().to_string(),
+            })
+        })
+        .collect()
+}
+
+pub fn file_at(path: &Path, hash: &str) -> Result<String> {
+    let directory = path.parent().unwrap_or(Path::new("."));
+    let file = path.file_name().unwrap_or_default().to_string_lossy();
+    git_output(directory, &["show", &format!("{}:./{}", hash, file)])
+}
+
+pub fn checkout_branch(name: &str) -> Result<()> {
+    let exists = git(&[
+        "rev-parse",
+        "--verify",
+        "--quiet",
+        &format!("refs/heads/{}", name),
+    ])?;
+    let checked_out = if exists {
+        git(&["checkout", name])?
+    } else {
+        git(&["checkout", "-b", name])?
+    };
+
+    if checked_out {
+        Ok(())
+    } else {
+        Err(Error::new(
+            ErrorKind::Other,
+            format!("Couldn't check out branch {}", name),
+        ))
+    }
+}
@@ -0,0 +1,195 @@
+use chrono::prelude::{Datelike, Local, NaiveDate};
+use chrono::Duration;
+use std::fs;
+use std::io::{Error, ErrorKind, Result};
+use termion::color::Rgb;
+use uuid::Uuid;
+
+use crate::columns::{project_row, ProjectColumn};
+use crate::formatted_string::FormattedString;
+use crate::locale::locale;
+use crate::project::Listable;
+use crate::theme::theme;
+
+static GREEN: Rgb = Rgb(46, 204, 113);
+static GRAY: Rgb = Rgb(127, 140, 141);
+static PURPLE: Rgb = Rgb(214, 162, 232);
+static YELLOW: Rgb = Rgb(241, 196, 15);
+
+const STRIP_LENGTH: usize = 14;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Frequency {
+    Daily,
+    Weekly,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Habit {
+    pub id: String,
+    pub name: String,
+    pub frequency: Frequency,
+    #[serde(default)]
+    pub done: Vec<NaiveDate>,
+}
+
+#[derive(Serialize, Deserialize, Default, Debug)]
+#[serde(default)]
+pub struct Habits {
+    pub habits: Vec<Habit>,
+    #[serde(skip)]
+    pub ephemeral: bool,
+}
+
+pub fn today() -> NaiveDate {
+    Local::now().date().naive_local()
+}
+
+impl Habit {
+    pub fn new(name: String, frequency: Frequency) -> Habit {
+        Habit {
+            id: Uuid::new_v4().to_string(),
+            name: name,
+            frequency: frequency,
+            done: vec![],
+        }
+    }
+
+    fn period(&self, date: NaiveDate) -> NaiveDate {
+        match self.frequency {
+            Frequency::Daily => date,
+            Frequency::Weekly => {
+                date - Duration::days(date.weekday().num_days_from_monday() as i64)
+            }
+        }
+    }
+
+    fn previous(&self, period: NaiveDate) -> NaiveDate {
+        match self.frequency {
+            Frequency::Daily => period - Duration::days(1),
+            Frequency::Weekly => period - Duration::weeks(1),
+        }
+    }
+
+    pub fn done_in(&self, date: NaiveDate) -> bool {
+        let period = self.period(date);
+        self.done.iter().any(|d| self.period(*d) == period)
+    }
+
+    pub fn toggle(&mut self, today: NaiveDate) {
+        if self.done_in(today) {
+            let period = self.period(today);
+            self.done = self
+                .done
+                .iter()
+                .cloned()
+                .filter(|d| self.period(*d) != period)
+                .collect();
+        } else {
+            self.done.push(today);
+            self.done.sort();
+        }
+    }
+
+    pub fn streak(&self, today: NaiveDate) -> usize {
+        let mut period = self.period(today);
+        if !self.done_in(period) {
+            period = self.previous(period);
+        }
+        let mut streak = 0;
+        while self.done_in(period) {
+            streak += 1;
+            period = self.previous(period);
+        }
+        streak
+    }
+
+    pub fn strip(&self, today: NaiveDate) -> String {
+        let (done, missed) = if theme().ascii {
+            ("#", ".")
+        } else {
+            ("■", "·")
+        };
+        let mut period = self.period(today);
+        let mut periods = vec![];
+        for _ in 0..STRIP_LENGTH {
+            periods.insert(0, period);
+            period = self.previous(period);
+        }
+        periods
+            .iter()
+            .map(|period| match self.done_in(*period) {
+                true => FormattedString::from(done).fg(GREEN).to_string(),
+                false => FormattedString::from(missed).fg(GRAY).to_string(),
+            })
+            .collect()
+    }
+
+    pub fn line(&self, today: NaiveDate) -> String {
+        let frequency = match self.frequency {
+            Frequency::Daily => &locale().daily,
+            Frequency::Weekly => &locale().weekly,
+        };
+        format!(
+            "{} {} {} {}",
+            FormattedString::from(&self.name).left(24),
+            FormattedString::from(frequency).left(8).fg(PURPLE),
+            FormattedString::from(&self.streak(today).to_string())
+                .right(4)
+                .fg(YELLOW),
+            self.strip(today)
+        )
+    }
+}
+
+impl Habits {
+    fn storage() -> Result<String> {
+        match dirs::home_dir() {
+            Some(path) => Ok(format!(
+                "{}{}",
+                path.to_str().unwrap(),
+                "/.tasks/habits.json"
+            )),
+            None => Err(Error::new(
+                ErrorKind::Other,
+                "Couldn't resolve your home directory",
+            )),
+        }
+    }
+
+    pub fn load() -> Result<Habits> {
+        match fs::read_to_string(Habits::storage()?) {
+            Ok(content) => Ok(serde_json::from_str(&content)?),
+            Err(ref e) if e.kind() == ErrorKind::NotFound => Ok(Habits::default()),
+            Err(e) => Err(e),
+        }
+    }
+
+    pub fn save(&self) -> Result<()> {
+        if self.ephemeral {
+            return Ok(());
+        }
+        let content = serde_json::to_string(self)?;
+        fs::write(Habits::storage()?, content)
+    }
+}
+
+impl Listable for Habits {
+    fn view(&self) -> String {
+        let today = today();
+        let done = self.habits.iter().filter(|h| h.done_in(today)).count();
+        project_row(|column, width| match column {
+            ProjectColumn::Tasks => FormattedString::from(&self.habits.len().to_string())
+                .fg(PURPLE)
+                .center(width),
+            ProjectColumn::Done => FormattedString::from(&done.to_string())
+                .fg(GREEN)
+                .center(width),
+            ProjectColumn::Description => FormattedString::from(&format!("♥ {}", locale().habits))
+                .left(width)
+                .fg(GREEN),
+            _ => FormattedString::from("").center(width),
+        })
+    }
+}
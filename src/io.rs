@@ -1,24 +1,238 @@
+use std::collections::{HashMap, VecDeque};
+use std::env;
 use std::fmt::Display;
-use std::io::{Result, Write};
+use std::fs;
+use std::io::{Error, ErrorKind, Read, Result, Write};
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
 use termion::cursor::Goto;
-use termion::event::Key;
+use termion::event::{Event, Key, MouseEvent};
 use termion::input::TermRead;
 use termion::raw::RawTerminal;
-use termion::screen::AlternateScreen;
+use termion::screen::{AlternateScreen, ToAlternateScreen, ToMainScreen};
+use uuid::Uuid;
+
+use crate::formatted_string::FormattedLine;
+use crate::locale::locale;
+
+const MACRO_LIMIT: usize = 10000;
+
+pub const TICK: Duration = Duration::from_secs(1);
+
+const ENTER_MOUSE: &str = "\x1b[?1000h\x1b[?1002h\x1b[?1015h\x1b[?1006h";
+const EXIT_MOUSE: &str = "\x1b[?1006l\x1b[?1015l\x1b[?1002l\x1b[?1000l";
+const ENTER_PASTE: &str = "\x1b[?2004h";
+const EXIT_PASTE: &str = "\x1b[?2004l";
+const PASTE_START: &[u8] = b"\x1b[200~";
+const PASTE_END: &[u8] = b"\x1b[201~";
+
+pub enum Input {
+    Key(Key),
+    Mouse(MouseEvent),
+    Paste(String),
+    Tick,
+}
+
+#[derive(Default)]
+pub struct Tty {
+    buffer: VecDeque<u8>,
+}
+
+impl Tty {
+    fn ready(&self, timeout: Duration) -> bool {
+        if !self.buffer.is_empty() {
+            return true;
+        }
+        let mut fd = libc::pollfd {
+            fd: libc::STDIN_FILENO,
+            events: libc::POLLIN,
+            revents: 0,
+        };
+        unsafe { libc::poll(&mut fd, 1, timeout.as_millis() as libc::c_int) > 0 }
+    }
+}
+
+impl Read for Tty {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        if self.buffer.is_empty() {
+            let mut chunk = [0u8; 1024];
+            let read = unsafe {
+                libc::read(
+                    libc::STDIN_FILENO,
+                    chunk.as_mut_ptr() as *mut libc::c_void,
+                    chunk.len(),
+                )
+            };
+            if read < 0 {
+                return Err(Error::last_os_error());
+            }
+            self.buffer.extend(&chunk[..read as usize]);
+        }
+        let wanted = match self.buffer.front() {
+            Some(b'\x1b') => 2,
+            _ => 1,
+        };
+        let count = wanted.min(buf.len()).min(self.buffer.len());
+        for (i, byte) in self.buffer.drain(..count).enumerate() {
+            buf[i] = byte;
+        }
+        Ok(count)
+    }
+}
 
 pub struct IO<'a> {
-    pub(crate) input: &'a mut std::io::Stdin,
+    pub(crate) input: Tty,
     pub(crate) output: &'a mut AlternateScreen<RawTerminal<std::io::Stdout>>,
+    pub(crate) clipboard: Option<arboard::Clipboard>,
+    pub(crate) macros: Macros,
+    pub(crate) mouse: bool,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Viewport {
+    pub row: u16,
+    pub column: u16,
+    pub width: u16,
+    pub height: u16,
+    pub offset: usize,
+}
+
+#[derive(Default)]
+pub struct Macros {
+    registers: HashMap<char, Vec<Key>>,
+    recording: Option<(char, Vec<Key>)>,
+    pending: VecDeque<Key>,
+}
+
+fn base64(content: &[u8]) -> String {
+    const ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut encoded = String::new();
+
+    for chunk in content.chunks(3) {
+        let bytes = [
+            chunk[0],
+            *chunk.get(1).unwrap_or(&0),
+            *chunk.get(2).unwrap_or(&0),
+        ];
+        let triple = (bytes[0] as u32) << 16 | (bytes[1] as u32) << 8 | bytes[2] as u32;
+        for i in 0..4 {
+            if i <= chunk.len() {
+                encoded.push(ALPHABET[(triple >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
 }
 
 impl<'a> IO<'a> {
     pub fn get_char(&mut self) -> Result<Key> {
-        match self.input.keys().next() {
-            Some(result) => result,
+        if let Some(key) = self.macros.pending.pop_front() {
+            return Ok(key);
+        }
+        match (&mut self.input).keys().next() {
+            Some(result) => {
+                let key = result?;
+                self.record(key.clone());
+                Ok(key)
+            }
             None => panic!("Couldn't get key from input"),
         }
     }
 
+    pub fn poll(&mut self, timeout: Duration) -> Result<Input> {
+        if let Some(key) = self.macros.pending.pop_front() {
+            return Ok(Input::Key(key));
+        }
+        let deadline = Instant::now() + timeout;
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if !self.input.ready(remaining) {
+                return Ok(Input::Tick);
+            }
+            match (&mut self.input).events().next() {
+                Some(Ok(Event::Key(key))) => {
+                    self.record(key.clone());
+                    return Ok(Input::Key(key));
+                }
+                Some(Ok(Event::Mouse(mouse))) => return Ok(Input::Mouse(mouse)),
+                Some(Ok(Event::Unsupported(ref bytes))) if bytes == PASTE_START => {
+                    let pasted = self.read_paste()?;
+                    pasted.chars().for_each(|c| self.record(Key::Char(c)));
+                    return Ok(Input::Paste(pasted));
+                }
+                Some(Ok(Event::Unsupported(_))) => continue,
+                Some(Err(e)) => return Err(e),
+                None => panic!("Couldn't get event from input"),
+            }
+        }
+    }
+
+    pub fn wait(&mut self) -> Result<Input> {
+        loop {
+            match self.poll(TICK)? {
+                Input::Tick => continue,
+                input => return Ok(input),
+            }
+        }
+    }
+
+    fn read_paste(&mut self) -> Result<String> {
+        let mut bytes = vec![];
+        let mut chunk = [0u8; 2];
+        while !bytes.ends_with(PASTE_END) {
+            match self.input.read(&mut chunk)? {
+                0 => break,
+                read => bytes.extend_from_slice(&chunk[..read]),
+            }
+        }
+        if bytes.ends_with(PASTE_END) {
+            bytes.truncate(bytes.len() - PASTE_END.len());
+        }
+        Ok(String::from_utf8_lossy(&bytes)
+            .replace("\r\n", "\n")
+            .replace('\r', "\n"))
+    }
+
+    fn record(&mut self, key: Key) {
+        if let Some((_, keys)) = self.macros.recording.as_mut() {
+            keys.push(key);
+        }
+    }
+
+    pub fn recording(&self) -> Option<char> {
+        self.macros
+            .recording
+            .as_ref()
+            .map(|(register, _)| *register)
+    }
+
+    pub fn start_recording(&mut self, register: char) {
+        self.macros.recording = Some((register, vec![]));
+    }
+
+    pub fn stop_recording(&mut self) -> Option<char> {
+        let (register, mut keys) = self.macros.recording.take()?;
+        keys.pop();
+        self.macros.registers.insert(register, keys);
+        Some(register)
+    }
+
+    pub fn replay(&mut self, register: char, times: usize) -> bool {
+        let keys = match self.macros.registers.get(&register) {
+            Some(keys) => keys.clone(),
+            None => return false,
+        };
+        for _ in 0..times {
+            if self.macros.pending.len() + keys.len() > MACRO_LIMIT {
+                break;
+            }
+            self.macros.pending.extend(keys.iter().cloned());
+        }
+        true
+    }
+
     pub fn write<D: Display>(&mut self, content: D) -> Result<()> {
         write!(self.output, "{}", content)?;
         self.output.flush()
@@ -29,6 +243,35 @@ impl<'a> IO<'a> {
         self.write(content)
     }
 
+    pub fn render_list(
+        &mut self,
+        rows: &[FormattedLine],
+        viewport: Viewport,
+        focus: Option<usize>,
+    ) -> Result<usize> {
+        let (width, height) = (viewport.width as usize, viewport.height as usize);
+        let offset = match focus {
+            Some(focus) if focus >= viewport.offset + height => focus + 1 - height,
+            Some(focus) if focus < viewport.offset => focus,
+            _ => viewport.offset,
+        };
+
+        let mut shown = 0;
+        for i in 0..height {
+            let line = match rows.get(offset + i) {
+                Some(row) => {
+                    shown += 1;
+                    row.truncate(width)
+                }
+                None => FormattedLine::new(),
+            };
+            let padding = " ".repeat(width.saturating_sub(line.width()));
+            let row = viewport.row + i as u16;
+            self.write_in_pos(row, viewport.column, format!("{}{}", line, padding))?;
+        }
+        Ok(shown)
+    }
+
     pub fn erase(&mut self, row: u16, column: u16) -> Result<()> {
         self.write_in_pos(row, column, ' ')?;
         self.write(Goto(column, row))
@@ -42,6 +285,126 @@ impl<'a> IO<'a> {
         self.write(termion::cursor::Hide)
     }
 
+    pub fn bell(&mut self) -> Result<()> {
+        self.write('\x07')
+    }
+
+    pub fn notify(&mut self, message: &str) -> Result<()> {
+        self.write(format!("\x1b]9;{}\x07", message.replace('\x07', "")))
+    }
+
+    pub fn copy(&mut self, content: &str) -> Result<()> {
+        let copied = match self.clipboard.as_mut() {
+            Some(clipboard) => clipboard.set_text(content).is_ok(),
+            None => false,
+        };
+
+        if copied {
+            Ok(())
+        } else {
+            self.write(format!("\x1b]52;c;{}\x07", base64(content.as_bytes())))
+        }
+    }
+
+    pub fn paste(&mut self) -> Option<String> {
+        self.clipboard.as_mut().and_then(|c| c.get_text().ok())
+    }
+
+    pub fn enable_paste(&mut self) -> Result<()> {
+        self.write(ENTER_PASTE)
+    }
+
+    pub fn disable_paste(&mut self) -> Result<()> {
+        self.write(EXIT_PASTE)
+    }
+
+    pub fn set_mouse(&mut self, enabled: bool) -> Result<()> {
+        self.mouse = enabled;
+        match enabled {
+            true => self.write(ENTER_MOUSE),
+            false => self.write(EXIT_MOUSE),
+        }
+    }
+
+    pub fn suspend(&mut self) -> Result<()> {
+        self.disable_paste()?;
+        if self.mouse {
+            self.write(EXIT_MOUSE)?;
+        }
+        self.write(ToMainScreen)?;
+        self.show_cursor()?;
+        self.output.suspend_raw_mode()
+    }
+
+    pub fn resume(&mut self) -> Result<()> {
+        self.output.activate_raw_mode()?;
+        self.write(ToAlternateScreen)?;
+        if self.mouse {
+            self.write(ENTER_MOUSE)?;
+        }
+        self.enable_paste()?;
+        self.hide_cursor()
+    }
+
+    pub fn edit_text(&mut self, content: &str) -> Result<String> {
+        let path = env::temp_dir().join(format!("rtasks-{}.md", Uuid::new_v4()));
+        fs::write(&path, content)?;
+
+        let editor = env::var("VISUAL")
+            .or_else(|_| env::var("EDITOR"))
+            .unwrap_or_else(|_| String::from("vi"));
+
+        let mut editor = Command::new(editor);
+        editor.arg(&path);
+        self.external(editor, None, false)?;
+
+        let edited = fs::read_to_string(&path);
+        fs::remove_file(&path)?;
+        edited
+    }
+
+    pub fn run(&mut self, command: &str, environment: &[(&str, String)]) -> Result<bool> {
+        let mut shell = Command::new("sh");
+        shell
+            .arg("-c")
+            .arg(command)
+            .envs(environment.iter().map(|(name, value)| (name, value)));
+        self.external(shell, None, false)
+    }
+
+    pub fn pipe(&mut self, command: &str, content: &str) -> Result<bool> {
+        let mut shell = Command::new("sh");
+        shell.arg("-c").arg(command).stdin(Stdio::piped());
+        self.external(shell, Some(content), true)
+    }
+
+    fn external(&mut self, mut command: Command, input: Option<&str>, pause: bool) -> Result<bool> {
+        self.suspend()?;
+        let status = command.spawn().and_then(|mut child| {
+            if let (Some(input), Some(mut stdin)) = (input, child.stdin.take()) {
+                match stdin.write_all(input.as_bytes()) {
+                    Err(e) if e.kind() != ErrorKind::BrokenPipe => {
+                        drop(stdin);
+                        child.wait()?;
+                        return Err(e);
+                    }
+                    _ => {}
+                }
+            }
+            child.wait()
+        });
+        let success = status.as_ref().map(|s| s.success()).unwrap_or(false);
+        let paused = match pause || !success {
+            true => self
+                .write(format!("\n{}", locale().press_enter))
+                .and_then(|_| self.get_char().map(|_| ())),
+            false => Ok(()),
+        };
+        self.resume()?;
+        paused?;
+        status.map(|_| success)
+    }
+
     pub fn clear_screen(&mut self) -> Result<()> {
         self.write(termion::clear::All)
     }
@@ -0,0 +1,98 @@
+use std::io::Result;
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use crate::theme::theme;
+
+type Work = Box<dyn FnOnce() -> Result<()> + Send>;
+
+pub const BUSY_TICK: Duration = Duration::from_millis(100);
+
+const SPINNER: [&str; 10] = ["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"];
+const ASCII_SPINNER: [&str; 4] = ["|", "/", "-", "\\"];
+
+pub struct Done {
+    pub name: &'static str,
+    pub result: Result<()>,
+}
+
+pub struct Jobs {
+    queue: Option<Sender<(&'static str, Work)>>,
+    done: Receiver<Done>,
+    worker: Option<JoinHandle<()>>,
+    running: usize,
+    frame: usize,
+}
+
+impl Jobs {
+    pub fn start() -> Jobs {
+        let (queue, work) = channel::<(&'static str, Work)>();
+        let (report, done) = channel();
+        let worker = thread::spawn(move || {
+            for (name, job) in work {
+                let result = job();
+                if let Err(e) = &result {
+                    tracing::error!(job = name, error = %e, "job failed");
+                }
+                let _ = report.send(Done {
+                    name: name,
+                    result: result,
+                });
+            }
+        });
+        Jobs {
+            queue: Some(queue),
+            done: done,
+            worker: Some(worker),
+            running: 0,
+            frame: 0,
+        }
+    }
+
+    pub fn spawn<F>(&mut self, name: &'static str, work: F)
+    where
+        F: FnOnce() -> Result<()> + Send + 'static,
+    {
+        let sent = match &self.queue {
+            Some(queue) => queue.send((name, Box::new(work))).is_ok(),
+            None => false,
+        };
+        if sent {
+            self.running += 1;
+        } else {
+            tracing::error!(job = name, "job worker is gone");
+        }
+    }
+
+    pub fn finished(&mut self) -> Vec<Done> {
+        let done: Vec<Done> = self.done.try_iter().collect();
+        self.running -= done.len();
+        done
+    }
+
+    pub fn running(&self) -> usize {
+        self.running
+    }
+
+    pub fn spinner(&mut self) -> Option<&'static str> {
+        if self.running == 0 {
+            return None;
+        }
+        let frames: &[&'static str] = match theme().ascii {
+            true => &ASCII_SPINNER,
+            false => &SPINNER,
+        };
+        self.frame = (self.frame + 1) % frames.len();
+        Some(frames[self.frame])
+    }
+}
+
+impl Drop for Jobs {
+    fn drop(&mut self) {
+        self.queue.take();
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+    }
+}
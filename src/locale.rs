@@ -0,0 +1,290 @@
+use std::sync::{Arc, RwLock};
+
+static LOCALE: RwLock<Option<Arc<Locale>>> = RwLock::new(None);
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct Locale {
+    pub date_format: String,
+    pub date_time_format: String,
+
+    pub state_todo: String,
+    pub state_ongoing: String,
+    pub state_waiting: String,
+    pub state_done: String,
+
+    pub header_tasks: String,
+    pub header_todo: String,
+    pub header_ongoing: String,
+    pub header_waiting: String,
+    pub header_done: String,
+    pub header_complete: String,
+    pub header_remaining: String,
+    pub header_description: String,
+    pub header_state: String,
+    pub header_progress: String,
+    pub header_assignee: String,
+    pub header_created_at: String,
+    pub header_when: String,
+    pub header_notes: String,
+    pub header_estimate: String,
+    pub header_id: String,
+    pub header_points: String,
+    pub header_sprint: String,
+    pub header_milestone: String,
+    pub header_due: String,
+
+    pub confirm_delete: String,
+    pub confirm_all_states: String,
+    pub confirm_state: String,
+    pub deleted: String,
+    pub moved_all: String,
+    pub undone: String,
+    pub imported: String,
+    pub imported_skipped: String,
+    pub similar: String,
+    pub filter: String,
+    pub view: String,
+    pub recent: String,
+    pub more_done: String,
+    pub contexts: String,
+    pub no_contexts: String,
+    pub stats: String,
+    pub activity: String,
+    pub event_empty_description: String,
+    pub event_out_of_order: String,
+    pub event_unchanged: String,
+    pub event_uncorrectable: String,
+    pub event_read_only: String,
+    pub celebrate_task: String,
+    pub celebrate_project: String,
+    pub event_removed: String,
+    pub event_corrected: String,
+    pub confirm_retract: String,
+    pub keep_idle: String,
+    pub favorite_added: String,
+    pub unknown_command: String,
+    pub snapshots: String,
+    pub no_snapshots: String,
+    pub viewing_snapshot: String,
+    pub move_usage: String,
+    pub transferred: String,
+    pub confirm_transfer: String,
+    pub split_part: String,
+    pub merge_nothing_marked: String,
+    pub confirm_merge: String,
+    pub merged: String,
+    pub split_link: String,
+    pub split_done: String,
+    pub no_substitutions: String,
+    pub more_substitutions: String,
+    pub substitute_preview: String,
+    pub substitute_confirm: String,
+    pub substituted: String,
+    pub favorite_removed: String,
+    pub favorites_full: String,
+    pub config_reloaded: String,
+    pub config_invalid: String,
+    pub idle_paused: String,
+    pub correct_event: String,
+    pub command_failed: String,
+    pub recording: String,
+    pub recorded: String,
+    pub no_macro: String,
+    pub snapshot_diff: String,
+    pub diff_totals: String,
+    pub job_failed: String,
+    pub created: String,
+    pub waiting_on: String,
+    pub waiting: String,
+    pub review_title: String,
+    pub review_keys: String,
+    pub review_nothing: String,
+    pub reviewed: String,
+    pub habits: String,
+    pub no_habits: String,
+    pub daily: String,
+    pub daily_reset: String,
+    pub weekly: String,
+    pub estimate: String,
+    pub over_estimate: String,
+    pub under_estimate: String,
+    pub sort_projects: String,
+    pub order_alphabetical: String,
+    pub order_open_tasks: String,
+    pub order_activity: String,
+    pub milestones: String,
+    pub archive: String,
+    pub no_archive: String,
+    pub open_link: String,
+    pub send_tasks: String,
+    pub results: String,
+    pub references: String,
+    pub no_references: String,
+    pub no_results: String,
+    pub yes_no: String,
+    pub press_enter: String,
+    pub format_plain: String,
+    pub format_json: String,
+    pub stats_week: String,
+    pub stats_month: String,
+    pub stats_created: String,
+    pub stats_completed: String,
+    pub stats_oldest: String,
+    pub stats_cycle_time: String,
+    pub stats_cycle_count: String,
+}
+
+impl Default for Locale {
+    fn default() -> Locale {
+        let text = |s: &str| String::from(s);
+        Locale {
+            date_format: text("%Y-%m-%d"),
+            date_time_format: text("%Y-%m-%d %H:%M:%S"),
+
+            state_todo: text("TODO"),
+            state_ongoing: text("ONGOING"),
+            state_waiting: text("WAITING"),
+            state_done: text("DONE"),
+
+            header_tasks: text("Tasks"),
+            header_todo: text("To Do"),
+            header_ongoing: text("Ongoing"),
+            header_waiting: text("Waiting"),
+            header_done: text("Done"),
+            header_complete: text("Complete"),
+            header_remaining: text("Remaining"),
+            header_description: text("Description"),
+            header_state: text("State"),
+            header_progress: text("Progress"),
+            header_assignee: text("Assignee"),
+            header_created_at: text("Created At"),
+            header_when: text("When"),
+            header_notes: text("Notes"),
+            header_estimate: text("Estimate"),
+            header_id: text("ID"),
+            header_points: text("Points"),
+            header_sprint: text("Sprint"),
+            header_milestone: text("Milestone"),
+            header_due: text("Due"),
+
+            confirm_delete: text("Are you sure you want to delete this row?"),
+            confirm_all_states: text("Move every task of this project to {}?"),
+            confirm_state: text("Move this task to {}?"),
+            deleted: text("Deleted '{}' - press u to undo"),
+            moved_all: text("Moved {} tasks - press u to undo"),
+            undone: text("Undone"),
+            imported: text("Imported {} tasks"),
+            imported_skipped: text("Imported {} tasks, skipped {} duplicates"),
+            similar: text("Similar to \"{}\" - [j]ump to it, [a]dd anyway?"),
+            filter: text("Filter: {}"),
+            view: text("View: {}"),
+            recent: text("Recent"),
+            more_done: text("…and {} more DONE (D to show all)"),
+            contexts: text("Contexts"),
+            no_contexts: text("No @contexts in any task"),
+            stats: text("Stats: {}"),
+            activity: text("Activity"),
+            event_empty_description: text("A task needs a description"),
+            event_out_of_order: text("Rejected an event older than the task history"),
+            event_unchanged: text("Nothing changed"),
+            event_uncorrectable: text("That event can't be corrected"),
+            event_read_only: text("Snapshots are read-only, the change was discarded"),
+            celebrate_task: text("Done: {}"),
+            celebrate_project: text("{} is complete!"),
+            event_removed: text("removed"),
+            event_corrected: text("corrected"),
+            confirm_retract: text("Remove this event from the task history?"),
+            config_reloaded: text("Config reloaded"),
+            config_invalid: text("Config not reloaded: {}"),
+            merge_nothing_marked: text("Mark the tasks to merge with space first"),
+            confirm_merge: text("Merge {} marked tasks into {}?"),
+            merged: text("Merged into {}, press u to undo"),
+            split_part: text(
+                "Part {} of the split, the first replaces the task (empty line to finish)",
+            ),
+            split_link: text("Link the new tasks to {}?"),
+            split_done: text("Split {} into {} tasks"),
+            move_usage: text("Usage: move <todo|ongoing|waiting|done> <project>"),
+            transferred: text("Moved {} tasks to {}"),
+            confirm_transfer: text("Move every {} task to {}?"),
+            snapshots: text("Snapshots (newest first)"),
+            no_snapshots: text("No git commits of the database found"),
+            viewing_snapshot: text("Snapshot {} (read-only)"),
+            unknown_command: text("Unknown command: {}"),
+            no_substitutions: text("No task descriptions contain {}"),
+            more_substitutions: text("… and {} more"),
+            substitute_preview: text("Rename {} tasks"),
+            substitute_confirm: text("Apply the rename to {} tasks?"),
+            substituted: text("Renamed {} tasks"),
+            favorite_added: text("Added {} to favorites"),
+            favorite_removed: text("Removed {} from favorites"),
+            favorites_full: text("The favorites bar holds {} projects"),
+            keep_idle: text("You were away for {}. Keep that time tracked?"),
+            idle_paused: text("Paused {} ongoing tasks while idle"),
+            correct_event: text("Correct to"),
+            command_failed: text("Command failed: {}"),
+            recording: text("recording macro {}, Q to stop"),
+            recorded: text("Recorded macro {}, replay it with \"{}"),
+            no_macro: text("No macro recorded in {}"),
+            snapshot_diff: text("Changes since {}"),
+            diff_totals: text("{} added, {} removed, {} changed"),
+            job_failed: text("Background {} failed: {}"),
+            created: text("Created"),
+            waiting_on: text("waiting on {} for {}"),
+            waiting: text("Waiting"),
+            review_title: text("Waiting review {} of {}"),
+            review_keys: text("t to do, o ongoing, d done, any other key skips, q stops"),
+            review_nothing: text("Nothing has been waiting for more than a week"),
+            reviewed: text("Updated {} waiting tasks"),
+            habits: text("Habits"),
+            no_habits: text("No habits yet - press a to add one"),
+            daily: text("Daily"),
+            daily_reset: text("A new day started, daily tasks are back to TODO"),
+            weekly: text("Weekly"),
+            estimate: text("{} estimated, {} tracked"),
+            over_estimate: text("{} over"),
+            under_estimate: text("{} under"),
+            sort_projects: text("Sort projects"),
+            order_alphabetical: text("Alphabetically"),
+            order_open_tasks: text("By open tasks"),
+            order_activity: text("By recent activity"),
+            milestones: text("Milestones: {}"),
+            archive: text("Archive: {}"),
+            no_archive: text("No archived tasks"),
+            open_link: text("Open which link?"),
+            send_tasks: text("Send tasks as"),
+            results: text("Results for \"{}\""),
+            references: text("Links of {}"),
+            no_references: text("No linked tasks"),
+            no_results: text("No results for \"{}\""),
+            yes_no: text(" [y/N]"),
+            press_enter: text("Press Enter to return"),
+            format_plain: text("Plain text"),
+            format_json: text("JSON"),
+            stats_week: text("7 days"),
+            stats_month: text("30 days"),
+            stats_created: text("Created"),
+            stats_completed: text("Completed"),
+            stats_oldest: text("Oldest open"),
+            stats_cycle_time: text("Avg cycle time"),
+            stats_cycle_count: text("({} tasks)"),
+        }
+    }
+}
+
+pub fn fill(template: &str, value: &str) -> String {
+    template.replacen("{}", value, 1)
+}
+
+pub fn init(locale: Locale) {
+    *LOCALE.write().unwrap() = Some(Arc::new(locale));
+}
+
+pub fn locale() -> Arc<Locale> {
+    if let Some(locale) = &*LOCALE.read().unwrap() {
+        return locale.clone();
+    }
+    init(Locale::default());
+    locale()
+}
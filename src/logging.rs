@@ -0,0 +1,30 @@
+use std::fs::{self, OpenOptions};
+use std::io::{Error, ErrorKind, Result};
+use std::sync::Mutex;
+use tracing::Level;
+
+fn directory() -> Result<String> {
+    match dirs::home_dir() {
+        Some(path) => Ok(format!("{}{}", path.to_str().unwrap(), "/.tasks/logs")),
+        None => Err(Error::new(
+            ErrorKind::Other,
+            "Couldn't resolve your home directory",
+        )),
+    }
+}
+
+pub fn init() -> Result<()> {
+    let directory = directory()?;
+    fs::create_dir_all(&directory)?;
+    let file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(format!("{}/rtasks.log", directory))?;
+
+    tracing_subscriber::fmt()
+        .with_writer(Mutex::new(file))
+        .with_max_level(Level::INFO)
+        .with_ansi(false)
+        .try_init()
+        .map_err(|e| Error::new(ErrorKind::Other, e.to_string()))
+}
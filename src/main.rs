@@ -6,44 +6,160 @@ extern crate serde_derive;
 extern crate serde;
 extern crate serde_json;
 
+mod accessible;
+mod calendar;
+mod celebrate;
+mod cli;
+mod columns;
+mod config;
 mod database;
+mod demo;
+mod detail;
+mod diff;
+mod filter;
 mod formatted_string;
+mod gantt;
+mod git;
+mod habits;
 mod io;
+mod jobs;
+mod locale;
+mod logging;
+mod markdown;
+mod mqtt;
+mod onboarding;
 mod project;
+mod query;
+mod search;
+mod serve;
+mod session;
+mod snapshots;
+mod stats;
+mod stress;
+mod substitute;
+mod theme;
+mod timeline;
 
+use crate::celebrate::Celebration;
+use crate::columns::{columns, dividers};
+use crate::config::*;
 use crate::database::*;
+use crate::detail::*;
+use crate::filter::*;
 use crate::formatted_string::*;
+use crate::habits::{Frequency, Habit, Habits};
 use crate::io::*;
+use crate::locale::{fill, locale};
 use crate::project::*;
+use crate::query::{Field, Op, Operand, Query};
+use crate::search::Hit;
+use crate::session::{Session, FAVORITES_LIMIT};
+use crate::snapshots::Snapshot;
+use crate::substitute::Substitution;
+use crate::theme::{theme, FocusStyle, Palette};
 
+use chrono::prelude::{NaiveDate, Utc};
+use std::collections::HashSet;
+use std::io::stdout;
+use std::io::Error;
+use std::io::ErrorKind;
 use std::io::Result;
-use std::io::{stdin, stdout};
-use termion::color::Rgb;
-use termion::event::Key;
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+use termion::color::{AnsiValue, Rgb};
+use termion::event::{Key, MouseButton, MouseEvent};
 use termion::raw::IntoRawMode;
 use termion::screen::AlternateScreen;
 
 const HEADER_OFFSET: u16 = 2;
+const NOTICE_DURATION: Duration = Duration::from_secs(3);
+const LONG_WAIT_DAYS: i64 = 7;
 const DIV_COLOR: Rgb = Rgb(0, 150, 230);
 const YELLOW: Rgb = Rgb(241, 196, 15);
 const PINK: Rgb = Rgb(200, 0, 150);
 const BLUE: Rgb = Rgb(52, 152, 219);
 
 fn main() -> Result<()> {
-    let mut database = Database::load()?;
+    let result = run();
+    if let Err(e) = &result {
+        tracing::error!(error = %e, "exited with an error");
+    }
+    result
+}
+
+fn run() -> Result<()> {
+    let mut config = Config::load()?;
+    logging::init()?;
+    let mut args: Vec<String> = std::env::args().skip(1).collect();
+    if args.is_empty() && onboarding::needed() {
+        onboarding::run()?;
+        config = Config::load()?;
+    }
+    if let Some(i) = args.iter().position(|arg| arg == "--theme") {
+        let name = args.get(i + 1).cloned().unwrap_or_default();
+        theme::override_palette(Palette::from_name(&name)?);
+        args.drain(i..(i + 2).min(args.len()));
+    }
+    let dry_run = match args.iter().position(|arg| arg == "--dry-run") {
+        Some(i) => {
+            args.remove(i);
+            true
+        }
+        None => false,
+    };
+
+    locale::init(config.locale.clone());
+    theme::init(config.theme.clone());
+    columns::init(config.columns.clone());
+    database::init(config.database());
+    let sandbox = args.first().map(String::as_str) == Some("demo");
+    let mut database = match sandbox {
+        true => {
+            args.remove(0);
+            Database::in_memory(demo::projects())?
+        }
+        false => Database::open(dry_run)?,
+    };
+    database.set_origin(config.me(), config.hostname());
+    database.set_wip_limit(config.wip.limit);
+    if !database.dry_run() {
+        database.set_mqtt(config.mqtt.clone());
+    }
+    if let Some(days) = config.archive.done_after_days {
+        let archived = database.archive_done(days)?;
+        if dry_run {
+            for (project, description) in archived.iter() {
+                println!("- [{}] {}", database.project(*project).title(), description);
+            }
+            println!("Would archive {} tasks", archived.len());
+        }
+    }
+
+    if dry_run && args.is_empty() {
+        return Ok(());
+    }
+    tracing::info!(args = ?args, dry_run, "started");
+    if !args.is_empty() {
+        return cli::run(&args, &config, &mut database);
+    }
 
+    database.start_jobs();
     let mut io = IO {
-        input: &mut stdin(),
+        input: Tty::default(),
         output: &mut AlternateScreen::from(stdout().into_raw_mode().unwrap()),
+        clipboard: arboard::Clipboard::new().ok(),
+        macros: Macros::default(),
+        mouse: false,
     };
 
-    handle_user_input(&mut io, &mut database)
+    handle_user_input(&mut io, config, &mut database)
 }
 
 #[derive(Copy, Clone)]
 enum Context {
     Project(u16, u16),
     Task(u16, u16),
+    Detail(u16, u16),
 }
 
 impl Context {
@@ -51,6 +167,7 @@ impl Context {
         match self {
             Context::Project(row, _) => (row - HEADER_OFFSET - 1) as usize,
             Context::Task(row, _) => (row - HEADER_OFFSET - 1) as usize,
+            Context::Detail(row, _) => (row - HEADER_OFFSET - 1) as usize,
         }
     }
 
@@ -70,6 +187,20 @@ impl Context {
                     None
                 }
             }
+            Context::Detail(row, len) => {
+                if len > 0 {
+                    Some(Context::Detail(row - 1, len - 1))
+                } else {
+                    None
+                }
+            }
+        }
+    }
+
+    fn is_detail(self) -> bool {
+        match self {
+            Context::Detail(_, _) => true,
+            _ => false,
         }
     }
 
@@ -77,9 +208,22 @@ impl Context {
         match self {
             Context::Project(_, length) => length as usize,
             Context::Task(_, length) => length as usize,
+            Context::Detail(_, length) => length as usize,
         }
     }
 
+    fn scroll(self, offset: usize, capacity: usize) -> usize {
+        let index = self.idx();
+        let offset = if index < offset {
+            index
+        } else if index >= offset + capacity {
+            index + 1 - capacity
+        } else {
+            offset
+        };
+        offset.min(self.length().saturating_sub(capacity))
+    }
+
     fn jump_to(self, index: usize) -> Option<Context> {
         self.jump(index as i16 - self.idx() as i16)
     }
@@ -101,43 +245,154 @@ impl Context {
                     None
                 }
             }
+            Context::Detail(row, len) => {
+                if index + distance >= 0 && index + distance < len as i16 {
+                    Some(Context::Detail((row as i16 + distance) as u16, len))
+                } else {
+                    None
+                }
+            }
         }
     }
 
     fn pane_div(self, terminal_width: u16) -> String {
         let columns = match self {
-            Context::Project(_, _) => vec![0, 8, 16, 26, 33],
-            Context::Task(_, _) => vec![0, 10, terminal_width - 24],
+            Context::Project(_, _) => dividers(&columns().project_widths()),
+            Context::Task(_, _) => dividers(&columns().task_widths()),
+            Context::Detail(_, _) => vec![0, 13],
         };
-        let raw_div = (0..terminal_width - 3)
-            .map(|i| if columns.contains(&i) { "╋" } else { "━" })
+        let raw_div = (0..terminal_width.saturating_sub(4))
+            .map(|i| {
+                if columns.contains(&i) {
+                    theme().cross()
+                } else {
+                    theme().horizontal()
+                }
+            })
             .collect::<String>();
 
         FormattedString::from(&raw_div).fg(DIV_COLOR).to_string()
     }
 }
 
-fn numbered_row<'a>(row: u16, focused_row: u16, content: &Listable) -> String {
+fn numbered_row<'a>(row: u16, focused_row: u16, content: &Listable) -> FormattedLine {
     let row_number = if row > HEADER_OFFSET {
         (row - HEADER_OFFSET).to_string()
     } else {
         " ".to_string()
     };
+    styled_row(&row_number, row, focused_row, content)
+}
+
+fn continued_row(row: u16, focused_row: u16, content: &Listable) -> FormattedLine {
+    styled_row(" ", row, focused_row, content)
+}
 
+fn styled_row(row_number: &str, row: u16, focused_row: u16, content: &Listable) -> FormattedLine {
     let cursor = FormattedString::from(&row_number).right(3);
-    let formatted_content = FormattedString::from(&content.view());
+    let mut formatted_content = FormattedString::from(&content.view());
+    if theme().striping && row > HEADER_OFFSET && (row - HEADER_OFFSET) % 2 == 0 {
+        formatted_content = formatted_content.bg(AnsiValue::grayscale(3));
+    }
 
-    if row == focused_row {
-        cursor.fg(YELLOW).concat(&formatted_content.focused())
-    } else {
-        cursor.concat(&formatted_content)
+    let line = FormattedLine::new();
+    if row != focused_row {
+        return line.push(cursor).push(formatted_content);
+    }
+
+    match theme().focus {
+        FocusStyle::Background => line
+            .push(cursor.fg(YELLOW))
+            .push(formatted_content.focused()),
+        FocusStyle::Marker => line
+            .push(FormattedString::from(&format!(">{:>2}", row_number)).fg(YELLOW))
+            .push(formatted_content),
+        FocusStyle::Bold => line.push(cursor.fg(YELLOW)).push(formatted_content.bold()),
+    }
+}
+
+fn draw_rows<'a>(
+    io: &mut IO<'a>,
+    rows: &[&Listable],
+    focused_row: u16,
+    viewport: Viewport,
+) -> Result<usize> {
+    let lines: Vec<FormattedLine> = rows
+        .iter()
+        .enumerate()
+        .skip(viewport.offset)
+        .take(viewport.height as usize)
+        .map(|(i, content)| numbered_row(i as u16 + HEADER_OFFSET + 1, focused_row, *content))
+        .collect();
+    io.render_list(
+        &lines,
+        Viewport {
+            offset: 0,
+            ..viewport
+        },
+        None,
+    )
+}
+
+fn draw_scroll_indicators<'a>(
+    io: &mut IO<'a>,
+    offset: usize,
+    shown: usize,
+    len: usize,
+    capacity: u16,
+) -> Result<()> {
+    let (terminal_width, _) = termion::terminal_size()?;
+    let below = len.saturating_sub(offset + shown);
+    if offset == 0 && below == 0 {
+        return Ok(());
+    }
+
+    if offset > 0 {
+        let more = format!(" ↑ {} more ", offset);
+        let column = terminal_width.saturating_sub(more.chars().count() as u16 + 2);
+        io.write_in_pos(
+            HEADER_OFFSET,
+            column,
+            FormattedString::from(&more).fg(YELLOW),
+        )?;
+    }
+    if below > 0 {
+        let more = format!("↓ {} more", below);
+        let row = HEADER_OFFSET + capacity + 1;
+        io.write_in_pos(row, 4, FormattedString::from(&more).fg(YELLOW))?;
+    }
+
+    let track = capacity as usize;
+    let thumb = (track * shown / len).max(1);
+    let start = (track - thumb) * offset / len.saturating_sub(shown).max(1);
+    for i in 0..track {
+        let mark = if i >= start && i < start + thumb {
+            FormattedString::from(theme().scroll_thumb()).fg(YELLOW)
+        } else {
+            FormattedString::from(theme().scroll_track()).fg(DIV_COLOR)
+        };
+        io.write_in_pos(HEADER_OFFSET + 1 + i as u16, terminal_width, mark)?;
+    }
+    Ok(())
+}
+
+fn confirmed<'a>(
+    config: &Config,
+    operation: Operation,
+    row: u16,
+    question: &str,
+    io: &mut IO<'a>,
+) -> Result<bool> {
+    match config.confirm.requires(operation) {
+        true => confirm(row, question, io),
+        false => Ok(true),
     }
 }
 
-fn confirm_deletion<'a>(row: u16, io: &mut IO<'a>) -> Result<bool> {
-    let question = FormattedString::from("Are you sure you want to delete this row?").fg(YELLOW);
+fn confirm<'a>(row: u16, question: &str, io: &mut IO<'a>) -> Result<bool> {
+    let question = FormattedString::from(question).fg(YELLOW);
     io.write_in_pos(row, 1, question)?;
-    io.write(FormattedString::from(" [y/N]").fg(BLUE))?;
+    io.write(FormattedString::from(&locale().yes_no).fg(BLUE))?;
 
     match io.get_char()? {
         Key::Char('y') | Key::Char('Y') => Ok(true),
@@ -145,170 +400,2297 @@ fn confirm_deletion<'a>(row: u16, io: &mut IO<'a>) -> Result<bool> {
     }
 }
 
-fn handle_user_input<'a>(io: &mut IO<'a>, db: &mut Database) -> Result<()> {
+fn draw_page<'a>(io: &mut IO<'a>, title: &str, lines: Vec<String>) -> Result<()> {
+    draw_list_page(io, title, lines, None)
+}
+
+fn draw_list_page<'a>(
+    io: &mut IO<'a>,
+    title: &str,
+    lines: Vec<String>,
+    focus: Option<usize>,
+) -> Result<()> {
+    let (terminal_width, terminal_height) = termion::terminal_size()?;
     io.clear_screen()?;
-    io.hide_cursor()?;
-    let mut context = Context::Project(HEADER_OFFSET + 1, db.project_count());
-    let mut project_context = Context::Project(HEADER_OFFSET + 1, db.project_count());
+    io.write_in_pos(1, 1, FormattedString::from(title).fg(YELLOW))?;
+    let lines: Vec<FormattedLine> = lines
+        .iter()
+        .map(|line| FormattedLine::new().push(FormattedString::from(line)))
+        .collect();
+    let viewport = Viewport {
+        row: HEADER_OFFSET + 1,
+        column: 1,
+        width: terminal_width,
+        height: terminal_height.saturating_sub(HEADER_OFFSET),
+        offset: 0,
+    };
+    io.render_list(&lines, viewport, focus).map(|_| ())
+}
+
+fn show_page<'a>(io: &mut IO<'a>, title: &str, lines: Vec<String>) -> Result<()> {
+    draw_page(io, title, lines)?;
+    io.get_char().map(|_| ())
+}
+
+fn reload_config(db: &mut Database) -> Result<(Config, Vec<(char, String)>)> {
+    let config = Config::load()?;
+    let bindings = config.bindings()?;
+    locale::init(config.locale.clone());
+    theme::init(config.theme.clone());
+    columns::init(config.columns.clone());
+    db.set_wip_limit(config.wip.limit);
+    Ok((config, bindings))
+}
+
+fn show_popup<'a>(io: &mut IO<'a>, title: &str, lines: Vec<String>) -> Result<()> {
+    draw_popup(io, title, lines)?;
+    io.get_char().map(|_| ())
+}
+
+fn draw_popup<'a>(io: &mut IO<'a>, title: &str, lines: Vec<String>) -> Result<()> {
     let (terminal_width, terminal_height) = termion::terminal_size()?;
+    let widest = lines
+        .iter()
+        .map(|line| FormattedString::from(line).width())
+        .chain(std::iter::once(FormattedString::from(title).width() + 2))
+        .max()
+        .unwrap_or(0);
+    let inner = (widest + 2).min((terminal_width as usize).saturating_sub(2));
+    let height = (lines.len() + 2).min(terminal_height as usize);
+    let column = (terminal_width as usize).saturating_sub(inner + 2) as u16 / 2 + 1;
+    let row = (terminal_height as usize).saturating_sub(height) as u16 / 2 + 1;
+    let [top_left, top_right, bottom_left, bottom_right] = theme().corners();
+    let border = |text: &str| FormattedString::from(text).fg(DIV_COLOR);
 
-    loop {
-        io.clear_screen()?;
+    let label = FormattedString::from(&format!(" {} ", title)).truncate(inner);
+    let rest = inner - label.width();
+    io.write_in_pos(row, column, border(top_left))?;
+    io.write(label.fg(YELLOW))?;
+    io.write(border(&theme().horizontal().repeat(rest)))?;
+    io.write(border(top_right))?;
 
-        match context {
-            Context::Project(focused_row, _) => {
-                io.write_in_pos(1, 1, numbered_row(0, 3, &Project::header()))?;
-                io.write_in_pos(2, 1, numbered_row(1, 4, &context.pane_div(terminal_width)))?;
-                for (i, project) in db.projects().enumerate() {
-                    let row = i as u16 + HEADER_OFFSET + 1;
-                    io.write_in_pos(row, 1, numbered_row(row, focused_row, project))?
-                }
-            }
-            Context::Task(focused_row, _) => {
-                io.write_in_pos(1, 1, numbered_row(0, 3, &Task::header()))?;
-                io.write_in_pos(2, 1, numbered_row(1, 4, &context.pane_div(terminal_width)))?;
-                for (i, task) in db.tasks(project_context.idx()).enumerate() {
-                    let row = i as u16 + HEADER_OFFSET + 1;
-                    io.write_in_pos(row, 1, numbered_row(row, focused_row, task))?
-                }
-            }
+    for (i, line) in lines.iter().take(height.saturating_sub(2)).enumerate() {
+        let content = FormattedString::from(line).truncate(inner.saturating_sub(2));
+        let padding = " ".repeat(inner.saturating_sub(2) - content.width());
+        io.write_in_pos(row + 1 + i as u16, column, border(theme().vertical()))?;
+        io.write(format!(" {}{} ", content, padding))?;
+        io.write(border(theme().vertical()))?;
+    }
+
+    let bottom = format!(
+        "{}{}{}",
+        bottom_left,
+        theme().horizontal().repeat(inner),
+        bottom_right
+    );
+    io.write_in_pos(row + height as u16 - 1, column, border(&bottom))
+}
+
+fn show_habits<'a>(
+    io: &mut IO<'a>,
+    config: &Config,
+    habits: &mut Habits,
+    terminal_height: u16,
+) -> Result<()> {
+    let mut focused = 0;
+    loop {
+        let today = habits::today();
+        let mut lines: Vec<String> = habits
+            .habits
+            .iter()
+            .enumerate()
+            .map(|(i, habit)| match i == focused {
+                true => FormattedString::from(&habit.line(today))
+                    .focused()
+                    .to_string(),
+                false => habit.line(today),
+            })
+            .collect();
+        if lines.is_empty() {
+            lines.push(
+                FormattedString::from(&locale().no_habits)
+                    .fg(DIV_COLOR)
+                    .to_string(),
+            );
         }
+        draw_page(io, &locale().habits, lines)?;
 
+        let len = habits.habits.len();
         match io.get_char()? {
-            Key::Char('q') => break,
-            Key::Char('j') | Key::Down => context = context.jump(1).unwrap_or(context),
-            Key::Char('k') | Key::Up => context = context.jump(-1).unwrap_or(context),
-            Key::Char('g') => context = context.jump_to(0).unwrap_or(context),
-            Key::Char('G') => context = context.jump_to(context.length() - 1).unwrap_or(context),
-            Key::Char(c @ 'J') | Key::Char(c @ 'K') => {
-                context = swap_rows(context, project_context.idx(), c, db)?;
+            Key::Char('j') | Key::Down if focused + 1 < len => focused += 1,
+            Key::Char('k') | Key::Up if focused > 0 => focused -= 1,
+            Key::Char(' ') | Key::Char('\n') if focused < len => {
+                habits.habits[focused].toggle(today);
+                habits.save()?;
             }
-            Key::Char('\n') => enter_context(&mut context, &mut project_context, db),
-            Key::Esc => leave_context(&mut context, &mut project_context),
-            Key::Char(change @ '>') | Key::Char(change @ '<') => {
-                context = change_status(context, project_context.idx(), db, change)?;
+            Key::Char('a') => {
+                if let Some(name) = prompt(io, terminal_height)? {
+                    let frequencies = vec![locale().daily.clone(), locale().weekly.clone()];
+                    let frequency = match pick(io, &name, &frequencies)? {
+                        Some(0) => Frequency::Daily,
+                        Some(_) => Frequency::Weekly,
+                        None => continue,
+                    };
+                    habits.habits.push(Habit::new(name, frequency));
+                    habits.save()?;
+                    focused = habits.habits.len() - 1;
+                }
             }
-            Key::Char('-') => match confirm_deletion(terminal_height, io)? {
-                true => context = delete_row(context, project_context, db)?,
-                _ => {}
-            },
-            Key::Char('+') => context = add_row(context, project_context, terminal_height, db, io)?,
+            Key::Char('-') if focused < len => {
+                let question = &locale().confirm_delete;
+                if confirmed(config, Operation::Delete, terminal_height, question, io)? {
+                    habits.habits.remove(focused);
+                    habits.save()?;
+                    focused = focused.min(len.saturating_sub(2));
+                }
+            }
+            Key::Esc | Key::Char('q') => return Ok(()),
             _ => {}
         }
     }
-    io.clear_screen()?;
-    io.show_cursor()?;
-    Ok(())
 }
 
-fn swap_rows(context: Context, project: usize, cmd: char, db: &mut Database) -> Result<Context> {
-    if let Some(next_context) = context.jump(if cmd == 'J' { 1 } else { -1 }) {
-        match context {
-            Context::Project(_, _) => db.swap_projects(context.idx(), next_context.idx())?,
-            Context::Task(_, _) => db.swap_tasks(project, context.idx(), next_context.idx())?,
-        }
-        Ok(next_context)
-    } else {
-        Ok(context)
+fn show_gantt<'a>(io: &mut IO<'a>, project: &Project, terminal_width: u16) -> Result<()> {
+    let today = habits::today();
+    let mut from = gantt::week_start(today) - chrono::Duration::weeks(1);
+    loop {
+        let lines = gantt::render(project, from, today, terminal_width as usize);
+        draw_page(io, project.title(), lines)?;
+
+        from = match io.get_char()? {
+            Key::Char('h') | Key::Left | Key::Char('<') => from - chrono::Duration::weeks(1),
+            Key::Char('l') | Key::Right | Key::Char('>') => from + chrono::Duration::weeks(1),
+            Key::Char('g') => gantt::week_start(today) - chrono::Duration::weeks(1),
+            _ => return Ok(()),
+        };
     }
 }
 
-fn get_input_line<'a>(io: &mut IO<'a>, row: u16) -> Result<Option<String>> {
-    let mut description = String::from("");
-    let mut result = Ok(None);
+fn show_project<'a>(
+    io: &mut IO<'a>,
+    db: &Database,
+    project: usize,
+    size: (u16, u16),
+) -> Result<()> {
+    let (terminal_width, terminal_height) = size;
+    let width = (terminal_width as usize).saturating_sub(2).max(10);
+    let capacity = terminal_height.saturating_sub(HEADER_OFFSET + 1) as usize;
+    let mut lines: Vec<String> = db
+        .project(project)
+        .description
+        .lines()
+        .flat_map(|line| match line.trim().is_empty() {
+            true => vec![String::from("")],
+            false => wrap(line, width),
+        })
+        .collect();
 
-    io.show_cursor()?;
+    let notes = &db.project(project).notes;
+    if !notes.trim().is_empty() {
+        lines.push(String::from(""));
+        lines.push(
+            FormattedString::from(&locale().header_notes)
+                .fg(BLUE)
+                .to_string(),
+        );
+        lines.extend(notes.lines().flat_map(|line| wrap(line, width)));
+    }
+
+    let remaining = capacity.saturating_sub(lines.len() + 2);
+    let activity = db.project_activity(project, remaining);
+    if !activity.is_empty() {
+        lines.push(String::from(""));
+        lines.push(
+            FormattedString::from(&locale().activity)
+                .fg(BLUE)
+                .to_string(),
+        );
+        lines.extend(activity.iter().map(|entry| {
+            timeline::activity_line(
+                db.project(project).title(),
+                db.task(entry.project, entry.task),
+                entry.date_time,
+                entry.event.as_ref(),
+            )
+        }));
+    }
+    lines.truncate(capacity);
+    show_page(io, db.project(project).title(), lines)
+}
 
+fn show_calendar<'a>(io: &mut IO<'a>, db: &Database) -> Result<Option<(usize, usize)>> {
+    let days = db.calendar();
+    let mut selected = habits::today();
     loop {
-        let c = io.get_char();
+        let title = selected.format("%B %Y").to_string();
+        draw_page(io, &title, calendar::render(selected, &days))?;
 
-        match c? {
-            Key::Esc => break,
+        selected = match io.get_char()? {
+            Key::Char('h') | Key::Left => selected - chrono::Duration::days(1),
+            Key::Char('l') | Key::Right => selected + chrono::Duration::days(1),
+            Key::Char('k') | Key::Up => selected - chrono::Duration::weeks(1),
+            Key::Char('j') | Key::Down => selected + chrono::Duration::weeks(1),
+            Key::Char('<') => calendar::shift_month(selected, -1),
+            Key::Char('>') => calendar::shift_month(selected, 1),
             Key::Char('\n') => {
-                result = Ok(Some(description));
-                break;
-            }
-            Key::Backspace => {
-                if let Some(_) = description.pop() {
-                    io.erase(row, 4 + description.chars().count() as u16)?
+                let tasks = db.tasks_on(selected);
+                if tasks.is_empty() {
+                    continue;
                 }
+                let title = selected.format(&locale().date_format).to_string();
+                return Ok(pick(io, &title, &task_summaries(&tasks, db))?.map(|i| tasks[i]));
             }
-            Key::Char(c) => {
-                description.push(c);
-                io.write_in_pos(row, 3 + description.chars().count() as u16, c)?
-            }
-            _ => {}
-        }
+            _ => return Ok(None),
+        };
     }
-
-    io.hide_cursor()?;
-    result
 }
 
-fn enter_context(context: &mut Context, project_context: &mut Context, db: &Database) {
-    if let Context::Project(_, _) = context {
-        *project_context = *context;
-        *context = Context::Task(HEADER_OFFSET + 1, db.task_count(project_context.idx()));
-    }
-}
+fn pick<'a>(io: &mut IO<'a>, title: &str, options: &[String]) -> Result<Option<usize>> {
+    let mut focused = 0;
+    loop {
+        let lines = options
+            .iter()
+            .enumerate()
+            .map(|(i, option)| {
+                let number = FormattedString::from(&(i + 1).to_string()).fg(BLUE);
+                match i == focused {
+                    true => format!("{} {}", number, FormattedString::from(option).focused()),
+                    false => format!("{} {}", number, option),
+                }
+            })
+            .collect();
+        draw_list_page(io, title, lines, Some(focused))?;
 
-fn leave_context(context: &mut Context, project_context: &mut Context) {
-    if let Context::Task(_, _) = context {
-        *context = *project_context
+        match io.get_char()? {
+            Key::Char(c @ '1'..='9') if (c as usize - '1' as usize) < options.len() => {
+                return Ok(Some(c as usize - '1' as usize))
+            }
+            Key::Char('j') | Key::Down if focused + 1 < options.len() => focused += 1,
+            Key::Char('k') | Key::Up if focused > 0 => focused -= 1,
+            Key::Char('j') | Key::Down | Key::Char('k') | Key::Up => {}
+            Key::Char('\n') if !options.is_empty() => return Ok(Some(focused)),
+            _ => return Ok(None),
+        }
     }
 }
 
-fn add_row<'a>(
-    context: Context,
-    project_context: Context,
-    terminal_height: u16,
-    db: &mut Database,
+fn draw_debug_overlay<'a>(
     io: &mut IO<'a>,
-) -> Result<Context> {
-    io.write_in_pos(terminal_height, 1, FormattedString::from("-> ").fg(PINK))?;
-    let description = get_input_line(io, terminal_height)?;
+    frame: Instant,
+    last_save: Option<std::time::Duration>,
+    width: u16,
+) -> Result<()> {
+    let last_save = match last_save {
+        Some(latency) => format!("{:.1}ms", latency.as_secs_f64() * 1000.0),
+        None => String::from("-"),
+    };
+    let text = format!(
+        " frame {:.1}ms | save {} ",
+        frame.elapsed().as_secs_f64() * 1000.0,
+        last_save
+    );
+    let column = width.saturating_sub(text.len() as u16) + 1;
+    io.write_in_pos(1, column, FormattedString::from(&text).fg(YELLOW).focused())
+}
 
-    if let Some(description) = description {
-        match context {
-            Context::Task(_, size) => {
-                let task = Task::new(description);
-                let task_index = db.add_task(project_context.idx(), task)?.unwrap() as u16;
-                Ok(Context::Task(task_index + HEADER_OFFSET + 1, size + 1))
+fn handle_user_input<'a>(io: &mut IO<'a>, mut config: Config, live: &mut Database) -> Result<()> {
+    io.clear_screen()?;
+    io.hide_cursor()?;
+    io.enable_paste()?;
+    io.set_mouse(config.mouse)?;
+    let saved_views = config.views()?;
+    let mut bindings = config.bindings()?;
+    let mut config_modified = Config::modified();
+    let (mut session, mut habits) = match live.dry_run() {
+        true => (
+            Session {
+                ephemeral: true,
+                ..Session::default()
+            },
+            demo::habits(),
+        ),
+        false => (Session::load()?, Habits::load()?),
+    };
+    let rows = project_rows(live, saved_views.len() + 2);
+    let mut context = Context::Project(HEADER_OFFSET + 1, rows);
+    let mut project_context = Context::Project(HEADER_OFFSET + 1, rows);
+    let mut task_context = Context::Task(HEADER_OFFSET + 1, 0);
+    let mut filter = Filter::default();
+    let mut toast: Option<String> = None;
+    let mut celebration: Option<Celebration> = None;
+    let mut notice: Option<(String, Instant)> = None;
+    let mut last_input = Instant::now();
+    let mut idle: Option<(Instant, Vec<(String, usize)>)> = None;
+    let mut marked: HashSet<String> = HashSet::new();
+    let (mut project_scroll, mut task_scroll, mut detail_scroll) = (0, 0, 0);
+    let mut dragged: Option<(usize, usize)> = None;
+    let mut overlay = false;
+    let mut snapshot: Option<Database> = None;
+    let mut opened: Option<Database> = None;
+    let mut closed = false;
+    let mut live_state = None;
+    let (terminal_width, terminal_height) = termion::terminal_size()?;
+
+    loop {
+        let frame = Instant::now();
+        io.clear_screen()?;
+
+        if let Some(opened) = opened.take() {
+            live_state = Some((
+                (context, project_context, task_context),
+                (project_scroll, task_scroll, detail_scroll),
+                filter.clone(),
+            ));
+            let rows = project_rows(&opened, saved_views.len() + 2);
+            context = Context::Project(HEADER_OFFSET + 1, rows);
+            project_context = context;
+            task_context = Context::Task(HEADER_OFFSET + 1, 0);
+            project_scroll = 0;
+            filter = Filter::default();
+            marked.clear();
+            session.ephemeral = true;
+            habits.ephemeral = true;
+            snapshot = Some(opened);
+        }
+        if closed {
+            closed = false;
+            snapshot = None;
+            if let Some((contexts, scrolls, live_filter)) = live_state.take() {
+                (context, project_context, task_context) = contexts;
+                (project_scroll, task_scroll, detail_scroll) = scrolls;
+                filter = live_filter;
             }
-            Context::Project(_, size) => {
-                db.add_project(Project::new(description))?;
-                Ok(Context::Project(size + HEADER_OFFSET + 1, size + 1))
+            marked.clear();
+            session = Session::load()?;
+            habits = Habits::load()?;
+        }
+        let db: &mut Database = match snapshot.as_mut() {
+            Some(snapshot) => snapshot,
+            None => &mut *live,
+        };
+
+        let project = project_context.idx();
+        let visible = match context {
+            Context::Project(_, _) => vec![],
+            _ => db.visible_tasks(project, &filter),
+        };
+
+        for done in db.take_finished() {
+            if let Err(e) = done.result {
+                let message = fill(&locale().job_failed, done.name);
+                toast = Some(fill(&message, &e.to_string()));
             }
         }
-    } else {
-        Ok(context)
-    }
-}
+        if let Some(rejected) = db.take_rejected() {
+            toast = Some(rejected.to_string());
+            if let Context::Task(_, _) = context {
+                context = task_context_at(db, project, &filter, None, context);
+            }
+        }
+        let mut touched = db.take_touched();
+        if config.celebrate.enabled {
+            celebration = celebrate::detect(db, &touched);
+            if let Some(celebration) = &celebration {
+                match config.celebrate.alert {
+                    Alert::Bell => io.bell()?,
+                    Alert::Notify => io.notify(&celebration.message())?,
+                    Alert::None => {}
+                }
+            }
+        }
+        if let Context::Detail(_, _) = context {
+            touched.push(db.task(project, visible[task_context.idx()]).id.clone());
+        }
+        if touched
+            .iter()
+            .fold(false, |changed, id| session.touch(id) || changed)
+        {
+            session.save()?;
+        }
 
-fn delete_row(context: Context, project_context: Context, db: &mut Database) -> Result<Context> {
-    match context.drop() {
-        Some(new_context @ Context::Project(_, _)) => {
-            db.remove_project(context.idx())?;
-            Ok(new_context)
+        let mut views: Vec<View> = saved_views
+            .iter()
+            .map(|(name, query)| View::new(name, query, db))
+            .collect();
+        let recent = session
+            .recent
+            .iter()
+            .filter_map(|id| db.find_task(id))
+            .collect();
+        views.push(View::from_tasks(&locale().recent, recent, db));
+        views.push(View::from_tasks(&locale().waiting, db.waiting_tasks(), db));
+
+        let favorites = favorite_projects(&session, db);
+        let footer = 2 + favorites.len().min(1) as u16;
+        let capacity = terminal_height
+            .saturating_sub(HEADER_OFFSET + footer)
+            .max(1);
+        let scroll = match context {
+            Context::Project(_, _) => &mut project_scroll,
+            Context::Task(_, _) => &mut task_scroll,
+            Context::Detail(_, _) => &mut detail_scroll,
+        };
+        *scroll = context.scroll(*scroll, capacity as usize);
+        let mut offset = *scroll;
+        let viewport = Viewport {
+            row: HEADER_OFFSET + 1,
+            column: 1,
+            width: terminal_width,
+            height: capacity,
+            offset: 0,
+        };
+
+        let shown = match context {
+            Context::Project(focused_row, _) => {
+                io.write_in_pos(1, 1, numbered_row(0, 3, &Project::header()))?;
+                io.write_in_pos(2, 1, numbered_row(1, 4, &context.pane_div(terminal_width)))?;
+                let rows: Vec<&Listable> = db
+                    .projects()
+                    .map(|project| project as &Listable)
+                    .chain(views.iter().map(|view| view as &Listable))
+                    .chain(std::iter::once(&habits as &Listable))
+                    .collect();
+                draw_rows(io, &rows, focused_row, Viewport { offset, ..viewport })?
+            }
+            Context::Task(focused_row, _) => {
+                let tasks: Vec<&Task> = visible.iter().map(|t| db.task(project, *t)).collect();
+                columns::fit_tasks(&tasks);
+                let heights: Vec<usize> = tasks
+                    .iter()
+                    .map(|task| 1 + task.continuations().len())
+                    .collect();
+                let focused = context.idx().min(heights.len().saturating_sub(1));
+                while offset < focused
+                    && heights[offset..=focused].iter().sum::<usize>() > capacity as usize
+                {
+                    offset += 1;
+                }
+                task_scroll = offset;
+                io.write_in_pos(1, 1, numbered_row(0, 3, &Task::header()))?;
+                io.write_in_pos(2, 1, numbered_row(1, 4, &context.pane_div(terminal_width)))?;
+                let mut lines = vec![];
+                let mut marks = vec![];
+                let mut shown = 0;
+                for (i, task) in tasks.iter().enumerate().skip(offset) {
+                    if lines.len() >= capacity as usize {
+                        break;
+                    }
+                    let row = i as u16 + HEADER_OFFSET + 1;
+                    if marked.contains(&task.id) {
+                        marks.push(HEADER_OFFSET + 1 + lines.len() as u16);
+                    }
+                    lines.push(numbered_row(row, focused_row, *task));
+                    for continuation in task.continuations() {
+                        lines.push(continued_row(row, focused_row, &continuation));
+                    }
+                    shown += 1;
+                }
+                let hidden = db.hidden_done_count(project, &filter);
+                if hidden > 0 {
+                    let more = fill(&locale().more_done, &hidden.to_string());
+                    lines.push(
+                        FormattedLine::new()
+                            .push(FormattedString::from("   "))
+                            .push(FormattedString::from(&more).fg(DIV_COLOR)),
+                    );
+                }
+                if narrow() && !theme().wrap && !visible.is_empty() {
+                    let task = db.task(project, visible[context.idx()]);
+                    let width = (terminal_width as usize).saturating_sub(4).max(10);
+                    lines.push(FormattedLine::new());
+                    lines.extend(wrap(&task.description(), width).iter().map(|line| {
+                        FormattedLine::new()
+                            .push(FormattedString::from("   "))
+                            .push(FormattedString::from(line))
+                    }));
+                }
+                io.render_list(&lines, viewport, None)?;
+                for mark in marks {
+                    io.write_in_pos(mark, 1, FormattedString::from("*").fg(PINK))?;
+                }
+                if let Some(summary) = filter.summary() {
+                    let status = FormattedString::from(&fill(&locale().filter, &summary)).fg(BLUE);
+                    io.write_in_pos(terminal_height, 1, status)?;
+                }
+                shown
+            }
+            Context::Detail(focused_row, _) => {
+                let task = db.task(project, visible[task_context.idx()]);
+                let reference = db.reference(project, visible[task_context.idx()]);
+                let header = timeline::header(task, &reference);
+                io.write_in_pos(1, 1, numbered_row(0, 3, &header))?;
+                io.write_in_pos(2, 1, numbered_row(1, 4, &context.pane_div(terminal_width)))?;
+                let detail_rows = detail_rows(task);
+                let rows: Vec<&Listable> = detail_rows.iter().map(|row| row as &Listable).collect();
+                draw_rows(io, &rows, focused_row, Viewport { offset, ..viewport })?
+            }
+        };
+        draw_scroll_indicators(io, offset, shown, context.length(), capacity)?;
+
+        if !favorites.is_empty() {
+            let current = match context {
+                Context::Project(_, _) => focused_project(context, db),
+                _ => Some(project),
+            };
+            draw_favorites(io, &favorites, current, terminal_height - 1, db)?;
         }
-        Some(new_context @ Context::Task(_, _)) => {
-            db.remove_task(project_context.idx(), context.idx())?;
-            Ok(new_context)
+
+        if let Some(message) = toast.take() {
+            let line = FormattedString::from(&message).fg(YELLOW).to_string();
+            notice = Some((line, Instant::now() + NOTICE_DURATION));
+        }
+        if let Some(celebration) = celebration.take() {
+            let line = celebration.banner(terminal_width);
+            notice = Some((line, Instant::now() + NOTICE_DURATION));
+        }
+        if notice
+            .as_ref()
+            .map_or(false, |(_, until)| Instant::now() >= *until)
+        {
+            notice = None;
+        }
+        if let Some((line, _)) = &notice {
+            io.write_in_pos(terminal_height, 1, termion::clear::CurrentLine)?;
+            io.write_in_pos(terminal_height, 1, line)?;
+        }
+        if overlay {
+            draw_debug_overlay(io, frame, db.last_save(), terminal_width)?;
+        }
+        let mut right = terminal_width;
+        if let Some(spinner) = db.spinner() {
+            io.write_in_pos(
+                terminal_height,
+                right,
+                FormattedString::from(spinner).fg(BLUE),
+            )?;
+            right = right.saturating_sub(2);
+        }
+        if let Some(label) = db.snapshot_label() {
+            let label = fill(&locale().viewing_snapshot, label);
+            let column = right.saturating_sub(label.chars().count() as u16) + 1;
+            io.write_in_pos(
+                terminal_height,
+                column,
+                FormattedString::from(&label).fg(BLUE),
+            )?;
+            right = column.saturating_sub(2);
+        }
+        if let Some(register) = io.recording() {
+            let recording = fill(&locale().recording, &register.to_string());
+            let column = right.saturating_sub(recording.chars().count() as u16) + 1;
+            io.write_in_pos(
+                terminal_height,
+                column,
+                FormattedString::from(&recording).fg(PINK),
+            )?;
+        }
+
+        let tick = match db.jobs_running() {
+            true => jobs::BUSY_TICK,
+            false => TICK,
+        };
+        let key = match io.poll(tick)? {
+            Input::Key(key) => key,
+            Input::Mouse(mouse) => {
+                if let Context::Project(_, _) = context {
+                    context = drag_project(context, mouse, offset, &mut dragged, db)?;
+                }
+                continue;
+            }
+            Input::Tick => {
+                let modified = Config::modified();
+                if modified != config_modified && db.snapshot_label().is_none() {
+                    config_modified = modified;
+                    toast = Some(match reload_config(db) {
+                        Ok((reloaded, reloaded_bindings)) => {
+                            if reloaded.mouse != config.mouse {
+                                io.set_mouse(reloaded.mouse)?;
+                            }
+                            config = reloaded;
+                            bindings = reloaded_bindings;
+                            locale().config_reloaded.clone()
+                        }
+                        Err(e) => fill(&locale().config_invalid, &e.to_string()),
+                    });
+                }
+                let threshold = config
+                    .idle
+                    .threshold()
+                    .filter(|_| db.snapshot_label().is_none());
+                if let (None, Some(threshold)) = (&idle, threshold) {
+                    let away = last_input.elapsed();
+                    if away >= threshold {
+                        let away =
+                            chrono::Duration::from_std(away).unwrap_or(chrono::Duration::zero());
+                        let paused = db.pause_ongoing(Utc::now() - away)?;
+                        if !paused.is_empty() {
+                            toast = Some(fill(&locale().idle_paused, &paused.len().to_string()));
+                            idle = Some((last_input, paused));
+                        }
+                    }
+                }
+                if db.reset_daily()? {
+                    toast = Some(locale().daily_reset.clone());
+                }
+                continue;
+            }
+            Input::Paste(_) => continue,
+        };
+        notice = None;
+        last_input = Instant::now();
+        if let Some((since, paused)) = idle.take() {
+            let away =
+                chrono::Duration::from_std(since.elapsed()).unwrap_or(chrono::Duration::zero());
+            let question = fill(&locale().keep_idle, &timeline::humanize(away));
+            io.write_in_pos(terminal_height, 1, termion::clear::CurrentLine)?;
+            let keep = confirm(terminal_height, &question, io)?;
+            db.resume_paused(&paused, keep)?;
+            continue;
         }
-        None => Ok(context),
-    }
-}
 
-fn change_status(context: Context, project: usize, db: &mut Database, c: char) -> Result<Context> {
-    if let Context::Task(_, len) = context {
-        let current_state = db.task_state(project, context.idx());
+        match key {
+            Key::Char(c) if bindings.iter().any(|(key, _)| *key == c) => {
+                let command = &bindings.iter().find(|(key, _)| *key == c).unwrap().1;
+                let project = match context {
+                    Context::Project(_, _) => focused_project(context, db),
+                    _ => Some(project),
+                };
+                if let Some(project) = project {
+                    let task = match context {
+                        Context::Detail(_, _) => visible.get(task_context.idx()).cloned(),
+                        _ => focused_task(context, &visible),
+                    };
+                    let environment = command_environment(db, project, task)?;
+                    tracing::info!(key = %c, command = %command, "running binding");
+                    match io.run(command, &environment) {
+                        Ok(true) => {}
+                        Ok(false) => toast = Some(fill(&locale().command_failed, command)),
+                        Err(e) => toast = Some(e.to_string()),
+                    }
+                }
+            }
+            Key::Char('q') if db.snapshot_label().is_some() => closed = true,
+            Key::Char('q') => break,
+            Key::Char('Q') if io.recording().is_some() => {
+                if let Some(register) = io.stop_recording() {
+                    let register = register.to_string();
+                    toast = Some(fill(&fill(&locale().recorded, &register), &register));
+                }
+            }
+            Key::Char('Q') => {
+                if let Key::Char(register @ 'a'..='z') = io.get_char()? {
+                    io.start_recording(register);
+                }
+            }
+            Key::Char('"') => {
+                let mut times = 0;
+                let register = loop {
+                    match io.get_char()? {
+                        Key::Char(c) if c.is_ascii_digit() => {
+                            times = times * 10 + c.to_digit(10).unwrap() as usize
+                        }
+                        key => break key,
+                    }
+                };
+                if let Key::Char(register @ 'a'..='z') = register {
+                    if !io.replay(register, times.max(1)) {
+                        toast = Some(fill(&locale().no_macro, &register.to_string()));
+                    }
+                }
+            }
+            Key::F(12) => overlay = !overlay,
+            Key::Char('j') | Key::Down => context = context.jump(1).unwrap_or(context),
+            Key::Char('k') | Key::Up => context = context.jump(-1).unwrap_or(context),
+            Key::Char('g') => context = context.jump_to(0).unwrap_or(context),
+            Key::Char('G') => context = context.jump_to(context.length() - 1).unwrap_or(context),
+            Key::Char(c @ 'J') | Key::Char(c @ 'K') => {
+                context = swap_rows(context, project, &visible, c, db)?;
+            }
+            Key::Char('m') => {
+                let position = prompt(io, terminal_height)?.and_then(|p| p.trim().parse().ok());
+                if let Some(position) = position.filter(|position: &usize| *position > 0) {
+                    context = move_row(context, project, &visible, &filter, position - 1, db)?;
+                }
+            }
+            Key::Char('@') => {
+                let names = db.contexts();
+                if names.is_empty() {
+                    toast = Some(locale().no_contexts.clone());
+                } else {
+                    let chips: Vec<String> =
+                        names.iter().map(|name| format!("@{}", name)).collect();
+                    if let Some(i) = pick(io, &locale().contexts, &chips)? {
+                        let query =
+                            Query::Compare(Field::Context, Op::Eq, Operand::Text(names[i].clone()));
+                        let view = View::new(&chips[i], &query, db);
+                        let title = fill(&locale().view, &view.name);
+                        if let Some(i) = pick(io, &title, &task_summaries(&view.tasks, db))? {
+                            let (project, task) = view.tasks[i];
+                            let contexts = (&mut context, &mut project_context, &mut task_context);
+                            jump_to_task(contexts, &mut filter, views.len(), project, task, db);
+                        }
+                    }
+                }
+            }
+            Key::Char('\n') if focused_habits(context) => {
+                show_habits(io, &config, &mut habits, terminal_height)?;
+            }
+            Key::Char('\n') if focused_view(context, db).is_some() => {
+                let view = &views[focused_view(context, db).unwrap()];
+                let title = fill(&locale().view, &view.name);
+                if let Some(i) = pick(io, &title, &task_summaries(&view.tasks, db))? {
+                    let (project, task) = view.tasks[i];
+                    let contexts = (&mut context, &mut project_context, &mut task_context);
+                    jump_to_task(contexts, &mut filter, views.len(), project, task, db);
+                }
+            }
+            Key::Char('\n') => {
+                if let Some(project) = focused_project(context, db) {
+                    filter = restore_filter(&session, &config, db.project(project));
+                }
+                let contexts = (&mut context, &mut project_context, &mut task_context);
+                enter_context(contexts, &visible, &filter, db)
+            }
+            Key::Char('*') => {
+                if let Some(project) = focused_project(context, db) {
+                    let project = db.project(project);
+                    toast = Some(match session.toggle_favorite(&project.id) {
+                        Some(true) => fill(&locale().favorite_added, project.title()),
+                        Some(false) => fill(&locale().favorite_removed, project.title()),
+                        None => fill(&locale().favorites_full, &FAVORITES_LIMIT.to_string()),
+                    });
+                    session.save()?;
+                }
+            }
+            Key::Char(c @ '1'..='9') => {
+                let slot = c.to_digit(10).unwrap() as usize - 1;
+                if let Some(&project) = favorite_projects(&session, db).get(slot) {
+                    let rows = project_rows(db, views.len());
+                    context = Context::Project(project as u16 + HEADER_OFFSET + 1, rows);
+                    filter = restore_filter(&session, &config, db.project(project));
+                    let contexts = (&mut context, &mut project_context, &mut task_context);
+                    enter_context(contexts, &visible, &filter, db)
+                }
+            }
+            Key::Esc => leave_context(&mut context, &mut project_context, &mut task_context),
+            Key::Char(change @ '>') | Key::Char(change @ '<')
+                if focused_project(context, db).is_some() =>
+            {
+                let state = match change {
+                    '>' => State::DONE,
+                    _ => State::TODO,
+                };
+                let question = fill(&locale().confirm_all_states, &theme().state_label(state));
+                if confirmed(&config, Operation::Bulk, terminal_height, &question, io)? {
+                    let moved = db.set_all_task_states(context.idx(), state)?;
+                    toast = Some(fill(&locale().moved_all, &moved.to_string()));
+                }
+            }
+            Key::Char(change @ '>') | Key::Char(change @ '<') => {
+                let operation = match change {
+                    '>' => Operation::Advance,
+                    _ => Operation::Regress,
+                };
+                if let Some(task) = focused_task(context, &visible) {
+                    let current = db.task_state(project, task);
+                    let state = match change {
+                        '>' => current.next(),
+                        _ => current.previous(),
+                    };
+                    let question = fill(&locale().confirm_state, &theme().state_label(state));
+                    if state == current
+                        || confirmed(&config, operation, terminal_height, &question, io)?
+                    {
+                        context = change_status(context, project, &visible, &filter, db, change)?;
+                    }
+                }
+            }
+            Key::Char('-') if context.is_detail() => {
+                let task = visible[task_context.idx()];
+                match focused_event(context, db.task(project, task)) {
+                    Some((index, _)) => {
+                        let question = &locale().confirm_retract;
+                        if confirmed(&config, Operation::Delete, terminal_height, question, io)? {
+                            let position = db.retract_task_event(project, task, index)?;
+                            task_context =
+                                task_context_at(db, project, &filter, position, task_context);
+                            let task = position.unwrap_or(task);
+                            context = detail_context(context, db.task(project, task));
+                        }
+                    }
+                    None => {
+                        context =
+                            edit_checklist(context, project, task, '-', terminal_height, db, io)?
+                    }
+                }
+            }
+            Key::Char('R') if context.is_detail() => {
+                let task = visible[task_context.idx()];
+                if let Some((index, event)) = focused_event(context, db.task(project, task)) {
+                    match amended_event(&event, terminal_height, io)? {
+                        Some(replacement) => {
+                            let position =
+                                db.amend_task_event(project, task, index, replacement)?;
+                            task_context =
+                                task_context_at(db, project, &filter, position, task_context);
+                            let task = position.unwrap_or(task);
+                            context = detail_context(context, db.task(project, task));
+                        }
+                        None if !amendable(&event) => {
+                            toast = Some(locale().event_uncorrectable.clone())
+                        }
+                        None => {}
+                    }
+                }
+            }
+            Key::Char(c @ 'c') | Key::Char(c @ ' ') if context.is_detail() => {
+                let task = visible[task_context.idx()];
+                context = edit_checklist(context, project, task, c, terminal_height, db, io)?;
+            }
+            Key::Char('+') if context.is_detail() => {}
+            Key::Char('-') if focused_view(context, db).is_some() => {}
+            Key::Char('-') if focused_project(context, db).is_some() => {
+                let question = &locale().confirm_delete;
+                if confirmed(&config, Operation::Delete, terminal_height, question, io)? {
+                    context = delete_row(context, project, &visible, db)?;
+                }
+            }
+            Key::Char('-') => {
+                if let Some(task) = focused_task(context, &visible) {
+                    let description = db.task(project, task).description();
+                    let question = &locale().confirm_delete;
+                    if confirmed(
+                        &config,
+                        Operation::DeleteTask,
+                        terminal_height,
+                        question,
+                        io,
+                    )? {
+                        context = delete_row(context, project, &visible, db)?;
+                        toast = Some(fill(&locale().deleted, &description));
+                    }
+                }
+            }
+            Key::Char('u') => {
+                if db.undo()? {
+                    let rows = project_rows(db, views.len());
+                    if let Context::Project(row, _) = project_context {
+                        let row = row.min(rows + HEADER_OFFSET).max(HEADER_OFFSET + 1);
+                        project_context = Context::Project(row, rows);
+                    }
+                    context = match context {
+                        Context::Project(_, _) => project_context,
+                        _ if project >= db.project_count() as usize => project_context,
+                        Context::Detail(_, _) => {
+                            task_context_at(db, project, &filter, None, task_context)
+                        }
+                        Context::Task(_, _) => task_context_at(db, project, &filter, None, context),
+                    };
+                    toast = Some(locale().undone.clone());
+                }
+            }
+            Key::Char(':') => {
+                io.write_in_pos(terminal_height, 1, FormattedString::from(":").fg(PINK))?;
+                if let Some(command) = get_input_line(io, terminal_height, false)? {
+                    let scope = match context {
+                        Context::Project(_, _) => focused_project(context, db),
+                        _ => Some(project),
+                    };
+                    toast = run_command(io, &config, command.trim(), scope, terminal_height, db)?;
+                    context = match context {
+                        Context::Project(_, _) => context,
+                        Context::Detail(_, _)
+                            if db.visible_tasks(project, &filter).len() == visible.len() =>
+                        {
+                            context
+                        }
+                        Context::Detail(_, _) => {
+                            task_context_at(db, project, &filter, None, task_context)
+                        }
+                        Context::Task(_, _) => task_context_at(db, project, &filter, None, context),
+                    };
+                }
+            }
+            Key::Char('i') => {
+                if let Some(project) = focused_project(context, db) {
+                    let project = db.project(project);
+                    let title = fill(&locale().stats, project.title());
+                    show_popup(io, &title, stats::quick_stats(project, Utc::now()))?;
+                }
+            }
+            Key::Char('s') => {
+                if let Some(project) = focused_project(context, db) {
+                    let project = db.project(project);
+                    let title = fill(&locale().stats, project.title());
+                    let mut lines = stats::cycle_time_report(project);
+                    lines.push(String::from(""));
+                    lines.extend(stats::cumulative_flow_chart(project, 40));
+                    lines.push(String::from(""));
+                    lines.extend(stats::velocity_report(project));
+                    lines.push(String::from(""));
+                    lines.extend(stats::sprint_report(project));
+                    if project.daily.is_some() {
+                        lines.push(String::from(""));
+                        lines.extend(stats::daily_report(project));
+                    }
+                    show_page(io, &title, lines)?;
+                }
+            }
+            Key::Char('p') => {
+                if let Some(task) = focused_task(context, &visible) {
+                    set_points(project, task, terminal_height, db, io)?;
+                }
+            }
+            Key::Char('E') => {
+                if let Some(task) = focused_task(context, &visible) {
+                    if let Some(estimate) = prompt(io, terminal_height)? {
+                        if let Some(estimate) = timeline::parse_duration(&estimate) {
+                            let minutes = estimate.num_minutes() as u32;
+                            db.set_task_estimate(project, task, minutes)?;
+                        }
+                    }
+                }
+            }
+            Key::Char('W') => {
+                if let Some(task) = focused_task(context, &visible) {
+                    if let Some(who) = prompt(io, terminal_height)? {
+                        if !who.trim().is_empty() {
+                            let index =
+                                db.set_task_waiting(project, task, who.trim().to_string())?;
+                            context = task_context_at(db, project, &filter, index, context);
+                        }
+                    }
+                }
+            }
+            Key::Char('d') => {
+                if let Some(task) = focused_task(context, &visible) {
+                    set_dates(project, task, terminal_height, db, io)?;
+                }
+            }
+            Key::Char('T') => match context {
+                Context::Project(_, _) => {
+                    if let Some(project) = focused_project(context, db) {
+                        show_gantt(io, db.project(project), terminal_width)?;
+                    }
+                }
+                _ => show_gantt(io, db.project(project), terminal_width)?,
+            },
+            Key::Char('V') => {
+                let reviewed = review_waiting(io, db)?;
+                toast = Some(match reviewed {
+                    Some(reviewed) => fill(&locale().reviewed, &reviewed.to_string()),
+                    None => locale().review_nothing.clone(),
+                });
+                if let Context::Task(_, _) = context {
+                    context = task_context_at(db, project, &filter, None, context);
+                }
+            }
+            Key::Char('w') if !db.dry_run() => {
+                let storage = Database::storage()?;
+                let snapshots = snapshots::list(&storage);
+                let titles: Vec<String> = snapshots.iter().map(Snapshot::title).collect();
+                if snapshots.is_empty() {
+                    toast = Some(locale().no_snapshots.clone());
+                } else if let Some(i) = pick(io, &locale().snapshots, &titles)? {
+                    match snapshots[i].load(&storage) {
+                        Ok(projects) => {
+                            opened = Some(Database::snapshot(projects, titles[i].clone()));
+                        }
+                        Err(e) => toast = Some(e.to_string()),
+                    }
+                }
+            }
+            Key::Char('x') => {
+                if let Some(task) = focused_task(context, &visible) {
+                    let reference = db.reference(project, task);
+                    let mut parts = vec![];
+                    loop {
+                        let hint = fill(&locale().split_part, &(parts.len() + 1).to_string());
+                        io.write_in_pos(terminal_height - 1, 1, termion::clear::CurrentLine)?;
+                        io.write_in_pos(
+                            terminal_height - 1,
+                            1,
+                            FormattedString::from(&hint).fg(BLUE),
+                        )?;
+                        io.write_in_pos(terminal_height, 1, termion::clear::CurrentLine)?;
+                        match prompt_lines(io, terminal_height)? {
+                            Some(text) if !text.trim().is_empty() => {
+                                parts.extend(task_lines(&text))
+                            }
+                            _ => break,
+                        }
+                    }
+                    if !parts.is_empty() {
+                        io.write_in_pos(terminal_height, 1, termion::clear::CurrentLine)?;
+                        let link =
+                            confirm(terminal_height, &fill(&locale().split_link, &reference), io)?;
+                        let id = db.task(project, task).id.clone();
+                        let count = db.split_task(project, task, &parts, link)?;
+                        let task = db.find_task(&id).map(|(_, task)| task);
+                        context = task_context_at(db, project, &filter, task, context);
+                        let done = fill(&locale().split_done, &reference);
+                        toast = Some(fill(&done, &count.to_string()));
+                    }
+                }
+            }
+            Key::Char('X') => {
+                if let Some(path) = prompt(io, terminal_height)? {
+                    match db.diff(path.trim()) {
+                        Ok(entries) => {
+                            let capacity = terminal_height.saturating_sub(HEADER_OFFSET) as usize;
+                            let mut lines = diff::lines(&entries, true);
+                            lines.truncate(capacity.saturating_sub(2));
+                            lines.push(String::from(""));
+                            lines.push(diff::totals(&entries));
+                            show_page(io, &fill(&locale().snapshot_diff, path.trim()), lines)?;
+                        }
+                        Err(e) => toast = Some(e.to_string()),
+                    }
+                }
+            }
+            Key::Char('H') => {
+                let limit = terminal_height.saturating_sub(HEADER_OFFSET + 1) as usize;
+                let activity = db.activity(limit);
+                let lines: Vec<String> = activity
+                    .iter()
+                    .map(|entry| {
+                        timeline::activity_line(
+                            db.project(entry.project).title(),
+                            db.task(entry.project, entry.task),
+                            entry.date_time,
+                            entry.event.as_ref(),
+                        )
+                    })
+                    .collect();
+                if let Some(i) = pick(io, &locale().activity, &lines)? {
+                    let (project, task) = (activity[i].project, activity[i].task);
+                    let contexts = (&mut context, &mut project_context, &mut task_context);
+                    jump_to_task(contexts, &mut filter, views.len(), project, task, db);
+                }
+            }
+            Key::Char('C') => {
+                if let Some((project, task)) = show_calendar(io, db)? {
+                    let contexts = (&mut context, &mut project_context, &mut task_context);
+                    jump_to_task(contexts, &mut filter, views.len(), project, task, db);
+                }
+            }
+            Key::Char('%') => {
+                if let Some(task) = focused_task(context, &visible) {
+                    if let Some(progress) = prompt(io, terminal_height)? {
+                        if let Ok(progress) = progress.trim().trim_end_matches('%').parse::<u8>() {
+                            db.set_task_progress(project, task, progress)?;
+                        }
+                    }
+                }
+            }
+            Key::Char('S') => match context {
+                Context::Project(_, _) => {
+                    if let Some(project) = focused_project(context, db) {
+                        add_sprint(project, terminal_height, db, io)?;
+                    }
+                }
+                Context::Task(_, _) => {
+                    if let Some(task) = focused_task(context, &visible) {
+                        set_sprint(project, task, terminal_height, db, io)?;
+                    }
+                }
+                _ => {}
+            },
+            Key::Char('M') => match context {
+                Context::Project(_, _) => {
+                    if let Some(project) = focused_project(context, db) {
+                        add_milestone(project, terminal_height, db, io)?;
+                    }
+                }
+                Context::Task(_, _) => {
+                    if let Some(task) = focused_task(context, &visible) {
+                        set_milestone(project, task, terminal_height, db, io)?;
+                    }
+                }
+                _ => {}
+            },
+            Key::Char('O') if focused_project(context, db).is_some() => {
+                let orders = [
+                    ProjectOrder::Alphabetical,
+                    ProjectOrder::OpenTasks,
+                    ProjectOrder::Activity,
+                ];
+                let labels = vec![
+                    locale().order_alphabetical.clone(),
+                    locale().order_open_tasks.clone(),
+                    locale().order_activity.clone(),
+                ];
+                if let Some(i) = pick(io, &locale().sort_projects, &labels)? {
+                    db.sort_projects(orders[i])?;
+                }
+            }
+            Key::Char('B') => {
+                if let Some(project) = focused_project(context, db) {
+                    let project = db.project(project);
+                    match project.archive.is_empty() {
+                        true => toast = Some(locale().no_archive.clone()),
+                        false => {
+                            let title = fill(&locale().archive, project.title());
+                            show_page(io, &title, stats::archive_report(project))?;
+                        }
+                    }
+                }
+            }
+            Key::Char('L') => {
+                if let Some(project) = focused_project(context, db) {
+                    let project = db.project(project);
+                    let title = fill(&locale().milestones, project.title());
+                    show_page(io, &title, stats::milestones_report(project))?;
+                }
+            }
+            Key::Char('f') => {
+                if let Context::Task(_, _) = context {
+                    if let Some(input) = prompt(io, terminal_height)? {
+                        filter = Filter::parse(&input, &config);
+                        session.settings_mut(&db.project(project).id).filter = input;
+                        session.save()?;
+                        context = task_context_at(db, project, &filter, None, context);
+                    }
+                }
+            }
+            Key::Char('z') => {
+                if let Context::Task(_, _) = context {
+                    filter.someday = !filter.someday;
+                    session.settings_mut(&db.project(project).id).someday = filter.someday;
+                    session.save()?;
+                    context = task_context_at(db, project, &filter, None, context);
+                }
+            }
+            Key::Char('Z') => {
+                if let Some(task) = focused_task(context, &visible) {
+                    db.set_task_someday(project, task, !filter.someday)?;
+                    if filter.someday {
+                        db.set_task_state(project, task, State::TODO)?;
+                    }
+                    context = task_context_at(db, project, &filter, None, context);
+                }
+            }
+            Key::Char('D') => {
+                if let Context::Task(_, _) = context {
+                    filter::set_all_done(!filter::all_done());
+                    session.settings_mut(&db.project(project).id).all_done = filter::all_done();
+                    session.save()?;
+                    context = task_context_at(db, project, &filter, None, context);
+                }
+            }
+            Key::Char('t') => timeline::toggle_relative_time(),
+            Key::Char('/') => {
+                if let Some(hit) = search(io, db, terminal_height)? {
+                    let contexts = (&mut context, &mut project_context, &mut task_context);
+                    jump_to_task(
+                        contexts,
+                        &mut filter,
+                        views.len(),
+                        hit.project,
+                        hit.task,
+                        db,
+                    );
+                }
+            }
+            Key::Char('r') => {
+                let task = match context {
+                    Context::Detail(_, _) => Some(visible[task_context.idx()]),
+                    _ => focused_task(context, &visible),
+                };
+                if let Some(task) = task {
+                    let outgoing = db.outgoing_links(project, task);
+                    let incoming = db.incoming_links(project, task);
+                    let links: Vec<(usize, usize)> =
+                        outgoing.iter().chain(&incoming).cloned().collect();
+                    if links.is_empty() {
+                        toast = Some(locale().no_references.clone());
+                    } else {
+                        let lines: Vec<String> = links
+                            .iter()
+                            .enumerate()
+                            .map(|(i, (p, t))| {
+                                let arrow = if i < outgoing.len() { "→" } else { "←" };
+                                format!(
+                                    "{} {} {}",
+                                    arrow,
+                                    FormattedString::from(&db.reference(*p, *t)).fg(YELLOW),
+                                    db.task(*p, *t).description()
+                                )
+                            })
+                            .collect();
+                        let title = fill(&locale().references, &db.reference(project, task));
+                        if let Some(i) = pick(io, &title, &lines)? {
+                            let (project, task) = links[i];
+                            let contexts = (&mut context, &mut project_context, &mut task_context);
+                            jump_to_task(contexts, &mut filter, views.len(), project, task, db);
+                        }
+                    }
+                }
+            }
+            Key::Char('a') => {
+                if let Some(task) = focused_task(context, &visible) {
+                    if let Some(assignee) = prompt(io, terminal_height)? {
+                        let assignee = Some(assignee.trim().to_string()).filter(|a| !a.is_empty());
+                        db.set_task_assignee(project, task, assignee)?;
+                        context = task_context_at(db, project, &filter, Some(task), context);
+                    }
+                }
+            }
+            Key::Char('e') if focused_project(context, db).is_some() => {
+                let project = focused_project(context, db).unwrap();
+                let description = db.project(project).description.clone();
+                let edited = io.edit_text(&description)?;
+                let edited = edited.trim();
+                if !edited.is_empty() && edited != description {
+                    db.set_project_description(project, edited.to_string())?;
+                }
+            }
+            Key::Char('v') => {
+                if let Some(project) = focused_project(context, db) {
+                    show_project(io, db, project, (terminal_width, terminal_height))?;
+                }
+            }
+            Key::Char('e') => {
+                let task = match context {
+                    Context::Detail(_, _) => Some(visible[task_context.idx()]),
+                    _ => focused_task(context, &visible),
+                };
+                if let Some(task) = task {
+                    let notes = db.task(project, task).notes();
+                    let edited = io.edit_text(&notes)?;
+                    if edited != notes {
+                        db.set_task_notes(project, task, edited)?;
+                    }
+                    if let Context::Detail(row, _) = context {
+                        let len = detail_len(db.task(project, task));
+                        context = Context::Detail(
+                            row.min(len + HEADER_OFFSET).max(HEADER_OFFSET + 1),
+                            len,
+                        );
+                    }
+                }
+            }
+            Key::Char('A') => {
+                if let Context::Detail(row, _) = context {
+                    let task = visible[task_context.idx()];
+                    if let Some(attachment) = prompt(io, terminal_height)? {
+                        let attachment = attachment.trim().to_string();
+                        if !attachment.is_empty() {
+                            db.add_task_attachment(project, task, attachment)?;
+                            context = Context::Detail(row, detail_len(db.task(project, task)));
+                        }
+                    }
+                }
+            }
+            Key::Char('o') => {
+                let targets = match context {
+                    Context::Detail(_, _) => {
+                        let task = db.task(project, visible[task_context.idx()]);
+                        match detail_rows(task).get(context.idx()) {
+                            Some(DetailRow::Event(entry)) => match entry.event() {
+                                Event::Attachment { data, .. } => vec![data.clone()],
+                                Event::Comment { data, .. } => find_urls(data),
+                                _ => task.urls(),
+                            },
+                            Some(DetailRow::Note(_, line)) => find_urls(line),
+                            _ => task.urls(),
+                        }
+                    }
+                    Context::Task(_, _) => match focused_task(context, &visible) {
+                        Some(task) => db.task(project, task).urls(),
+                        None => vec![],
+                    },
+                    Context::Project(_, _) => vec![],
+                };
+
+                let choice = match targets.len() {
+                    0 => None,
+                    1 => Some(0),
+                    _ => pick(io, &locale().open_link, &targets)?,
+                };
+                if let Some(target) = choice.and_then(|i| targets.get(i)) {
+                    open_external(target).ok();
+                }
+            }
+            Key::Char(c @ 'y') | Key::Char(c @ 'Y') => {
+                if let Some(task) = focused_task(context, &visible) {
+                    let task = db.task(project, task);
+                    match c {
+                        'y' => io.copy(&task.description())?,
+                        _ => io.copy(&task.id)?,
+                    }
+                }
+            }
+            Key::Char('b') => {
+                if let Some(task) = focused_task(context, &visible) {
+                    let branch = git::branch_name(db.task(project, task));
+                    match git::checkout_branch(&branch) {
+                        Ok(()) if db.task(project, task).branch() != Some(branch.clone()) => {
+                            db.set_task_branch(project, task, branch)?
+                        }
+                        Ok(()) => {}
+                        Err(e) => toast = Some(e.to_string()),
+                    }
+                }
+            }
+            Key::Char(' ') => {
+                if let Some(task) = focused_task(context, &visible) {
+                    let id = db.task(project, task).id.clone();
+                    if !marked.remove(&id) {
+                        marked.insert(id);
+                    }
+                    context = context.jump(1).unwrap_or(context);
+                }
+            }
+            Key::Char('&') => {
+                if let Some(task) = focused_task(context, &visible) {
+                    let id = db.task(project, task).id.clone();
+                    let duplicates: Vec<String> = marked
+                        .iter()
+                        .filter(|marked| **marked != id)
+                        .cloned()
+                        .collect();
+                    if duplicates.is_empty() {
+                        toast = Some(locale().merge_nothing_marked.clone());
+                    } else {
+                        let reference = db.reference(project, task);
+                        let question = fill(
+                            &fill(&locale().confirm_merge, &duplicates.len().to_string()),
+                            &reference,
+                        );
+                        if confirmed(
+                            &config,
+                            Operation::DeleteTask,
+                            terminal_height,
+                            &question,
+                            io,
+                        )? {
+                            let task = db.merge_tasks(project, task, &duplicates)?;
+                            context = task_context_at(db, project, &filter, task, context);
+                            marked.clear();
+                            toast = Some(fill(&locale().merged, &reference));
+                        }
+                    }
+                }
+            }
+            Key::Char('|') => {
+                if let Some(focused) = focused_task(context, &visible) {
+                    let mut tasks: Vec<&Task> = db
+                        .projects()
+                        .flat_map(|p| p.tasks.iter())
+                        .filter(|t| marked.contains(&t.id))
+                        .collect();
+                    if tasks.is_empty() {
+                        tasks.push(db.task(project, focused));
+                    }
+
+                    if let Some(command) = prompt(io, terminal_height)? {
+                        let formats = [locale().format_plain.clone(), locale().format_json.clone()];
+                        if let Some(format) = pick(io, &locale().send_tasks, &formats)? {
+                            let content = serialize_tasks(&tasks, format == 1)?;
+                            match io.pipe(&command, &content) {
+                                Ok(true) => marked.clear(),
+                                Ok(false) => toast = Some(fill(&locale().command_failed, &command)),
+                                Err(e) => toast = Some(e.to_string()),
+                            }
+                        }
+                    }
+                }
+            }
+            Key::Char('I') => {
+                if let Context::Task(_, _) = context {
+                    let pasted = io.paste().unwrap_or_default();
+                    let tasks: Vec<Task> = task_lines(&io.edit_text(&pasted)?)
+                        .into_iter()
+                        .map(Task::new)
+                        .collect();
+                    if !tasks.is_empty() {
+                        let summary = db.import_tasks(project, tasks)?;
+                        let added = summary.added.len().to_string();
+                        toast = Some(match summary.skipped.len() {
+                            0 => fill(&locale().imported, &added),
+                            skipped => fill(
+                                &fill(&locale().imported_skipped, &added),
+                                &skipped.to_string(),
+                            ),
+                        });
+                        context = task_context_at(db, project, &filter, None, context);
+                    }
+                }
+            }
+            Key::Char('+') => {
+                context = add_row(context, project, &filter, terminal_height, db, io)?
+            }
+            _ => {}
+        }
+    }
+    io.disable_paste()?;
+    io.set_mouse(false)?;
+    io.clear_screen()?;
+    io.show_cursor()?;
+    Ok(())
+}
+
+fn open_external(target: &str) -> Result<()> {
+    let opener = if cfg!(target_os = "macos") {
+        "open"
+    } else {
+        "xdg-open"
+    };
+
+    Command::new(opener)
+        .arg(target)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map(|_| ())
+}
+
+fn search<'a>(io: &mut IO<'a>, db: &Database, terminal_height: u16) -> Result<Option<Hit>> {
+    let query = match prompt(io, terminal_height)? {
+        Some(query) => query,
+        None => return Ok(None),
+    };
+
+    let mut hits = db.search(&query);
+    if hits.is_empty() {
+        show_page(io, &fill(&locale().no_results, &query), vec![])?;
+        return Ok(None);
+    }
+
+    let results: Vec<String> = hits
+        .iter()
+        .map(|hit| {
+            format!(
+                "{} {}",
+                FormattedString::from(db.project(hit.project).title()).fg(PINK),
+                search::highlight(&db.task(hit.project, hit.task).description(), &query)
+            )
+        })
+        .collect();
+    let title = fill(&locale().results, &query);
+    Ok(pick(io, &title, &results)?.map(|i| hits.swap_remove(i)))
+}
+
+fn command_environment(
+    db: &Database,
+    project: usize,
+    task: Option<usize>,
+) -> Result<Vec<(&'static str, String)>> {
+    let mut environment = vec![
+        ("RTASKS_PROJECT", db.project(project).title().to_string()),
+        ("RTASKS_PROJECT_ID", db.project(project).id.clone()),
+    ];
+    if let Some(task) = task {
+        let focused = db.task(project, task);
+        environment.extend(vec![
+            ("RTASKS_TASK_ID", focused.id.clone()),
+            ("RTASKS_TASK_REFERENCE", db.reference(project, task)),
+            ("RTASKS_TASK_DESCRIPTION", focused.description()),
+            ("RTASKS_TASK_STATE", format!("{:?}", focused.state())),
+            ("RTASKS_TASK_JSON", serde_json::to_string(focused)?),
+        ]);
+    }
+    Ok(environment)
+}
+
+fn focused_project(context: Context, db: &Database) -> Option<usize> {
+    match context {
+        Context::Project(_, _) if context.idx() < db.project_count() as usize => {
+            Some(context.idx())
+        }
+        _ => None,
+    }
+}
+
+fn focused_view(context: Context, db: &Database) -> Option<usize> {
+    match context {
+        Context::Project(_, _)
+            if focused_project(context, db).is_none() && !focused_habits(context) =>
+        {
+            Some(context.idx() - db.project_count() as usize)
+        }
+        _ => None,
+    }
+}
+
+fn focused_habits(context: Context) -> bool {
+    match context {
+        Context::Project(_, len) => context.idx() + 1 == len as usize,
+        _ => false,
+    }
+}
+
+fn project_rows(db: &Database, views: usize) -> u16 {
+    db.project_count() + views as u16 + 1
+}
+
+fn task_summaries(tasks: &[(usize, usize)], db: &Database) -> Vec<String> {
+    tasks
+        .iter()
+        .map(|(project, task)| {
+            let task = db.task(*project, *task);
+            format!(
+                "{} {} {}",
+                FormattedString::from(&format!("{:?}", task.state())).right(7),
+                FormattedString::from(db.project(*project).title()).fg(PINK),
+                task.description()
+            )
+        })
+        .collect()
+}
+
+fn serialize_tasks(tasks: &[&Task], json: bool) -> Result<String> {
+    if json {
+        let tasks: Vec<serde_json::Value> = tasks
+            .iter()
+            .map(|task| {
+                serde_json::json!({
+                    "id": task.id,
+                    "description": task.description(),
+                    "state": task.state(),
+                    "assignee": task.assignee(),
+                    "sprint": task.sprint(),
+                    "points": task.points(),
+                    "notes": task.notes(),
+                })
+            })
+            .collect();
+        Ok(serde_json::to_string_pretty(&tasks)?)
+    } else {
+        Ok(tasks
+            .iter()
+            .map(|task| format!("- [{:?}] {}\n", task.state(), task.description()))
+            .collect())
+    }
+}
+
+fn focused_task(context: Context, visible: &[usize]) -> Option<usize> {
+    match context {
+        Context::Task(_, len) if len > 0 => visible.get(context.idx()).cloned(),
+        _ => None,
+    }
+}
+
+fn task_context_at(
+    db: &Database,
+    project: usize,
+    filter: &Filter,
+    task: Option<usize>,
+    fallback: Context,
+) -> Context {
+    let visible = db.visible_tasks(project, filter);
+    let len = visible.len() as u16;
+    match task.and_then(|t| visible.iter().position(|v| *v == t)) {
+        Some(row) => Context::Task(row as u16 + HEADER_OFFSET + 1, len),
+        None => match fallback {
+            Context::Task(row, _) if row <= len + HEADER_OFFSET => Context::Task(row, len),
+            _ => Context::Task(HEADER_OFFSET + 1, len),
+        },
+    }
+}
+
+fn swap_rows(
+    context: Context,
+    project: usize,
+    visible: &[usize],
+    cmd: char,
+    db: &mut Database,
+) -> Result<Context> {
+    if let Some(next_context) = context.jump(if cmd == 'J' { 1 } else { -1 }) {
+        match context {
+            Context::Project(_, _) => {
+                match (
+                    focused_project(context, db),
+                    focused_project(next_context, db),
+                ) {
+                    (Some(first), Some(second)) => db.swap_projects(first, second)?,
+                    _ => return Ok(context),
+                }
+            }
+            Context::Task(_, _) => {
+                db.swap_tasks(project, visible[context.idx()], visible[next_context.idx()])?
+            }
+            Context::Detail(_, _) => return Ok(context),
+        }
+        Ok(next_context)
+    } else {
+        Ok(context)
+    }
+}
+
+fn run_command<'a>(
+    io: &mut IO<'a>,
+    config: &Config,
+    command: &str,
+    project: Option<usize>,
+    terminal_height: u16,
+    db: &mut Database,
+) -> Result<Option<String>> {
+    let (name, arguments) = command.split_at(command.find(' ').unwrap_or(command.len()));
+    let result = match name {
+        "" => return Ok(None),
+        "sub" => substitute(io, arguments, project, terminal_height, db),
+        "move" => transfer(io, config, arguments, project, terminal_height, db),
+        _ => return Ok(Some(fill(&locale().unknown_command, name))),
+    };
+    match result {
+        Err(e) if e.kind() == ErrorKind::InvalidInput => Ok(Some(e.to_string())),
+        result => result,
+    }
+}
+
+fn review_waiting<'a>(io: &mut IO<'a>, db: &mut Database) -> Result<Option<usize>> {
+    let cutoff = Utc::now() - chrono::Duration::days(LONG_WAIT_DAYS);
+    let waiting: Vec<String> = db
+        .waiting_tasks()
+        .into_iter()
+        .map(|(p, t)| db.task(p, t))
+        .filter(|task| {
+            task.waiting_on()
+                .map_or(false, |(_, since)| since <= cutoff)
+        })
+        .map(|task| task.id.clone())
+        .collect();
+    if waiting.is_empty() {
+        return Ok(None);
+    }
+    let mut reviewed = 0;
+    for (i, id) in waiting.iter().enumerate() {
+        let (project, task) = match db.find_task(id) {
+            Some(found) => found,
+            None => continue,
+        };
+        let (who, since) = match db.task(project, task).waiting_on() {
+            Some(waiting) => waiting,
+            None => continue,
+        };
+        let title = fill(&locale().review_title, &(i + 1).to_string());
+        let waited = fill(&locale().waiting_on, &who);
+        let lines = vec![
+            format!(
+                "{}  {}",
+                db.reference(project, task),
+                db.task(project, task).description()
+            ),
+            fill(&waited, &timeline::humanize(Utc::now() - since)),
+            String::new(),
+            locale().review_keys.clone(),
+        ];
+        io.clear_screen()?;
+        draw_popup(io, &fill(&title, &waiting.len().to_string()), lines)?;
+        let state = match io.get_char()? {
+            Key::Char('t') => State::TODO,
+            Key::Char('o') => State::ONGOING,
+            Key::Char('d') => State::DONE,
+            Key::Char('q') | Key::Esc => break,
+            _ => continue,
+        };
+        db.set_task_state(project, task, state)?;
+        reviewed += 1;
+    }
+    Ok(Some(reviewed))
+}
+
+fn substitute<'a>(
+    io: &mut IO<'a>,
+    arguments: &str,
+    project: Option<usize>,
+    terminal_height: u16,
+    db: &mut Database,
+) -> Result<Option<String>> {
+    let substitution = Substitution::parse(arguments)?;
+    let matches = substitution.matches(db, project);
+    if matches.is_empty() {
+        return Ok(Some(fill(
+            &locale().no_substitutions,
+            &substitution.pattern,
+        )));
+    }
+    let preview_rows = (terminal_height as usize).saturating_sub(6).max(1);
+    let mut lines: Vec<String> = matches
+        .iter()
+        .take(preview_rows)
+        .map(|(p, t, renamed)| format!("{}  {}", db.reference(*p, *t), renamed))
+        .collect();
+    if matches.len() > preview_rows {
+        let more = (matches.len() - preview_rows).to_string();
+        lines.push(fill(&locale().more_substitutions, &more));
+    }
+    let count = matches.len().to_string();
+    draw_popup(io, &fill(&locale().substitute_preview, &count), lines)?;
+    if !confirm(
+        terminal_height,
+        &fill(&locale().substitute_confirm, &count),
+        io,
+    )? {
+        return Ok(None);
+    }
+    let renamed = db.rename_tasks(matches)?;
+    Ok(Some(fill(&locale().substituted, &renamed.to_string())))
+}
+
+fn transfer<'a>(
+    io: &mut IO<'a>,
+    config: &Config,
+    arguments: &str,
+    project: Option<usize>,
+    terminal_height: u16,
+    db: &mut Database,
+) -> Result<Option<String>> {
+    let usage = || Error::new(ErrorKind::InvalidInput, locale().move_usage.clone());
+    let (state, target) = arguments.trim().split_once(' ').ok_or_else(usage)?;
+    let state = filter::parse_state(state).ok_or_else(usage)?;
+    let project = project.ok_or_else(usage)?;
+    let target = db.find_project(target.trim())?;
+    let question = fill(&locale().confirm_transfer, &theme().state_label(state));
+    let question = fill(&question, db.project(target).title());
+    if !confirmed(config, Operation::Bulk, terminal_height, &question, io)? {
+        return Ok(None);
+    }
+    let moved = db.transfer_tasks(project, target, state)?;
+    let message = fill(&locale().transferred, &moved.to_string());
+    Ok(Some(fill(&message, db.project(target).title())))
+}
+
+fn restore_filter(session: &Session, config: &Config, project: &Project) -> Filter {
+    let settings = session.settings(&project.id);
+    let mut filter = Filter::parse(&settings.filter, config);
+    filter.someday = settings.someday;
+    filter::set_all_done(settings.all_done);
+    filter
+}
+
+fn favorite_projects(session: &Session, db: &Database) -> Vec<usize> {
+    session
+        .favorites
+        .iter()
+        .filter_map(|id| db.projects().position(|project| project.id == *id))
+        .collect()
+}
+
+fn draw_favorites<'a>(
+    io: &mut IO<'a>,
+    favorites: &[usize],
+    current: Option<usize>,
+    row: u16,
+    db: &Database,
+) -> Result<()> {
+    let mut bar = FormattedLine::new();
+    for (slot, project) in favorites.iter().enumerate() {
+        let label = format!(" {} {} ", slot + 1, db.project(*project).title());
+        bar = bar.push(match current == Some(*project) {
+            true => FormattedString::from(&label).fg(YELLOW),
+            false => FormattedString::from(&label).fg(DIV_COLOR),
+        });
+    }
+    let (terminal_width, _) = termion::terminal_size()?;
+    io.write_in_pos(row, 1, bar.truncate(terminal_width as usize))
+}
+
+fn move_row(
+    context: Context,
+    project: usize,
+    visible: &[usize],
+    filter: &Filter,
+    position: usize,
+    db: &mut Database,
+) -> Result<Context> {
+    match context {
+        Context::Project(_, _) => match focused_project(context, db) {
+            Some(from) => {
+                let to = position.min(db.project_count() as usize - 1);
+                db.move_project(from, to);
+                db.save()?;
+                Ok(context.jump_to(to).unwrap_or(context))
+            }
+            None => Ok(context),
+        },
+        Context::Task(_, _) if !visible.is_empty() => {
+            let target = visible[position.min(visible.len() - 1)];
+            let task = db.move_task(project, visible[context.idx()], target)?;
+            Ok(task_context_at(db, project, filter, task, context))
+        }
+        _ => Ok(context),
+    }
+}
+
+fn drag_project(
+    context: Context,
+    mouse: MouseEvent,
+    offset: usize,
+    dragged: &mut Option<(usize, usize)>,
+    db: &mut Database,
+) -> Result<Context> {
+    let project_at = |row: u16| match row > HEADER_OFFSET {
+        true => Some(offset + (row - HEADER_OFFSET - 1) as usize),
+        false => None,
+    };
+    let focus = |project: usize| {
+        Context::Project(project as u16 + HEADER_OFFSET + 1, context.length() as u16)
+    };
+
+    match (mouse, *dragged) {
+        (MouseEvent::Press(MouseButton::Left, _, row), _) => {
+            match project_at(row).filter(|p| *p < db.project_count() as usize) {
+                Some(project) => {
+                    *dragged = Some((project, project));
+                    Ok(focus(project))
+                }
+                None => Ok(context),
+            }
+        }
+        (MouseEvent::Hold(_, row), Some((origin, current))) => {
+            let last = db.project_count() as usize - 1;
+            let target = project_at(row).unwrap_or(0).min(last);
+            if target != current {
+                db.move_project(current, target);
+                *dragged = Some((origin, target));
+            }
+            Ok(focus(target))
+        }
+        (MouseEvent::Release(_, _), Some((origin, current))) => {
+            *dragged = None;
+            if origin != current {
+                db.save()?;
+            }
+            Ok(context)
+        }
+        _ => Ok(context),
+    }
+}
+
+fn get_input_line<'a>(io: &mut IO<'a>, row: u16, multiline: bool) -> Result<Option<String>> {
+    let mut description = String::from("");
+    let mut result = Ok(None);
+
+    io.show_cursor()?;
+
+    loop {
+        let pasted = match io.wait()? {
+            Input::Key(Key::Esc) => break,
+            Input::Key(Key::Char('\n')) => {
+                result = Ok(Some(description));
+                break;
+            }
+            Input::Key(Key::Backspace) => {
+                if let Some(_) = description.pop() {
+                    io.erase(row, 4 + description.chars().count() as u16)?
+                }
+                continue;
+            }
+            Input::Key(Key::Ctrl('v')) => io.paste(),
+            Input::Paste(pasted) => Some(pasted),
+            Input::Key(Key::Char(c)) => {
+                description.push(c);
+                io.write_in_pos(row, 3 + description.chars().count() as u16, c)?;
+                continue;
+            }
+            _ => continue,
+        };
+        if let Some(pasted) = pasted {
+            let pasted = pasted
+                .trim_end_matches('\n')
+                .replace(|c: char| c.is_control() && !(multiline && c == '\n'), " ");
+            let shown = pasted.replace('\n', "↵");
+            io.write_in_pos(row, 4 + description.chars().count() as u16, &shown)?;
+            description.push_str(&pasted);
+        }
+    }
+
+    io.hide_cursor()?;
+    result
+}
+
+fn enter_context(
+    (context, project_context, task_context): (&mut Context, &mut Context, &mut Context),
+    visible: &[usize],
+    filter: &Filter,
+    db: &Database,
+) {
+    match context {
+        Context::Project(_, len) if *len > 0 => {
+            *project_context = *context;
+            let tasks = db.visible_tasks(project_context.idx(), filter);
+            *context = Context::Task(HEADER_OFFSET + 1, tasks.len() as u16);
+        }
+        Context::Task(_, len) if *len > 0 => {
+            *task_context = *context;
+            let task = db.task(project_context.idx(), visible[task_context.idx()]);
+            *context = Context::Detail(HEADER_OFFSET + 1, detail_len(task));
+        }
+        _ => {}
+    }
+}
+
+fn jump_to_task(
+    (context, project_context, task_context): (&mut Context, &mut Context, &mut Context),
+    filter: &mut Filter,
+    views: usize,
+    project: usize,
+    task: usize,
+    db: &Database,
+) {
+    *filter = Filter::default();
+    *project_context =
+        Context::Project(project as u16 + HEADER_OFFSET + 1, project_rows(db, views));
+    *task_context = task_context_at(db, project, filter, Some(task), *context);
+    *context = *task_context;
+}
+
+fn detail_len(task: &Task) -> u16 {
+    detail_rows(task).len() as u16
+}
+
+fn detail_context(context: Context, task: &Task) -> Context {
+    let len = detail_len(task);
+    match context {
+        Context::Detail(row, _) => {
+            Context::Detail(row.min(len + HEADER_OFFSET).max(HEADER_OFFSET + 1), len)
+        }
+        _ => context,
+    }
+}
+
+fn focused_event(context: Context, task: &Task) -> Option<(usize, Event)> {
+    match detail_rows(task).into_iter().nth(context.idx()) {
+        Some(DetailRow::Event(entry)) if entry.correctable() => {
+            Some((entry.index(), entry.event().clone()))
+        }
+        _ => None,
+    }
+}
+
+fn amendable(event: &Event) -> bool {
+    match event {
+        Event::State { .. }
+        | Event::Description { .. }
+        | Event::Comment { .. }
+        | Event::Waiting { .. }
+        | Event::Points { .. } => true,
+        _ => false,
+    }
+}
+
+fn amended_event<'a>(event: &Event, row: u16, io: &mut IO<'a>) -> Result<Option<Event>> {
+    let mut replacement = event.clone();
+    match &mut replacement {
+        Event::State { data, .. } => {
+            let states = [State::TODO, State::ONGOING, State::WAITING, State::DONE];
+            let labels: Vec<String> = states
+                .iter()
+                .map(|state| theme().state_label(*state).to_string())
+                .collect();
+            match pick(io, &locale().correct_event, &labels)? {
+                Some(i) if states[i] != *data => *data = states[i],
+                _ => return Ok(None),
+            }
+        }
+        Event::Description { data, .. }
+        | Event::Comment { data, .. }
+        | Event::Waiting { data, .. } => match prompt(io, row)? {
+            Some(text) if !text.trim().is_empty() => *data = text.trim().to_string(),
+            _ => return Ok(None),
+        },
+        Event::Points { data, .. } => match prompt(io, row)?.and_then(|p| p.trim().parse().ok()) {
+            Some(points) => *data = points,
+            None => return Ok(None),
+        },
+        _ => return Ok(None),
+    }
+    Ok(Some(replacement))
+}
+
+fn edit_checklist<'a>(
+    context: Context,
+    project: usize,
+    task: usize,
+    cmd: char,
+    terminal_height: u16,
+    db: &mut Database,
+    io: &mut IO<'a>,
+) -> Result<Context> {
+    let mut checklist = db.task(project, task).checklist();
+    let focused = context.idx();
+
+    match cmd {
+        'c' => match prompt(io, terminal_height)? {
+            Some(ref text) if !text.trim().is_empty() => checklist.push(ChecklistItem {
+                text: text.trim().to_string(),
+                done: false,
+            }),
+            _ => return Ok(context),
+        },
+        ' ' if focused < checklist.len() => checklist[focused].done = !checklist[focused].done,
+        '-' if focused < checklist.len() => {
+            checklist.remove(focused);
+        }
+        _ => return Ok(context),
+    }
+
+    db.set_task_checklist(project, task, checklist)?;
+    let len = detail_len(db.task(project, task));
+    match context {
+        Context::Detail(row, _) if row < len + HEADER_OFFSET + 1 => Ok(Context::Detail(row, len)),
+        _ => Ok(Context::Detail(len + HEADER_OFFSET, len)),
+    }
+}
+
+fn leave_context(context: &mut Context, project_context: &mut Context, task_context: &mut Context) {
+    match context {
+        Context::Task(_, _) => *context = *project_context,
+        Context::Detail(_, _) => *context = *task_context,
+        _ => {}
+    }
+}
+
+fn prompt<'a>(io: &mut IO<'a>, row: u16) -> Result<Option<String>> {
+    io.write_in_pos(row, 1, FormattedString::from("-> ").fg(PINK))?;
+    get_input_line(io, row, false)
+}
+
+fn prompt_lines<'a>(io: &mut IO<'a>, row: u16) -> Result<Option<String>> {
+    io.write_in_pos(row, 1, FormattedString::from("-> ").fg(PINK))?;
+    get_input_line(io, row, true)
+}
+
+fn set_points<'a>(
+    project: usize,
+    task: usize,
+    terminal_height: u16,
+    db: &mut Database,
+    io: &mut IO<'a>,
+) -> Result<()> {
+    if let Some(points) = prompt(io, terminal_height)? {
+        if let Ok(points) = points.trim().parse::<u32>() {
+            db.set_task_points(project, task, points)?;
+        }
+    }
+    Ok(())
+}
+
+fn set_dates<'a>(
+    project: usize,
+    task: usize,
+    terminal_height: u16,
+    db: &mut Database,
+    io: &mut IO<'a>,
+) -> Result<()> {
+    let input = match prompt(io, terminal_height)? {
+        Some(input) => input,
+        None => return Ok(()),
+    };
+    let date = |text: &str| NaiveDate::parse_from_str(text.trim(), "%Y-%m-%d").ok();
+
+    match input.trim().find("..").map(|i| input.trim().split_at(i)) {
+        _ if input.trim().is_empty() => {
+            if db.task(project, task).start().is_some() {
+                db.set_task_start(project, task, None)?;
+            }
+            if db.task(project, task).due().is_some() {
+                db.set_task_due(project, task, None)?;
+            }
+        }
+        Some((start, due)) if due[2..].trim().is_empty() => {
+            if let Some(start) = date(start) {
+                db.set_task_start(project, task, Some(start))?;
+            }
+        }
+        Some((start, due)) => {
+            if let (Some(start), Some(due)) = (date(start), date(&due[2..])) {
+                db.set_task_start(project, task, Some(start))?;
+                db.set_task_due(project, task, Some(due))?;
+            }
+        }
+        None => {
+            if let Some(due) = date(&input) {
+                db.set_task_due(project, task, Some(due))?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn add_sprint<'a>(
+    project: usize,
+    terminal_height: u16,
+    db: &mut Database,
+    io: &mut IO<'a>,
+) -> Result<()> {
+    if let Some(input) = prompt(io, terminal_height)? {
+        let parts: Vec<&str> = input.split_whitespace().collect();
+        if let [name, start, end] = parts.as_slice() {
+            let start = NaiveDate::parse_from_str(start, "%Y-%m-%d");
+            let end = NaiveDate::parse_from_str(end, "%Y-%m-%d");
+            if let (Ok(start), Ok(end)) = (start, end) {
+                let name = name.to_string();
+                db.add_sprint(project, Sprint { name, start, end })?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn set_sprint<'a>(
+    project: usize,
+    task: usize,
+    terminal_height: u16,
+    db: &mut Database,
+    io: &mut IO<'a>,
+) -> Result<()> {
+    if let Some(name) = prompt(io, terminal_height)? {
+        let name = name.trim();
+        if name.is_empty() {
+            db.set_task_sprint(project, task, None)?;
+        } else if db.project(project).sprint(name).is_some() {
+            db.set_task_sprint(project, task, Some(name.to_string()))?;
+        }
+    }
+    Ok(())
+}
+
+fn add_milestone<'a>(
+    project: usize,
+    terminal_height: u16,
+    db: &mut Database,
+    io: &mut IO<'a>,
+) -> Result<()> {
+    if let Some(input) = prompt(io, terminal_height)? {
+        let parts: Vec<&str> = input.split_whitespace().collect();
+        if let [name, target] = parts.as_slice() {
+            if let Ok(target) = NaiveDate::parse_from_str(target, "%Y-%m-%d") {
+                let name = name.to_string();
+                db.add_milestone(project, Milestone { name, target })?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn set_milestone<'a>(
+    project: usize,
+    task: usize,
+    terminal_height: u16,
+    db: &mut Database,
+    io: &mut IO<'a>,
+) -> Result<()> {
+    if let Some(name) = prompt(io, terminal_height)? {
+        let name = name.trim();
+        if name.is_empty() {
+            db.set_task_milestone(project, task, None)?;
+        } else if db.project(project).milestone(name).is_some() {
+            db.set_task_milestone(project, task, Some(name.to_string()))?;
+        }
+    }
+    Ok(())
+}
+
+fn warn_duplicate<'a>(io: &mut IO<'a>, row: u16, existing: &Task) -> Result<Key> {
+    let warning = fill(&locale().similar, &existing.description());
+    io.write_in_pos(row, 1, termion::clear::CurrentLine)?;
+    io.write_in_pos(row, 1, FormattedString::from(&warning).fg(YELLOW))?;
+    io.get_char()
+}
+
+fn add_row<'a>(
+    context: Context,
+    project: usize,
+    filter: &Filter,
+    terminal_height: u16,
+    db: &mut Database,
+    io: &mut IO<'a>,
+) -> Result<Context> {
+    let description = prompt_lines(io, terminal_height)?;
+
+    if let Some(description) = description {
+        match context {
+            Context::Task(_, _) if description.contains('\n') => {
+                let tasks: Vec<Task> = task_lines(&description)
+                    .into_iter()
+                    .map(Task::new)
+                    .collect();
+                db.import_tasks(project, tasks)?;
+                Ok(task_context_at(db, project, filter, None, context))
+            }
+            Context::Task(_, _) => {
+                if let Some(existing) = db.project(project).similar_task(&description) {
+                    match warn_duplicate(io, terminal_height, db.task(project, existing))? {
+                        Key::Char('j') => {
+                            return Ok(task_context_at(
+                                db,
+                                project,
+                                filter,
+                                Some(existing),
+                                context,
+                            ))
+                        }
+                        Key::Char('a') => {}
+                        _ => return Ok(context),
+                    }
+                }
+                let task_index = db.add_task(project, Task::new(description))?;
+                if let (Some(task), Some(sprint)) = (task_index, &filter.sprint) {
+                    db.set_task_sprint(project, task, Some(sprint.clone()))?;
+                }
+                Ok(task_context_at(db, project, filter, task_index, context))
+            }
+            Context::Project(_, size) => {
+                db.add_project(Project::new(description))?;
+                Ok(Context::Project(
+                    db.project_count() + HEADER_OFFSET,
+                    size + 1,
+                ))
+            }
+            Context::Detail(_, _) => Ok(context),
+        }
+    } else {
+        Ok(context)
+    }
+}
+
+fn delete_row(
+    context: Context,
+    project: usize,
+    visible: &[usize],
+    db: &mut Database,
+) -> Result<Context> {
+    match context.drop() {
+        Some(new_context @ Context::Project(_, _)) => {
+            db.remove_project(context.idx())?;
+            Ok(new_context)
+        }
+        Some(new_context @ Context::Task(_, _)) => {
+            db.remove_task(project, visible[context.idx()])?;
+            Ok(new_context)
+        }
+        Some(Context::Detail(_, _)) | None => Ok(context),
+    }
+}
+
+fn change_status(
+    context: Context,
+    project: usize,
+    visible: &[usize],
+    filter: &Filter,
+    db: &mut Database,
+    c: char,
+) -> Result<Context> {
+    if let Some(task) = focused_task(context, visible) {
+        let current_state = db.task_state(project, task);
         let next_state = match c {
             '>' => current_state.next(),
             _ => current_state.previous(),
         };
 
-        match db.set_task_state(project, context.idx(), next_state)? {
-            Some(new_index) => Ok(Context::Task(new_index as u16 + HEADER_OFFSET + 1, len)),
+        match db.set_task_state(project, task, next_state)? {
+            Some(new_index) => Ok(task_context_at(
+                db,
+                project,
+                filter,
+                Some(new_index),
+                context,
+            )),
             None => Ok(context),
         }
     } else {
@@ -0,0 +1,117 @@
+use termion::color::Rgb;
+
+use crate::formatted_string::FormattedString;
+use crate::project::{context_at, reference_at};
+
+static YELLOW: Rgb = Rgb(241, 196, 15);
+static PINK: Rgb = Rgb(200, 0, 150);
+static PURPLE: Rgb = Rgb(214, 162, 232);
+static BLUE: Rgb = Rgb(52, 152, 219);
+static GRAY: Rgb = Rgb(127, 140, 141);
+
+pub fn render_line(line: &str) -> String {
+    let trimmed = line.trim_start();
+    let indent = &line[..line.len() - trimmed.len()];
+
+    if trimmed.starts_with("# ") || trimmed.starts_with("## ") || trimmed.starts_with("### ") {
+        let heading = trimmed.trim_start_matches('#').trim_start();
+        return FormattedString::from(heading).bold().to_string();
+    }
+
+    for bullet in &["- ", "* ", "+ "] {
+        if trimmed.starts_with(bullet) {
+            return format!("{}  • {}", indent, render_inline(&trimmed[2..]));
+        }
+    }
+
+    format!("{}{}", indent, render_inline(trimmed))
+}
+
+fn delimited<'a>(text: &'a str, open: &str, close: &str) -> Option<(&'a str, &'a str)> {
+    if !text.starts_with(open) {
+        return None;
+    }
+
+    let body = &text[open.len()..];
+    match body.find(close) {
+        Some(end) if end > 0 => Some((&body[..end], &body[end + close.len()..])),
+        _ => None,
+    }
+}
+
+pub fn highlight(text: &str) -> FormattedString {
+    let mut spans = vec![];
+    let mut plain = String::new();
+    let mut rest = text;
+
+    while let Some(c) = rest.chars().next() {
+        let word_start = plain.is_empty() && spans.is_empty() || plain.ends_with(' ');
+        let styled = if let Some((_, _, len)) = reference_at(rest) {
+            Some((FormattedString::from(&rest[..len]).fg(YELLOW), len))
+        } else if let Some(len) = context_at(rest).filter(|_| word_start) {
+            Some((FormattedString::from(&rest[..len]).fg(PURPLE).bold(), len))
+        } else {
+            None
+        };
+        match styled {
+            Some((span, len)) => {
+                if !plain.is_empty() {
+                    spans.push(FormattedString::from(&plain));
+                    plain.clear();
+                }
+                spans.push(span);
+                rest = &rest[len..];
+            }
+            None => {
+                plain.push(c);
+                rest = &rest[c.len_utf8()..];
+            }
+        }
+    }
+    if !plain.is_empty() {
+        spans.push(FormattedString::from(&plain));
+    }
+    FormattedString::spans(spans)
+}
+
+pub fn render_inline(text: &str) -> String {
+    let mut rendered = String::new();
+    let mut rest = text;
+
+    while let Some(c) = rest.chars().next() {
+        if let Some((_, _, len)) = reference_at(rest) {
+            rendered.push_str(&FormattedString::from(&rest[..len]).fg(YELLOW).to_string());
+            rest = &rest[len..];
+        } else if let Some((code, tail)) = delimited(rest, "`", "`") {
+            rendered.push_str(&FormattedString::from(code).fg(PINK).to_string());
+            rest = tail;
+        } else if let Some((bold, tail)) = delimited(rest, "**", "**") {
+            rendered.push_str(&FormattedString::from(bold).bold().to_string());
+            rest = tail;
+        } else if let Some((italic, tail)) =
+            delimited(rest, "*", "*").or_else(|| delimited(rest, "_", "_"))
+        {
+            rendered.push_str(&FormattedString::from(italic).italic().to_string());
+            rest = tail;
+        } else if let Some((label, tail)) = delimited(rest, "[", "](") {
+            match tail.find(')') {
+                Some(end) => {
+                    rendered.push_str(&format!(
+                        "{} {}",
+                        FormattedString::from(label).underline().fg(BLUE),
+                        FormattedString::from(&format!("({})", &tail[..end])).fg(GRAY)
+                    ));
+                    rest = &tail[end + 1..];
+                }
+                None => {
+                    rendered.push(c);
+                    rest = &rest[c.len_utf8()..];
+                }
+            }
+        } else {
+            rendered.push(c);
+            rest = &rest[c.len_utf8()..];
+        }
+    }
+    rendered
+}
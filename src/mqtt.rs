@@ -0,0 +1,85 @@
+use std::io::{Error, ErrorKind, Read, Result, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::time::Duration;
+
+const TIMEOUT: Duration = Duration::from_secs(2);
+const KEEP_ALIVE: u16 = 60;
+
+fn default_port() -> u16 {
+    1883
+}
+
+fn default_client_id() -> String {
+    String::from("rtasks")
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct Mqtt {
+    pub host: String,
+    #[serde(default = "default_port")]
+    pub port: u16,
+    pub topic: String,
+    #[serde(default = "default_client_id")]
+    pub client_id: String,
+}
+
+fn string(value: &str) -> Vec<u8> {
+    let mut bytes = (value.len() as u16).to_be_bytes().to_vec();
+    bytes.extend_from_slice(value.as_bytes());
+    bytes
+}
+
+fn packet(kind: u8, body: Vec<u8>) -> Vec<u8> {
+    let mut packet = vec![kind];
+    let mut length = body.len();
+    loop {
+        let mut byte = (length % 128) as u8;
+        length /= 128;
+        if length > 0 {
+            byte |= 0x80;
+        }
+        packet.push(byte);
+        if length == 0 {
+            break;
+        }
+    }
+    packet.extend(body);
+    packet
+}
+
+impl Mqtt {
+    fn connect(&self) -> Result<TcpStream> {
+        let address = (self.host.as_str(), self.port)
+            .to_socket_addrs()?
+            .next()
+            .ok_or_else(|| Error::new(ErrorKind::NotFound, "Couldn't resolve MQTT host"))?;
+        let mut stream = TcpStream::connect_timeout(&address, TIMEOUT)?;
+        stream.set_read_timeout(Some(TIMEOUT))?;
+        stream.set_write_timeout(Some(TIMEOUT))?;
+
+        let mut body = string("MQTT");
+        body.push(4);
+        body.push(0x02);
+        body.extend_from_slice(&KEEP_ALIVE.to_be_bytes());
+        body.extend(string(&self.client_id));
+        stream.write_all(&packet(0x10, body))?;
+
+        let mut ack = [0; 4];
+        stream.read_exact(&mut ack)?;
+        match ack {
+            [0x20, 0x02, _, 0] => Ok(stream),
+            [_, _, _, code] => Err(Error::new(
+                ErrorKind::ConnectionRefused,
+                format!("MQTT broker refused the connection ({})", code),
+            )),
+        }
+    }
+
+    pub fn publish(&self, payload: &str) -> Result<()> {
+        let mut stream = self.connect()?;
+        let mut body = string(&self.topic);
+        body.extend_from_slice(payload.as_bytes());
+        stream.write_all(&packet(0x30, body))?;
+        stream.write_all(&packet(0xE0, vec![]))
+    }
+}
@@ -0,0 +1,101 @@
+use std::fs;
+use std::io::{stdin, stdout, Error, ErrorKind, Result, Write};
+use std::path::Path;
+
+use crate::config::{expand_home, Config};
+use crate::database::Database;
+use crate::project::*;
+use crate::theme::Palette;
+
+const THEMES: [&str; 3] = ["default", "colorblind", "high-contrast"];
+
+const SAMPLES: [(&str, &[&str]); 2] = [
+    (
+        "Getting started",
+        &[
+            "Press Enter on a project to open it, Esc to go back",
+            "Press + to add a task or a project",
+            "Press > and < to move a task between states",
+            "Press / to search across every project",
+            "Press Q and a letter to record a macro, \" and the letter to replay it",
+            "Press q to quit, your changes are saved as you go",
+        ],
+    ),
+    ("Personal", &["Plan the week", "Read a chapter of a book"]),
+];
+
+pub fn needed() -> bool {
+    let interactive = unsafe { libc::isatty(libc::STDIN_FILENO) == 1 };
+    let database = Database::storage()
+        .map(|storage| fs::metadata(storage).is_ok())
+        .unwrap_or(true);
+    interactive && !Config::exists() && !database
+}
+
+fn ask(question: &str, default: &str) -> Result<String> {
+    print!("{} [{}]: ", question, default);
+    stdout().flush()?;
+    let mut line = String::new();
+    if stdin().read_line(&mut line)? == 0 {
+        return Err(Error::new(ErrorKind::UnexpectedEof, "Setup cancelled"));
+    }
+    match line.trim() {
+        "" => Ok(default.to_string()),
+        answer => Ok(answer.to_string()),
+    }
+}
+
+fn theme() -> Result<String> {
+    loop {
+        let answer = ask(&format!("Theme ({})", THEMES.join(", ")), THEMES[0])?;
+        match Palette::from_name(&answer) {
+            Ok(_) => return Ok(answer),
+            Err(e) => println!("{}", e),
+        }
+    }
+}
+
+fn samples() -> Vec<Project> {
+    SAMPLES
+        .iter()
+        .map(|(description, tasks)| {
+            let mut project = Project::new(description.to_string());
+            project
+                .tasks
+                .extend(tasks.iter().map(|task| Task::new(task.to_string())));
+            project.rank_tasks();
+            project
+        })
+        .collect()
+}
+
+pub fn run() -> Result<()> {
+    println!("Welcome to rtasks! Let's get you set up.\n");
+    let default = Database::storage()?;
+    let storage = expand_home(&ask("Where should your tasks be stored?", &default)?);
+    let palette = theme()?;
+    let create = ask("Create sample projects to explore?", "Y/n")?;
+    let projects = match create.to_lowercase().as_str() {
+        "n" | "no" => vec![],
+        _ => samples(),
+    };
+
+    let mut config = vec![];
+    if storage != default {
+        config.push(format!(
+            "storage = {}\n",
+            toml::Value::String(storage.clone())
+        ));
+    }
+    config.push(format!("[theme]\npalette = \"{}\"\n", palette));
+    Config::write(&config.join("\n"))?;
+
+    if let Some(parent) = Path::new(&storage).parent() {
+        fs::create_dir_all(parent)?;
+    }
+    if fs::metadata(&storage).is_err() {
+        fs::write(&storage, serde_json::to_string(&projects)?)?;
+    }
+    println!("\nAll set! Your settings live in ~/.tasks/config.toml.");
+    Ok(())
+}
@@ -1,24 +1,40 @@
 use chrono::prelude::DateTime;
 use chrono::prelude::Local;
+use chrono::prelude::NaiveDate;
+use chrono::prelude::NaiveTime;
 use chrono::prelude::Utc;
+use chrono::Duration;
+use std::cell::RefCell;
+use std::collections::HashMap;
 use std::fmt;
 use std::fmt::Display;
 use std::fmt::Formatter;
+use std::sync::Arc;
 use termion::color::Rgb;
+use unicode_width::UnicodeWidthStr;
 use uuid::Uuid;
 
+use crate::columns::*;
 use crate::formatted_string::FormattedString;
+use crate::locale::{fill, locale};
+use crate::markdown;
+use crate::stats;
+use crate::theme::theme;
+use crate::timeline::{humanize, timestamp};
 
-static RED: Rgb = Rgb(192, 57, 43);
-static YELLOW: Rgb = Rgb(241, 196, 15);
 static GREEN: Rgb = Rgb(46, 204, 113);
 static PINK: Rgb = Rgb(200, 0, 150);
 static BLUE: Rgb = Rgb(52, 152, 219);
 static PURPLE: Rgb = Rgb(214, 162, 232);
+static YELLOW: Rgb = Rgb(241, 196, 15);
+static GRAY: Rgb = Rgb(127, 140, 141);
+
+const NARROW_WIDTH: usize = 100;
 
 #[derive(PartialEq, PartialOrd, Eq, Ord, Serialize, Deserialize, Copy, Clone, Debug)]
 pub enum State {
     ONGOING,
+    WAITING,
     TODO,
     DONE,
 }
@@ -27,6 +43,7 @@ impl State {
     pub fn next(&self) -> State {
         match self {
             State::TODO => State::ONGOING,
+            State::WAITING => State::ONGOING,
             State::ONGOING => State::DONE,
             State::DONE => State::DONE,
         }
@@ -35,6 +52,7 @@ impl State {
     pub fn previous(&self) -> State {
         match self {
             State::TODO => State::TODO,
+            State::WAITING => State::TODO,
             State::ONGOING => State::TODO,
             State::DONE => State::ONGOING,
         }
@@ -43,35 +61,403 @@ impl State {
 
 impl Display for State {
     fn fmt(&self, f: &mut Formatter) -> fmt::Result {
-        let name = match self {
-            State::TODO => FormattedString::from("TODO").right(7).fg(RED),
-            State::ONGOING => FormattedString::from("ONGOING").fg(YELLOW),
-            State::DONE => FormattedString::from("DONE").right(7).fg(GREEN),
+        let label: String = match state_width() {
+            1 => theme().state_label(*self).chars().take(1).collect(),
+            _ => theme().state_label(*self).to_string(),
         };
+        let name = FormattedString::from(theme().state_icon(*self).unwrap_or(&label))
+            .right(state_width())
+            .fg(theme().state_color(*self));
 
         write!(f, "{}", name)
     }
 }
 
+pub fn find_urls(text: &str) -> Vec<String> {
+    text.split_whitespace()
+        .filter(|word| word.contains("http://") || word.contains("https://"))
+        .map(|word| {
+            let start = word.find("http").unwrap();
+            word[start..]
+                .trim_end_matches(|c| ".,;:!?)]}>\"'".contains(c))
+                .to_string()
+        })
+        .collect()
+}
+
+pub fn reference_at(text: &str) -> Option<(String, usize, usize)> {
+    if !text.starts_with('#') {
+        return None;
+    }
+
+    let key_len = text[1..]
+        .find(|c: char| !c.is_ascii_alphanumeric())
+        .unwrap_or(text.len() - 1);
+    let rest = &text[1 + key_len..];
+    if key_len == 0 || !rest.starts_with('-') {
+        return None;
+    }
+
+    let number_len = rest[1..]
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(rest.len() - 1);
+    let number = rest[1..1 + number_len].parse().ok()?;
+    let key = text[1..1 + key_len].to_uppercase();
+    Some((key, number, 2 + key_len + number_len))
+}
+
+pub fn find_references(text: &str) -> Vec<(String, usize)> {
+    text.match_indices('#')
+        .filter_map(|(i, _)| reference_at(&text[i..]))
+        .map(|(key, number, _)| (key, number))
+        .collect()
+}
+
+pub fn context_at(text: &str) -> Option<usize> {
+    if !text.starts_with('@') {
+        return None;
+    }
+
+    match text[1..].find(|c: char| !(c.is_alphanumeric() || c == '-' || c == '_')) {
+        Some(0) => None,
+        Some(len) => Some(1 + len),
+        None if text.len() > 1 => Some(text.len()),
+        None => None,
+    }
+}
+
+pub fn derive_key(description: &str) -> String {
+    let key = description
+        .split_whitespace()
+        .next()
+        .unwrap_or_default()
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .collect::<String>()
+        .to_uppercase();
+    match key.is_empty() {
+        true => String::from("P"),
+        false => key,
+    }
+}
+
+pub fn find_contexts(text: &str) -> Vec<String> {
+    text.split_whitespace()
+        .filter_map(|word| context_at(word).map(|len| word[1..len].to_lowercase()))
+        .collect()
+}
+
+fn strip_list_marker(line: &str) -> &str {
+    let digits = line
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(line.len());
+    let numbered = &line[digits..];
+    let line = if digits > 0 && (numbered.starts_with(". ") || numbered.starts_with(") ")) {
+        &numbered[2..]
+    } else {
+        line
+    };
+
+    ["- ", "* ", "+ ", "[ ] ", "[x] "]
+        .iter()
+        .fold(line, |line, marker| {
+            if line.starts_with(marker) {
+                line[marker.len()..].trim_start()
+            } else {
+                line
+            }
+        })
+}
+
+pub fn task_lines(text: &str) -> Vec<String> {
+    text.lines()
+        .map(|line| strip_list_marker(line.trim()).trim())
+        .filter(|line| !line.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+fn normalize(text: &str) -> Vec<char> {
+    text.to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<&str>>()
+        .join(" ")
+        .chars()
+        .collect()
+}
+
+fn levenshtein(a: &[char], b: &[char]) -> usize {
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.iter().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + if ca == cb { 0 } else { 1 };
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+pub fn progress_cell(percent: u8) -> String {
+    let filled = percent.min(100) as usize / 20;
+    format!(
+        "{}{} {:>3}%",
+        "█".repeat(filled),
+        "░".repeat(5 - filled),
+        percent
+    )
+}
+
 fn div() -> FormattedString {
-    FormattedString::from("┃").fg(BLUE)
+    FormattedString::from(theme().vertical()).fg(BLUE)
 }
 
-#[derive(Serialize, Deserialize, Clone, Debug)]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 #[serde(tag = "type", rename_all = "lowercase")]
 pub enum Event {
     Description {
         data: String,
         date_time: DateTime<Utc>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        actor: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        host: Option<String>,
     },
     State {
         data: State,
         date_time: DateTime<Utc>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        actor: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        host: Option<String>,
     },
     Comment {
         data: String,
         date_time: DateTime<Utc>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        actor: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        host: Option<String>,
+    },
+    Points {
+        data: u32,
+        date_time: DateTime<Utc>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        actor: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        host: Option<String>,
+    },
+    Sprint {
+        data: Option<String>,
+        date_time: DateTime<Utc>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        actor: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        host: Option<String>,
+    },
+    Milestone {
+        data: Option<String>,
+        date_time: DateTime<Utc>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        actor: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        host: Option<String>,
+    },
+    Assignee {
+        data: Option<String>,
+        date_time: DateTime<Utc>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        actor: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        host: Option<String>,
     },
+    Attachment {
+        data: String,
+        date_time: DateTime<Utc>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        actor: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        host: Option<String>,
+    },
+    Branch {
+        data: String,
+        date_time: DateTime<Utc>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        actor: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        host: Option<String>,
+    },
+    Progress {
+        data: u8,
+        date_time: DateTime<Utc>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        actor: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        host: Option<String>,
+    },
+    Checklist {
+        data: Vec<ChecklistItem>,
+        date_time: DateTime<Utc>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        actor: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        host: Option<String>,
+    },
+    Notes {
+        data: String,
+        date_time: DateTime<Utc>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        actor: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        host: Option<String>,
+    },
+    Someday {
+        data: bool,
+        date_time: DateTime<Utc>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        actor: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        host: Option<String>,
+    },
+    Estimate {
+        data: u32,
+        date_time: DateTime<Utc>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        actor: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        host: Option<String>,
+    },
+    Due {
+        data: Option<NaiveDate>,
+        date_time: DateTime<Utc>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        actor: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        host: Option<String>,
+    },
+    Start {
+        data: Option<NaiveDate>,
+        date_time: DateTime<Utc>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        actor: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        host: Option<String>,
+    },
+    Waiting {
+        data: String,
+        date_time: DateTime<Utc>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        actor: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        host: Option<String>,
+    },
+    Retract {
+        data: usize,
+        date_time: DateTime<Utc>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        actor: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        host: Option<String>,
+    },
+    Amend {
+        data: Amendment,
+        date_time: DateTime<Utc>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        actor: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        host: Option<String>,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Amendment {
+    pub index: usize,
+    pub event: Box<Event>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ChecklistItem {
+    pub text: String,
+    pub done: bool,
+}
+
+impl Event {
+    pub fn date_time(&self) -> DateTime<Utc> {
+        match self {
+            Event::Description { date_time, .. } => *date_time,
+            Event::State { date_time, .. } => *date_time,
+            Event::Comment { date_time, .. } => *date_time,
+            Event::Points { date_time, .. } => *date_time,
+            Event::Sprint { date_time, .. } => *date_time,
+            Event::Milestone { date_time, .. } => *date_time,
+            Event::Assignee { date_time, .. } => *date_time,
+            Event::Attachment { date_time, .. } => *date_time,
+            Event::Branch { date_time, .. } => *date_time,
+            Event::Progress { date_time, .. } => *date_time,
+            Event::Checklist { date_time, .. } => *date_time,
+            Event::Notes { date_time, .. } => *date_time,
+            Event::Someday { date_time, .. } => *date_time,
+            Event::Estimate { date_time, .. } => *date_time,
+            Event::Due { date_time, .. } => *date_time,
+            Event::Start { date_time, .. } => *date_time,
+            Event::Waiting { date_time, .. } => *date_time,
+            Event::Retract { date_time, .. } => *date_time,
+            Event::Amend { date_time, .. } => *date_time,
+        }
+    }
+
+    pub fn origin(&self) -> (Option<&String>, Option<&String>) {
+        match self {
+            Event::Description { actor, host, .. }
+            | Event::State { actor, host, .. }
+            | Event::Comment { actor, host, .. }
+            | Event::Points { actor, host, .. }
+            | Event::Sprint { actor, host, .. }
+            | Event::Milestone { actor, host, .. }
+            | Event::Assignee { actor, host, .. }
+            | Event::Attachment { actor, host, .. }
+            | Event::Branch { actor, host, .. }
+            | Event::Progress { actor, host, .. }
+            | Event::Checklist { actor, host, .. }
+            | Event::Notes { actor, host, .. }
+            | Event::Someday { actor, host, .. }
+            | Event::Estimate { actor, host, .. }
+            | Event::Due { actor, host, .. }
+            | Event::Start { actor, host, .. }
+            | Event::Waiting { actor, host, .. }
+            | Event::Retract { actor, host, .. }
+            | Event::Amend { actor, host, .. } => (actor.as_ref(), host.as_ref()),
+        }
+    }
+
+    pub fn set_origin(&mut self, new_actor: Option<String>, new_host: Option<String>) {
+        match self {
+            Event::Description { actor, host, .. }
+            | Event::State { actor, host, .. }
+            | Event::Comment { actor, host, .. }
+            | Event::Points { actor, host, .. }
+            | Event::Sprint { actor, host, .. }
+            | Event::Milestone { actor, host, .. }
+            | Event::Assignee { actor, host, .. }
+            | Event::Attachment { actor, host, .. }
+            | Event::Branch { actor, host, .. }
+            | Event::Progress { actor, host, .. }
+            | Event::Checklist { actor, host, .. }
+            | Event::Notes { actor, host, .. }
+            | Event::Someday { actor, host, .. }
+            | Event::Estimate { actor, host, .. }
+            | Event::Due { actor, host, .. }
+            | Event::Start { actor, host, .. }
+            | Event::Waiting { actor, host, .. }
+            | Event::Retract { actor, host, .. }
+            | Event::Amend { actor, host, .. } => {
+                *actor = new_actor;
+                *host = new_host;
+            }
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
@@ -79,55 +465,318 @@ pub struct Task {
     pub id: String,
     pub created_at: DateTime<Utc>,
     pub events: Vec<Event>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rank: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub number: Option<usize>,
+    #[serde(skip)]
+    corrected: RefCell<Option<(usize, Arc<Vec<Event>>)>>,
 }
 
-#[derive(Serialize, Deserialize, Clone, Debug)]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Sprint {
+    pub name: String,
+    pub start: NaiveDate,
+    pub end: NaiveDate,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Milestone {
+    pub name: String,
+    pub target: NaiveDate,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct DayLog {
+    pub date: NaiveDate,
+    pub done: usize,
+    pub total: usize,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Daily {
+    pub reset_at: NaiveTime,
+    #[serde(default)]
+    pub last_reset: Option<NaiveDate>,
+    #[serde(default)]
+    pub log: Vec<DayLog>,
+}
+
+impl Daily {
+    pub fn new(reset_at: NaiveTime) -> Daily {
+        Daily {
+            reset_at: reset_at,
+            last_reset: Some(Daily::day_at(reset_at, Local::now())),
+            log: vec![],
+        }
+    }
+
+    fn day_at(reset_at: NaiveTime, now: DateTime<Local>) -> NaiveDate {
+        match now.time() >= reset_at {
+            true => now.date().naive_local(),
+            false => now.date().naive_local() - Duration::days(1),
+        }
+    }
+
+    pub fn day(&self, now: DateTime<Local>) -> NaiveDate {
+        Daily::day_at(self.reset_at, now)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ProjectOrder {
+    Alphabetical,
+    OpenTasks,
+    Activity,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 pub struct Project {
     pub id: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub key: String,
+    #[serde(default)]
+    pub next_number: usize,
     pub description: String,
     pub notes: String,
     pub tasks: Vec<Task>,
+    #[serde(default)]
+    pub sprints: Vec<Sprint>,
+    #[serde(default)]
+    pub milestones: Vec<Milestone>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub archive: Vec<Task>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rate: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub daily: Option<Daily>,
 }
 
 impl Project {
     pub fn new(description: String) -> Project {
         Project {
             id: Uuid::new_v4().to_string(),
+            key: derive_key(&description),
+            next_number: 1,
             description: description,
             notes: String::from(""),
             tasks: vec![],
+            sprints: vec![],
+            milestones: vec![],
+            archive: vec![],
+            rate: None,
+            daily: None,
+        }
+    }
+
+    pub fn archive_done(&mut self, cutoff: DateTime<Utc>) -> bool {
+        let expired = |task: &Task| match task.state_changes().last() {
+            Some((State::DONE, date_time)) => *date_time < cutoff,
+            _ => false,
+        };
+        let (archived, kept): (Vec<Task>, Vec<Task>) = self.tasks.drain(..).partition(expired);
+        self.tasks = kept;
+        let changed = !archived.is_empty();
+        self.archive.extend(archived);
+        changed
+    }
+
+    pub fn start_day(&mut self, now: DateTime<Local>) -> bool {
+        let day = match &self.daily {
+            Some(daily) => daily.day(now),
+            None => return false,
+        };
+        let done = self.task_state_count(State::DONE);
+        let total = self.task_count();
+        let daily = self.daily.as_mut().unwrap();
+        match daily.last_reset {
+            Some(last) if last >= day => return false,
+            Some(last) => daily.log.push(DayLog {
+                date: last,
+                done: done,
+                total: total,
+            }),
+            None => {}
+        }
+        daily.last_reset = Some(day);
+        true
+    }
+
+    pub fn sprint(&self, name: &str) -> Option<&Sprint> {
+        self.sprints.iter().find(|s| s.name == name)
+    }
+
+    pub fn milestone(&self, name: &str) -> Option<&Milestone> {
+        self.milestones.iter().find(|m| m.name == name)
+    }
+
+    pub fn rollover_sprints(&mut self) -> bool {
+        let today = Utc::now().date().naive_utc();
+        let current = self
+            .sprints
+            .iter()
+            .filter(|s| s.end >= today)
+            .min_by_key(|s| s.start)
+            .map(|s| s.name.clone());
+        let mut changed = false;
+
+        for task in self.tasks.iter_mut() {
+            if task.state() == State::DONE {
+                continue;
+            }
+            let closed = match task.sprint() {
+                Some(name) => self.sprints.iter().any(|s| s.name == name && s.end < today),
+                None => false,
+            };
+            if closed && current.is_some() {
+                task.events.push(Event::Sprint {
+                    data: current.clone(),
+                    date_time: Utc::now(),
+                    actor: None,
+                    host: None,
+                });
+                changed = true;
+            }
+        }
+        changed
+    }
+
+    pub fn rank_tasks(&mut self) {
+        let ranked = self.tasks.iter().filter_map(|t| t.rank).max();
+        let mut next = ranked.map_or(0, |rank| rank + 1);
+        for task in self.tasks.iter_mut().filter(|t| t.rank.is_none()) {
+            task.rank = Some(next);
+            next += 1;
+        }
+    }
+
+    pub fn number_tasks(&mut self) {
+        let highest = self
+            .tasks
+            .iter()
+            .chain(self.archive.iter())
+            .filter_map(|t| t.number)
+            .max();
+        self.next_number = self.next_number.max(highest.map_or(1, |n| n + 1));
+        let mut unnumbered: Vec<&mut Task> = self
+            .tasks
+            .iter_mut()
+            .filter(|t| t.number.is_none())
+            .collect();
+        unnumbered.sort_by(|a, b| (a.created_at, &a.id).cmp(&(b.created_at, &b.id)));
+        let archived = self.archive.iter_mut().filter(|t| t.number.is_none());
+        for task in unnumbered.into_iter().chain(archived) {
+            task.number = Some(self.next_number);
+            self.next_number += 1;
         }
     }
 
     pub fn sort_tasks(&mut self) {
-        self.tasks.sort_by(|a, b| a.state().cmp(&b.state()));
+        self.rank_tasks();
+        self.number_tasks();
+        self.tasks
+            .sort_by_key(|t| (t.state(), t.rank, t.created_at));
     }
 
     pub fn task_count(&self) -> usize {
-        self.tasks.len()
+        self.tasks.iter().filter(|t| !t.someday()).count()
+    }
+
+    pub fn title(&self) -> &str {
+        self.description.lines().next().unwrap_or_default()
+    }
+
+    pub fn key(&self) -> String {
+        match self.key.is_empty() {
+            true => derive_key(&self.description),
+            false => self.key.clone(),
+        }
+    }
+
+    pub fn task_number(&self, task: usize) -> usize {
+        self.tasks[task].number.unwrap_or_default()
+    }
+
+    pub fn task_by_number(&self, number: usize) -> Option<usize> {
+        self.tasks.iter().position(|t| t.number == Some(number))
     }
 
     pub fn task_position(&self, task_id: String) -> Option<usize> {
         self.tasks.iter().position(|t| t.id == task_id)
     }
 
+    pub fn similar_task(&self, description: &str) -> Option<usize> {
+        let wanted = normalize(description);
+        self.tasks
+            .iter()
+            .enumerate()
+            .filter(|(_, t)| t.state() != State::DONE)
+            .map(|(i, t)| {
+                let existing = normalize(&t.description());
+                let longest = wanted.len().max(existing.len()).max(1);
+                (i, levenshtein(&wanted, &existing) * 100 / longest)
+            })
+            .filter(|(_, distance)| *distance <= 20)
+            .min_by_key(|(_, distance)| *distance)
+            .map(|(i, _)| i)
+    }
+
+    pub fn open_task_count(&self) -> usize {
+        self.task_count() - self.task_state_count(State::DONE)
+    }
+
+    pub fn last_activity(&self) -> Option<DateTime<Utc>> {
+        self.tasks
+            .iter()
+            .flat_map(|t| t.events.iter().map(Event::date_time))
+            .max()
+    }
+
+    pub fn remaining(&self) -> Option<Duration> {
+        self.tasks
+            .iter()
+            .filter(|t| !t.someday())
+            .filter_map(Task::remaining)
+            .fold(None, |total, remaining| {
+                Some(total.unwrap_or_else(Duration::zero) + remaining)
+            })
+    }
+
     pub fn task_state_count(&self, state: State) -> usize {
         self.tasks
             .iter()
+            .filter(|t| !t.someday())
             .fold(0, |acc, t| acc + if t.state() == state { 1 } else { 0 })
     }
 
+    pub fn state_counts(&self) -> Vec<(State, usize)> {
+        [State::DONE, State::ONGOING, State::WAITING, State::TODO]
+            .iter()
+            .map(|state| (*state, self.task_state_count(*state)))
+            .collect()
+    }
+
+    pub fn completion(&self) -> u8 {
+        let tasks: Vec<&Task> = self.tasks.iter().filter(|t| !t.someday()).collect();
+        if tasks.is_empty() {
+            return 0;
+        }
+
+        let total: usize = tasks
+            .iter()
+            .map(|t| match t.state() {
+                State::DONE => 100,
+                _ => t.progress().unwrap_or(0) as usize,
+            })
+            .sum();
+        (total / tasks.len()) as u8
+    }
+
     pub fn header() -> String {
-        format!(
-            "{div_left}{tasks}{div}{todo}{div}{ongoing}{div}{done}{div}{desc}",
-            tasks = "Tasks",
-            todo = "To Do",
-            ongoing = "Ongoing",
-            done = "Done",
-            desc = FormattedString::from("Description").left(79),
-            div_left = div().left(2),
-            div = div().center(3)
-        )
+        project_row(|column, width| match column {
+            ProjectColumn::Description => FormattedString::from(&column.label()).left(width),
+            _ => FormattedString::from(&column.label()).center(width),
+        })
     }
 }
 
@@ -140,55 +789,410 @@ impl Task {
                 Event::State {
                     data: State::TODO,
                     date_time: Utc::now(),
+                    actor: None,
+                    host: None,
                 },
                 Event::Description {
                     data: description,
                     date_time: Utc::now(),
+                    actor: None,
+                    host: None,
                 },
             ],
+            rank: None,
+            number: None,
+            corrected: RefCell::new(None),
+        }
+    }
+
+    pub fn with_contexts(&self, description: &str) -> String {
+        let mut description = description.to_string();
+        let contexts = find_contexts(&description);
+        for context in self.contexts() {
+            if !contexts.contains(&context) {
+                description.push_str(&format!(" @{}", context));
+            }
+        }
+        description
+    }
+
+    pub fn split_off(&self, description: &str, origin: Option<&str>) -> Task {
+        let mut task = Task::new(self.with_contexts(description));
+        let now = Utc::now();
+        if let Some(sprint) = self.sprint() {
+            task.events.push(Event::Sprint {
+                data: Some(sprint),
+                date_time: now,
+                actor: None,
+                host: None,
+            });
         }
+        if let Some(milestone) = self.milestone() {
+            task.events.push(Event::Milestone {
+                data: Some(milestone),
+                date_time: now,
+                actor: None,
+                host: None,
+            });
+        }
+        if let Some(assignee) = self.assignee() {
+            task.events.push(Event::Assignee {
+                data: Some(assignee),
+                date_time: now,
+                actor: None,
+                host: None,
+            });
+        }
+        if let Some(origin) = origin {
+            task.events.push(Event::Comment {
+                data: format!("Split from {}", origin),
+                date_time: now,
+                actor: None,
+                host: None,
+            });
+        }
+        task
+    }
+
+    pub fn corrections(&self) -> HashMap<usize, Option<Event>> {
+        let mut corrections = HashMap::new();
+        for event in self.events.iter() {
+            match event {
+                Event::Retract { data, .. } => {
+                    corrections.insert(*data, None);
+                }
+                Event::Amend { data, .. } => {
+                    if corrections.get(&data.index).map_or(true, Option::is_some) {
+                        corrections.insert(data.index, Some(*data.event.clone()));
+                    }
+                }
+                _ => {}
+            }
+        }
+        corrections
+    }
+
+    pub fn history(&self) -> Arc<Vec<Event>> {
+        if let Some((length, history)) = &*self.corrected.borrow() {
+            if *length == self.events.len() {
+                return history.clone();
+            }
+        }
+        let corrections = self.corrections();
+        let history: Vec<Event> = self
+            .events
+            .iter()
+            .enumerate()
+            .filter_map(|(i, event)| match (event, corrections.get(&i)) {
+                (Event::Retract { .. }, _) | (Event::Amend { .. }, _) => None,
+                (_, Some(correction)) => correction.clone(),
+                (event, None) => Some(event.clone()),
+            })
+            .collect();
+        let history = Arc::new(history);
+        *self.corrected.borrow_mut() = Some((self.events.len(), history.clone()));
+        history
+    }
+
+    pub fn set_origin(&mut self, actor: Option<String>, host: Option<String>) {
+        for event in self.events.iter_mut() {
+            if event.origin() == (None, None) {
+                event.set_origin(actor.clone(), host.clone());
+            }
+        }
+        *self.corrected.get_mut() = None;
+    }
+
+    pub fn absorb(&mut self, other: &Task, origin: &str) {
+        let history = other.history();
+        let kept = history.iter().filter(|event| match event {
+            Event::Comment { .. } | Event::Attachment { .. } => true,
+            _ => false,
+        });
+        self.events.extend(kept.cloned());
+        let states: Vec<String> = other
+            .state_changes()
+            .iter()
+            .map(|(state, date_time)| format!("{:?} {}", state, date_time.format("%Y-%m-%d %H:%M")))
+            .collect();
+        self.events.push(Event::Comment {
+            data: format!(
+                "Merged {} ({}): {}",
+                origin,
+                other.description(),
+                states.join(", ")
+            ),
+            date_time: Utc::now(),
+            actor: None,
+            host: None,
+        });
+        self.sort_events();
+    }
+
+    pub fn sort_events(&mut self) {
+        let mut order: Vec<usize> = (0..self.events.len()).collect();
+        order.sort_by_key(|&i| self.events[i].date_time());
+        let mut position = vec![0; order.len()];
+        for (new, &old) in order.iter().enumerate() {
+            position[old] = new;
+        }
+        let mut events: Vec<Event> = order.iter().map(|&i| self.events[i].clone()).collect();
+        for event in events.iter_mut() {
+            match event {
+                Event::Retract { data, .. } => *data = position[*data],
+                Event::Amend { data, .. } => data.index = position[data.index],
+                _ => {}
+            }
+        }
+        self.events = events;
+        *self.corrected.get_mut() = None;
     }
 
     pub fn state(&self) -> State {
         let mut state = State::TODO;
-        for event in self.events.iter() {
-            if let Event::State { data, date_time: _ } = event {
+        for event in self.history().iter() {
+            if let Event::State { data, .. } = event {
                 state = data.clone()
             }
         }
         state
     }
 
-    fn description(&self) -> String {
+    pub fn state_changes(&self) -> Vec<(State, DateTime<Utc>)> {
+        self.history()
+            .iter()
+            .filter_map(|event| match event {
+                Event::State {
+                    data, date_time, ..
+                } => Some((*data, *date_time)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    pub fn waiting_on(&self) -> Option<(String, DateTime<Utc>)> {
+        if self.state() != State::WAITING {
+            return None;
+        }
+        let mut waiting_on = None;
+        for event in self.history().iter() {
+            if let Event::Waiting { data, .. } = event {
+                waiting_on = Some(data.clone())
+            }
+        }
+        let since = self.state_changes().last().map(|(_, since)| *since)?;
+        Some((waiting_on.unwrap_or_default(), since))
+    }
+
+    pub fn points(&self) -> Option<u32> {
+        let mut points = None;
+        for event in self.history().iter() {
+            if let Event::Points { data, .. } = event {
+                points = Some(*data)
+            }
+        }
+        points
+    }
+
+    pub fn estimate(&self) -> Option<Duration> {
+        let mut estimate = None;
+        for event in self.history().iter() {
+            if let Event::Estimate { data, .. } = event {
+                estimate = Some(Duration::minutes(*data as i64))
+            }
+        }
+        estimate
+    }
+
+    pub fn tracked(&self) -> Duration {
+        stats::time_in_state(self, State::ONGOING)
+    }
+
+    pub fn remaining(&self) -> Option<Duration> {
+        match self.state() {
+            State::DONE => None,
+            _ => self
+                .estimate()
+                .map(|estimate| (estimate - self.tracked()).max(Duration::zero())),
+        }
+    }
+
+    pub fn due(&self) -> Option<NaiveDate> {
+        let mut due = None;
+        for event in self.history().iter() {
+            if let Event::Due { data, .. } = event {
+                due = *data
+            }
+        }
+        due
+    }
+
+    pub fn start(&self) -> Option<NaiveDate> {
+        let mut start = None;
+        for event in self.history().iter() {
+            if let Event::Start { data, .. } = event {
+                start = *data
+            }
+        }
+        start
+    }
+
+    pub fn scheduled(&self) -> bool {
+        let today = Local::now().date().naive_local();
+        self.start().map(|start| start > today).unwrap_or(false)
+    }
+
+    pub fn sprint(&self) -> Option<String> {
+        let mut sprint = None;
+        for event in self.history().iter() {
+            if let Event::Sprint { data, .. } = event {
+                sprint = data.clone()
+            }
+        }
+        sprint
+    }
+
+    pub fn milestone(&self) -> Option<String> {
+        let mut milestone = None;
+        for event in self.history().iter() {
+            if let Event::Milestone { data, .. } = event {
+                milestone = data.clone()
+            }
+        }
+        milestone
+    }
+
+    pub fn assignee(&self) -> Option<String> {
+        let mut assignee = None;
+        for event in self.history().iter() {
+            if let Event::Assignee { data, .. } = event {
+                assignee = data.clone()
+            }
+        }
+        assignee
+    }
+
+    pub fn progress(&self) -> Option<u8> {
+        let mut progress = None;
+        for event in self.history().iter() {
+            if let Event::Progress { data, .. } = event {
+                progress = Some(*data)
+            }
+        }
+        progress
+    }
+
+    pub fn checklist(&self) -> Vec<ChecklistItem> {
+        let mut checklist = vec![];
+        for event in self.history().iter() {
+            if let Event::Checklist { data, .. } = event {
+                checklist = data.clone()
+            }
+        }
+        checklist
+    }
+
+    pub fn notes(&self) -> String {
+        let mut notes = String::from("");
+        for event in self.history().iter() {
+            if let Event::Notes { data, .. } = event {
+                notes = data.clone()
+            }
+        }
+        notes
+    }
+
+    pub fn shortcode(&self) -> String {
+        self.id.chars().take(8).collect()
+    }
+
+    pub fn branch(&self) -> Option<String> {
+        let mut branch = None;
+        for event in self.history().iter() {
+            if let Event::Branch { data, .. } = event {
+                branch = Some(data.clone())
+            }
+        }
+        branch
+    }
+
+    pub fn someday(&self) -> bool {
+        let mut someday = false;
+        for event in self.history().iter() {
+            if let Event::Someday { data, .. } = event {
+                someday = *data
+            }
+        }
+        someday
+    }
+
+    pub fn contexts(&self) -> Vec<String> {
+        let mut contexts = find_contexts(&self.description());
+        contexts.sort();
+        contexts.dedup();
+        contexts
+    }
+
+    pub fn references(&self) -> Vec<(String, usize)> {
+        let mut references = find_references(&self.description());
+        references.extend(find_references(&self.notes()));
+        for event in self.history().iter() {
+            if let Event::Comment { data, .. } = event {
+                references.extend(find_references(data));
+            }
+        }
+        references.dedup();
+        references
+    }
+
+    pub fn urls(&self) -> Vec<String> {
+        let mut urls = find_urls(&self.description());
+        urls.extend(find_urls(&self.notes()));
+        for event in self.history().iter() {
+            if let Event::Comment { data, .. } = event {
+                urls.extend(find_urls(data));
+            }
+        }
+        urls.dedup();
+        urls
+    }
+
+    pub fn description(&self) -> String {
         let mut description = String::from("");
-        for event in self.events.iter() {
-            if let Event::Description { data, date_time: _ } = event {
+        for event in self.history().iter() {
+            if let Event::Description { data, .. } = event {
                 description = data.clone()
             }
         }
         description
     }
 
-    fn created_at(&self) -> FormattedString {
-        let date = self
-            .created_at
-            .with_timezone(&Local::now().timezone())
-            .format("%Y-%m-%d %H:%M:%S")
-            .to_string();
-
-        FormattedString::from(&date).fg(PINK)
+    pub fn header() -> String {
+        task_row(|column, width| match column {
+            TaskColumn::State => {
+                let state: String = column.label().chars().take(width).collect();
+                FormattedString::from(&state).center(width)
+            }
+            _ => FormattedString::from(&truncate(&column.label(), width)).left(width),
+        })
     }
 
-    pub fn header() -> String {
-        let desc_width = termion::terminal_size().unwrap().0 as usize - 37;
-        format!(
-            "{div_left}{state}{div}{desc}{div}{date}",
-            state = FormattedString::from("State").center(7),
-            desc = FormattedString::from("Description").left(desc_width),
-            date = "Created At",
-            div_left = div().left(2),
-            div = div().center(3),
-        )
+    pub fn cell(&self, column: TaskColumn) -> String {
+        match column {
+            TaskColumn::State => theme().state_label(self.state()).to_string(),
+            TaskColumn::Progress => self.progress().map(progress_cell).unwrap_or_default(),
+            TaskColumn::Description => self.list_description(),
+            TaskColumn::Assignee => self.assignee().unwrap_or_default(),
+            TaskColumn::Created => timestamp(self.created_at, &locale().date_time_format),
+            TaskColumn::Id => self.shortcode(),
+            TaskColumn::Points => self.points().map(|p| p.to_string()).unwrap_or_default(),
+            TaskColumn::Sprint => self.sprint().unwrap_or_default(),
+            TaskColumn::Milestone => self.milestone().unwrap_or_default(),
+            TaskColumn::Due => self
+                .due()
+                .map(|due| due.format(&locale().date_format).to_string())
+                .unwrap_or_default(),
+        }
     }
 }
 
@@ -208,40 +1212,271 @@ impl Listable for &str {
     }
 }
 
-impl Listable for Task {
+pub fn terminal_width() -> usize {
+    termion::terminal_size()
+        .map(|(w, _)| w as usize)
+        .unwrap_or(80)
+}
+
+pub fn narrow() -> bool {
+    terminal_width() < NARROW_WIDTH
+}
+
+pub fn state_width() -> usize {
+    match theme().state_icon(State::TODO) {
+        None if narrow() => 1,
+        _ => theme().state_width(),
+    }
+}
+
+fn truncate(text: &str, width: usize) -> String {
+    if UnicodeWidthStr::width(text) <= width {
+        return text.to_string();
+    }
+
+    let mut truncated = String::new();
+    for c in text.chars() {
+        if UnicodeWidthStr::width(truncated.as_str()) + 2 > width {
+            break;
+        }
+        truncated.push(c);
+    }
+    if width > 0 {
+        truncated.push('…');
+    }
+    truncated
+}
+
+pub fn wrap(text: &str, width: usize) -> Vec<String> {
+    let mut lines = vec![];
+    let mut line = String::new();
+    for word in text.split_whitespace() {
+        let needed = UnicodeWidthStr::width(line.as_str()) + UnicodeWidthStr::width(word) + 1;
+        if !line.is_empty() && needed > width {
+            lines.push(line);
+            line = String::new();
+        }
+        if !line.is_empty() {
+            line.push(' ');
+        }
+        for c in word.chars() {
+            if !line.is_empty() && UnicodeWidthStr::width(line.as_str()) >= width.max(1) {
+                lines.push(line);
+                line = String::new();
+            }
+            line.push(c);
+        }
+    }
+    if !line.is_empty() || lines.is_empty() {
+        lines.push(line);
+    }
+    lines
+}
+
+pub struct Continuation(pub String);
+
+impl Listable for Continuation {
     fn view(&self) -> String {
-        let desc_width = termion::terminal_size().unwrap().0 as usize - 37;
-        format!(
-            "{div_left}{state}{div}{desc}{div}{date}",
-            state = self.state(),
-            desc = FormattedString::from(&self.description()).left(desc_width),
-            date = self.created_at(),
-            div_left = div().left(2),
-            div = div().center(3),
+        task_row(|column, width| match column {
+            TaskColumn::Description => {
+                let text = markdown::highlight(&self.0);
+                FormattedString::spans(vec![FormattedString::from("  "), text]).left(width)
+            }
+            _ => FormattedString::from("").left(width),
+        })
+    }
+}
+
+impl Task {
+    fn list_description(&self) -> String {
+        let checklist = self.checklist();
+        let mut description = self.description();
+        if !checklist.is_empty() {
+            let done = checklist.iter().filter(|item| item.done).count();
+            description = format!("{} [{}/{}]", description, done, checklist.len());
+        }
+        if let Some((who, since)) = self.waiting_on() {
+            let waited = humanize(Utc::now().signed_duration_since(since));
+            description = format!(
+                "{} ({})",
+                description,
+                fill(&fill(&locale().waiting_on, &who), &waited)
+            );
+        }
+        description
+    }
+
+    fn description_lines(&self) -> Vec<String> {
+        wrap(
+            &self.list_description(),
+            columns().description_width().saturating_sub(2),
         )
     }
+
+    pub fn continuations(&self) -> Vec<Continuation> {
+        if !theme().wrap {
+            return vec![];
+        }
+        self.description_lines()
+            .into_iter()
+            .skip(1)
+            .map(Continuation)
+            .collect()
+    }
 }
 
-impl Listable for Project {
+impl PartialEq for Task {
+    fn eq(&self, other: &Task) -> bool {
+        self.id == other.id
+            && self.created_at == other.created_at
+            && self.events == other.events
+            && self.rank == other.rank
+            && self.number == other.number
+    }
+}
+
+impl Listable for Task {
     fn view(&self) -> String {
-        let desc_width = termion::terminal_size().unwrap().0 as usize - 38;
+        task_row(|column, width| {
+            let text = match column {
+                TaskColumn::State => return FormattedString::from(&self.state().to_string()),
+                TaskColumn::Description if theme().wrap => self.description_lines().remove(0),
+                _ => truncate(&self.cell(column), width),
+            };
+            let cell = match column {
+                TaskColumn::Description => markdown::highlight(&text),
+                _ => FormattedString::from(&text),
+            }
+            .left(width);
+            match column {
+                TaskColumn::Progress => cell.fg(GREEN),
+                TaskColumn::Assignee => cell.fg(PURPLE),
+                TaskColumn::Created => cell.fg(PINK),
+                TaskColumn::Points => cell.fg(BLUE),
+                TaskColumn::Due => cell.fg(YELLOW),
+                TaskColumn::Description if self.scheduled() => cell.fg(GRAY),
+                _ => cell,
+            }
+        })
+    }
+}
+
+impl Listable for ChecklistItem {
+    fn view(&self) -> String {
+        let check = match self.done {
+            true => FormattedString::from("☑").right(10).fg(GREEN),
+            false => FormattedString::from("☐").right(10),
+        };
+
         format!(
-            "{div_left}{tasks}{div}{todo}{div}{ongoing}{div}{done}{div}{desc}",
-            tasks = FormattedString::from(&self.task_count().to_string())
-                .fg(PURPLE)
-                .center(5),
-            todo = FormattedString::from(&self.task_state_count(State::TODO).to_string())
-                .fg(RED)
-                .center(5),
-            ongoing = FormattedString::from(&self.task_state_count(State::ONGOING).to_string())
-                .fg(YELLOW)
-                .center(7),
-            done = FormattedString::from(&self.task_state_count(State::DONE).to_string())
-                .fg(GREEN)
-                .center(4),
-            desc = FormattedString::from(&self.description).left(desc_width),
+            "{div_left}{check}{div}{text}",
+            check = check,
+            text = self.text,
             div_left = div().left(2),
             div = div().center(3)
         )
     }
 }
+
+impl Listable for Project {
+    fn view(&self) -> String {
+        project_row(|column, width| {
+            let count = |state: State| {
+                FormattedString::from(&self.task_state_count(state).to_string())
+                    .fg(theme().state_color(state))
+                    .center(width)
+            };
+            match column {
+                ProjectColumn::Tasks => FormattedString::from(&self.task_count().to_string())
+                    .fg(PURPLE)
+                    .center(width),
+                ProjectColumn::Todo => count(State::TODO),
+                ProjectColumn::Ongoing => count(State::ONGOING),
+                ProjectColumn::Waiting => count(State::WAITING),
+                ProjectColumn::Done => count(State::DONE),
+                ProjectColumn::Complete => {
+                    FormattedString::from(&format!("{}%", self.completion()))
+                        .fg(BLUE)
+                        .center(width)
+                }
+                ProjectColumn::Remaining => {
+                    FormattedString::from(&self.remaining().map(humanize).unwrap_or_default())
+                        .fg(PURPLE)
+                        .center(width)
+                }
+                ProjectColumn::Bar => state_bar(&self.state_counts(), width),
+                ProjectColumn::Description => {
+                    let title = match self.description.lines().nth(1) {
+                        Some(_) => format!("{}…", self.title()),
+                        None => self.title().to_string(),
+                    };
+                    match self.daily {
+                        Some(_) => FormattedString::from(&format!("↻ {}", title)),
+                        None => FormattedString::from(&title),
+                    }
+                    .left(width)
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn comment(data: &str, minutes: i64) -> Event {
+        Event::Comment {
+            data: data.to_string(),
+            date_time: Utc::now() - Duration::minutes(minutes),
+            actor: None,
+            host: None,
+        }
+    }
+
+    #[test]
+    fn sort_events_remaps_corrections() {
+        let mut task = Task::new(String::from("task"));
+        task.events = vec![comment("late", 10), comment("early", 30)];
+        task.events.push(Event::Retract {
+            data: 0,
+            date_time: Utc::now(),
+            actor: None,
+            host: None,
+        });
+        task.events.push(Event::Amend {
+            data: Amendment {
+                index: 1,
+                event: Box::new(comment("amended", 30)),
+            },
+            date_time: Utc::now(),
+            actor: None,
+            host: None,
+        });
+        task.sort_events();
+
+        match (&task.events[2], &task.events[3]) {
+            (Event::Retract { data: 1, .. }, Event::Amend { data, .. }) => {
+                assert_eq!(data.index, 0)
+            }
+            events => panic!("unexpected events {:?}", events),
+        }
+        let comments: Vec<String> = task
+            .history()
+            .iter()
+            .filter_map(|event| match event {
+                Event::Comment { data, .. } => Some(data.clone()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(comments, vec![String::from("amended")]);
+    }
+
+    #[test]
+    fn history_follows_new_events() {
+        let mut task = Task::new(String::from("task"));
+        assert_eq!(task.history().len(), 2);
+        task.events.push(comment("new", 0));
+        assert_eq!(task.history().len(), 3);
+    }
+}
@@ -0,0 +1,351 @@
+use chrono::prelude::NaiveDate;
+use std::cmp::Ordering;
+use std::io::{Error, ErrorKind, Result};
+use std::iter::Peekable;
+use std::str::Chars;
+use std::vec::IntoIter;
+
+use crate::config::Config;
+use crate::project::*;
+use crate::stats;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Op {
+    Eq,
+    Ne,
+    Contains,
+    NotContains,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Field {
+    Id,
+    State,
+    Project,
+    Description,
+    Assignee,
+    Sprint,
+    Milestone,
+    Comment,
+    Notes,
+    Context,
+    Points,
+    Progress,
+    Created,
+    Completed,
+}
+
+#[derive(Clone, Debug)]
+pub enum Operand {
+    Text(String),
+    Number(u32),
+    Date(NaiveDate),
+    State(State),
+}
+
+#[derive(Clone, Debug)]
+pub enum Query {
+    All,
+    And(Box<Query>, Box<Query>),
+    Or(Box<Query>, Box<Query>),
+    Not(Box<Query>),
+    Compare(Field, Op, Operand),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Word(String),
+    Op(Op),
+    Open,
+    Close,
+}
+
+fn invalid(message: String) -> Error {
+    Error::new(ErrorKind::InvalidInput, message)
+}
+
+fn followed_by(chars: &mut Peekable<Chars>, next: char) -> bool {
+    if chars.peek() == Some(&next) {
+        chars.next();
+        true
+    } else {
+        false
+    }
+}
+
+fn tokenize(input: &str) -> Result<Vec<Token>> {
+    let mut tokens = vec![];
+    let mut chars = input.chars().peekable();
+
+    while let Some(&c) = chars.peek() {
+        match c {
+            _ if c.is_whitespace() => {
+                chars.next();
+            }
+            '(' | ')' => {
+                chars.next();
+                tokens.push(if c == '(' { Token::Open } else { Token::Close });
+            }
+            '=' | '!' | '~' | '<' | '>' => {
+                chars.next();
+                tokens.push(Token::Op(match c {
+                    '=' => Op::Eq,
+                    '~' => Op::Contains,
+                    '!' if followed_by(&mut chars, '=') => Op::Ne,
+                    '!' if followed_by(&mut chars, '~') => Op::NotContains,
+                    '<' if followed_by(&mut chars, '=') => Op::Le,
+                    '<' => Op::Lt,
+                    '>' if followed_by(&mut chars, '=') => Op::Ge,
+                    '>' => Op::Gt,
+                    _ => return Err(invalid(format!("Unexpected '{}'", c))),
+                }));
+            }
+            '"' => {
+                chars.next();
+                let word: String = chars.by_ref().take_while(|c| *c != '"').collect();
+                tokens.push(Token::Word(word));
+            }
+            _ => {
+                let mut word = String::new();
+                while let Some(&c) = chars.peek() {
+                    if c.is_whitespace() || "()=!~<>\"".contains(c) {
+                        break;
+                    }
+                    word.push(c);
+                    chars.next();
+                }
+                tokens.push(Token::Word(word));
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+fn parse_field(name: &str) -> Result<Field> {
+    match name.to_lowercase().as_str() {
+        "id" => Ok(Field::Id),
+        "state" => Ok(Field::State),
+        "project" => Ok(Field::Project),
+        "description" | "desc" => Ok(Field::Description),
+        "assignee" => Ok(Field::Assignee),
+        "sprint" => Ok(Field::Sprint),
+        "milestone" => Ok(Field::Milestone),
+        "comment" => Ok(Field::Comment),
+        "notes" => Ok(Field::Notes),
+        "context" => Ok(Field::Context),
+        "points" => Ok(Field::Points),
+        "progress" => Ok(Field::Progress),
+        "created" => Ok(Field::Created),
+        "completed" => Ok(Field::Completed),
+        _ => Err(invalid(format!("Unknown field: {}", name))),
+    }
+}
+
+fn parse_operand(field: Field, op: Op, value: &str, config: &Config) -> Result<Operand> {
+    let ordered = match op {
+        Op::Lt | Op::Le | Op::Gt | Op::Ge => true,
+        _ => false,
+    };
+    let textual = match op {
+        Op::Contains | Op::NotContains => true,
+        _ => false,
+    };
+
+    match field {
+        Field::Points | Field::Progress if !textual => value
+            .parse()
+            .map(Operand::Number)
+            .map_err(|_| invalid(format!("Expected a number, got: {}", value))),
+        Field::Created | Field::Completed if !textual => {
+            NaiveDate::parse_from_str(value, "%Y-%m-%d")
+                .map(Operand::Date)
+                .map_err(|_| invalid(format!("Expected a YYYY-MM-DD date, got: {}", value)))
+        }
+        Field::State if !textual && !ordered => match value.to_lowercase().as_str() {
+            "todo" => Ok(Operand::State(State::TODO)),
+            "ongoing" => Ok(Operand::State(State::ONGOING)),
+            "waiting" => Ok(Operand::State(State::WAITING)),
+            "done" => Ok(Operand::State(State::DONE)),
+            _ => Err(invalid(format!("Unknown state: {}", value))),
+        },
+        Field::Context => Ok(Operand::Text(value.trim_start_matches('@').to_lowercase())),
+        Field::Assignee if value == "me" => Ok(Operand::Text(
+            config.me().unwrap_or_default().to_lowercase(),
+        )),
+        Field::Id
+        | Field::Project
+        | Field::Description
+        | Field::Assignee
+        | Field::Sprint
+        | Field::Milestone
+        | Field::Comment
+        | Field::Notes => Ok(Operand::Text(value.to_lowercase())),
+        _ => Err(invalid(format!(
+            "Operator {:?} isn't supported for {:?}",
+            op, field
+        ))),
+    }
+}
+
+struct Parser<'a> {
+    tokens: Peekable<IntoIter<Token>>,
+    config: &'a Config,
+}
+
+impl<'a> Parser<'a> {
+    fn keyword(&mut self, keyword: &str) -> bool {
+        match self.tokens.peek() {
+            Some(Token::Word(word)) if word.eq_ignore_ascii_case(keyword) => {
+                self.tokens.next();
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn expression(&mut self) -> Result<Query> {
+        let mut query = self.term()?;
+        while self.keyword("or") {
+            query = Query::Or(Box::new(query), Box::new(self.term()?));
+        }
+        Ok(query)
+    }
+
+    fn term(&mut self) -> Result<Query> {
+        let mut query = self.factor()?;
+        while self.keyword("and") {
+            query = Query::And(Box::new(query), Box::new(self.factor()?));
+        }
+        Ok(query)
+    }
+
+    fn factor(&mut self) -> Result<Query> {
+        if self.keyword("not") {
+            return Ok(Query::Not(Box::new(self.factor()?)));
+        }
+
+        match self.tokens.next() {
+            Some(Token::Open) => {
+                let query = self.expression()?;
+                match self.tokens.next() {
+                    Some(Token::Close) => Ok(query),
+                    _ => Err(invalid(String::from("Expected ')'"))),
+                }
+            }
+            Some(Token::Word(name)) => {
+                let field = parse_field(&name)?;
+                match (self.tokens.next(), self.tokens.next()) {
+                    (Some(Token::Op(op)), Some(Token::Word(value))) => Ok(Query::Compare(
+                        field,
+                        op,
+                        parse_operand(field, op, &value, self.config)?,
+                    )),
+                    _ => Err(invalid(format!("Expected a comparison after {}", name))),
+                }
+            }
+            Some(token) => Err(invalid(format!("Unexpected {:?}", token))),
+            None => Err(invalid(String::from("Unexpected end of query"))),
+        }
+    }
+}
+
+fn compare<T: Ord>(value: Option<T>, op: Op, operand: T) -> bool {
+    match value {
+        Some(value) => {
+            let ordering = value.cmp(&operand);
+            match op {
+                Op::Eq => ordering == Ordering::Equal,
+                Op::Ne => ordering != Ordering::Equal,
+                Op::Lt => ordering == Ordering::Less,
+                Op::Le => ordering != Ordering::Greater,
+                Op::Gt => ordering == Ordering::Greater,
+                Op::Ge => ordering != Ordering::Less,
+                Op::Contains | Op::NotContains => false,
+            }
+        }
+        None => op == Op::Ne,
+    }
+}
+
+fn compare_text(values: Vec<String>, op: Op, operand: &str) -> bool {
+    let values: Vec<String> = values.iter().map(|v| v.to_lowercase()).collect();
+    match op {
+        Op::Contains => values.iter().any(|v| v.contains(operand)),
+        Op::NotContains => !values.iter().any(|v| v.contains(operand)),
+        Op::Ne => !values.iter().any(|v| v == operand),
+        _ => values
+            .into_iter()
+            .any(|v| compare(Some(v), op, operand.to_string())),
+    }
+}
+
+impl Query {
+    pub fn parse(input: &str, config: &Config) -> Result<Query> {
+        let mut parser = Parser {
+            tokens: tokenize(input)?.into_iter().peekable(),
+            config: config,
+        };
+
+        if parser.tokens.peek().is_none() {
+            return Ok(Query::All);
+        }
+
+        let query = parser.expression()?;
+        match parser.tokens.next() {
+            Some(token) => Err(invalid(format!("Unexpected {:?}", token))),
+            None => Ok(query),
+        }
+    }
+
+    pub fn matches(&self, project: &Project, task: &Task) -> bool {
+        match self {
+            Query::All => true,
+            Query::And(left, right) => left.matches(project, task) && right.matches(project, task),
+            Query::Or(left, right) => left.matches(project, task) || right.matches(project, task),
+            Query::Not(query) => !query.matches(project, task),
+            Query::Compare(field, op, Operand::Text(text)) => {
+                let values = match field {
+                    Field::Id => vec![task.id.clone()],
+                    Field::Project => vec![project.title().to_string()],
+                    Field::Description => vec![task.description()],
+                    Field::Assignee => task.assignee().into_iter().collect(),
+                    Field::Sprint => task.sprint().into_iter().collect(),
+                    Field::Milestone => task.milestone().into_iter().collect(),
+                    Field::Notes => vec![task.notes()],
+                    Field::Context => task.contexts(),
+                    Field::Comment => task
+                        .history()
+                        .iter()
+                        .filter_map(|event| match event {
+                            Event::Comment { data, .. } => Some(data.clone()),
+                            _ => None,
+                        })
+                        .collect(),
+                    _ => vec![],
+                };
+                compare_text(values, *op, text)
+            }
+            Query::Compare(field, op, Operand::Number(number)) => {
+                let value = match field {
+                    Field::Points => task.points(),
+                    _ => task.progress().map(u32::from),
+                };
+                compare(value, *op, *number)
+            }
+            Query::Compare(field, op, Operand::Date(date)) => {
+                let value = match field {
+                    Field::Created => Some(task.created_at),
+                    _ => stats::completed_at(task),
+                };
+                compare(value.map(|v| v.date().naive_utc()), *op, *date)
+            }
+            Query::Compare(_, op, Operand::State(state)) => {
+                compare(Some(task.state()), *op, *state)
+            }
+        }
+    }
+}
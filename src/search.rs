@@ -0,0 +1,135 @@
+use std::collections::BTreeMap;
+use termion::color::Rgb;
+
+use crate::formatted_string::FormattedString;
+use crate::project::*;
+
+static YELLOW: Rgb = Rgb(241, 196, 15);
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Posting {
+    pub project: usize,
+    pub task: usize,
+    weight: u32,
+}
+
+#[derive(Debug)]
+pub struct Hit {
+    pub project: usize,
+    pub task: usize,
+    pub score: u32,
+}
+
+pub fn highlight(text: &str, query: &str) -> FormattedString {
+    let terms = tokenize(query);
+    let mut spans = vec![];
+    let mut rest = text;
+
+    while let Some(c) = rest.chars().next() {
+        let word = c.is_alphanumeric();
+        let len = rest
+            .find(|n: char| n.is_alphanumeric() != word)
+            .unwrap_or(rest.len());
+        let part = &rest[..len];
+        let lowered = part.to_lowercase();
+        let matched = word && terms.iter().any(|term| lowered.starts_with(term.as_str()));
+        spans.push(match matched {
+            true => FormattedString::from(part).fg(YELLOW).bold(),
+            false => FormattedString::from(part),
+        });
+        rest = &rest[len..];
+    }
+    FormattedString::spans(spans)
+}
+
+#[derive(Default)]
+pub struct Index {
+    postings: BTreeMap<String, Vec<Posting>>,
+}
+
+pub fn tokenize(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+impl Index {
+    pub fn build(projects: &[Project]) -> Index {
+        let mut index = Index::default();
+
+        for (p, project) in projects.iter().enumerate() {
+            for (t, task) in project.tasks.iter().enumerate() {
+                index.add(p, t, &task.description(), 3);
+                index.add(p, t, &task.notes(), 2);
+                for item in task.checklist() {
+                    index.add(p, t, &item.text, 2);
+                }
+                for event in task.history().iter() {
+                    if let Event::Comment { data, .. } = event {
+                        index.add(p, t, data, 1);
+                    }
+                }
+            }
+        }
+        index
+    }
+
+    fn add(&mut self, project: usize, task: usize, text: &str, weight: u32) {
+        for token in tokenize(text) {
+            self.postings
+                .entry(token)
+                .or_insert_with(Vec::new)
+                .push(Posting {
+                    project: project,
+                    task: task,
+                    weight: weight,
+                });
+        }
+    }
+
+    fn lookup(&self, term: &str) -> BTreeMap<(usize, usize), u32> {
+        let mut scores = BTreeMap::new();
+        let prefixed = self
+            .postings
+            .range(term.to_string()..)
+            .take_while(|(token, _)| token.starts_with(term));
+
+        for (token, postings) in prefixed {
+            let exact = if token == term { 2 } else { 1 };
+            for posting in postings {
+                *scores.entry((posting.project, posting.task)).or_insert(0) +=
+                    posting.weight * exact;
+            }
+        }
+        scores
+    }
+
+    pub fn search(&self, query: &str) -> Vec<Hit> {
+        let terms = tokenize(query);
+        let mut scores: Option<BTreeMap<(usize, usize), u32>> = None;
+
+        for term in terms.iter() {
+            let matches = self.lookup(term);
+            scores = Some(match scores {
+                None => matches,
+                Some(previous) => previous
+                    .into_iter()
+                    .filter_map(|(key, score)| matches.get(&key).map(|s| (key, score + s)))
+                    .collect(),
+            });
+        }
+
+        let mut hits: Vec<Hit> = scores
+            .unwrap_or_default()
+            .into_iter()
+            .map(|((project, task), score)| Hit {
+                project: project,
+                task: task,
+                score: score,
+            })
+            .collect();
+        hits.sort_by(|a, b| b.score.cmp(&a.score));
+        hits
+    }
+}
@@ -0,0 +1,100 @@
+use std::io::{BufRead, BufReader, Result, Write};
+use std::net::{TcpListener, TcpStream};
+use std::time::Duration;
+
+use crate::database::Database;
+use crate::project::*;
+
+pub const DEFAULT_PORT: u16 = 9464;
+const READ_TIMEOUT: Duration = Duration::from_secs(5);
+
+const STATES: [State; 4] = [State::TODO, State::ONGOING, State::WAITING, State::DONE];
+
+fn label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+fn completions(task: &Task) -> usize {
+    task.state_changes()
+        .iter()
+        .filter(|(state, _)| *state == State::DONE)
+        .count()
+}
+
+pub fn metrics(db: &Database) -> String {
+    let mut lines = vec![
+        String::from("# HELP rtasks_tasks Tasks per project and state."),
+        String::from("# TYPE rtasks_tasks gauge"),
+    ];
+    for project in db.projects() {
+        for state in STATES.iter() {
+            lines.push(format!(
+                "rtasks_tasks{{project=\"{}\",state=\"{}\"}} {}",
+                label(project.title()),
+                format!("{:?}", state).to_lowercase(),
+                project.tasks.iter().filter(|t| t.state() == *state).count()
+            ));
+        }
+    }
+
+    lines.push(String::from(
+        "# HELP rtasks_completions Moves to DONE recorded on the current and archived tasks.",
+    ));
+    lines.push(String::from("# TYPE rtasks_completions gauge"));
+    for project in db.projects() {
+        let total: usize = project
+            .tasks
+            .iter()
+            .chain(project.archive.iter())
+            .map(completions)
+            .sum();
+        lines.push(format!(
+            "rtasks_completions{{project=\"{}\"}} {}",
+            label(project.title()),
+            total
+        ));
+    }
+    lines.push(String::from("# EOF"));
+    lines.join("\n") + "\n"
+}
+
+fn respond(mut stream: TcpStream) -> Result<()> {
+    stream.set_read_timeout(Some(READ_TIMEOUT))?;
+    let mut request = String::new();
+    BufReader::new(&stream).read_line(&mut request)?;
+
+    let (status, content_type, body) = match request.split_whitespace().nth(1) {
+        Some("/metrics") => (
+            "200 OK",
+            "application/openmetrics-text; version=1.0.0; charset=utf-8",
+            metrics(&Database::read_only()?),
+        ),
+        _ => (
+            "404 Not Found",
+            "text/plain; charset=utf-8",
+            String::from("Not found\n"),
+        ),
+    };
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        content_type,
+        body.len(),
+        body
+    )
+}
+
+pub fn run(port: u16) -> Result<()> {
+    let listener = TcpListener::bind(("127.0.0.1", port))?;
+    println!("Serving metrics on http://127.0.0.1:{}/metrics", port);
+    for stream in listener.incoming() {
+        if let Err(e) = stream.and_then(respond) {
+            tracing::warn!(error = %e, "metrics request failed");
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,86 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io::{Error, ErrorKind, Result};
+
+const RECENT_LIMIT: usize = 20;
+pub const FAVORITES_LIMIT: usize = 9;
+
+#[derive(Serialize, Deserialize, Default, Clone, Debug)]
+#[serde(default)]
+pub struct ProjectSettings {
+    pub filter: String,
+    pub someday: bool,
+    pub all_done: bool,
+}
+
+#[derive(Serialize, Deserialize, Default, Debug)]
+#[serde(default)]
+pub struct Session {
+    pub recent: Vec<String>,
+    pub projects: HashMap<String, ProjectSettings>,
+    pub favorites: Vec<String>,
+    #[serde(skip)]
+    pub ephemeral: bool,
+}
+
+impl Session {
+    fn storage() -> Result<String> {
+        match dirs::home_dir() {
+            Some(path) => Ok(format!(
+                "{}{}",
+                path.to_str().unwrap(),
+                "/.tasks/session.json"
+            )),
+            None => Err(Error::new(
+                ErrorKind::Other,
+                "Couldn't resolve your home directory",
+            )),
+        }
+    }
+
+    pub fn load() -> Result<Session> {
+        match fs::read_to_string(Session::storage()?) {
+            Ok(content) => Ok(serde_json::from_str(&content)?),
+            Err(ref e) if e.kind() == ErrorKind::NotFound => Ok(Session::default()),
+            Err(e) => Err(e),
+        }
+    }
+
+    pub fn save(&self) -> Result<()> {
+        if self.ephemeral {
+            return Ok(());
+        }
+        let content = serde_json::to_string(self)?;
+        fs::write(Session::storage()?, content)
+    }
+
+    pub fn settings(&self, project_id: &str) -> ProjectSettings {
+        self.projects.get(project_id).cloned().unwrap_or_default()
+    }
+
+    pub fn settings_mut(&mut self, project_id: &str) -> &mut ProjectSettings {
+        self.projects.entry(project_id.to_string()).or_default()
+    }
+
+    pub fn touch(&mut self, task_id: &str) -> bool {
+        if self.recent.first().map(String::as_str) == Some(task_id) {
+            return false;
+        }
+        self.recent.retain(|id| id != task_id);
+        self.recent.insert(0, task_id.to_string());
+        self.recent.truncate(RECENT_LIMIT);
+        true
+    }
+
+    pub fn toggle_favorite(&mut self, project_id: &str) -> Option<bool> {
+        if self.favorites.iter().any(|id| id == project_id) {
+            self.favorites.retain(|id| id != project_id);
+            return Some(false);
+        }
+        if self.favorites.len() >= FAVORITES_LIMIT {
+            return None;
+        }
+        self.favorites.push(project_id.to_string());
+        Some(true)
+    }
+}
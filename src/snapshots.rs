@@ -0,0 +1,40 @@
+use chrono::prelude::{DateTime, Local};
+use std::io::Result;
+use std::path::Path;
+
+use crate::git;
+use crate::project::Project;
+
+const COMMIT_LIMIT: usize = 50;
+
+pub struct Snapshot {
+    pub taken: DateTime<Local>,
+    pub label: String,
+    hash: String,
+}
+
+impl Snapshot {
+    pub fn title(&self) -> String {
+        format!("{}  {}", self.taken.format("%Y-%m-%d %H:%M"), self.label)
+    }
+
+    pub fn load(&self, storage: &str) -> Result<Vec<Project>> {
+        let content = git::file_at(Path::new(storage), &self.hash)?;
+        Ok(serde_json::from_str(&content)?)
+    }
+}
+
+pub fn list(storage: &str) -> Vec<Snapshot> {
+    git::file_log(Path::new(storage), COMMIT_LIMIT)
+        .into_iter()
+        .map(|commit| Snapshot {
+            taken: commit.date.with_timezone(&Local),
+            label: format!(
+                "{} {}",
+                &commit.hash[..7.min(commit.hash.len())],
+                commit.subject
+            ),
+            hash: commit.hash,
+        })
+        .collect()
+}
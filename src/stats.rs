@@ -0,0 +1,547 @@
+use chrono::prelude::DateTime;
+use chrono::prelude::Datelike;
+use chrono::prelude::NaiveDate;
+use chrono::prelude::Utc;
+use chrono::Duration;
+use termion::color::Rgb;
+
+use crate::formatted_string::FormattedString;
+use crate::locale::{fill, locale};
+use crate::project::*;
+use crate::theme::theme;
+use crate::timeline::humanize;
+
+static RED: Rgb = Rgb(192, 57, 43);
+static YELLOW: Rgb = Rgb(241, 196, 15);
+static GREEN: Rgb = Rgb(46, 204, 113);
+static GRAY: Rgb = Rgb(127, 140, 141);
+
+pub struct Summary {
+    pub count: usize,
+    pub average: Duration,
+    pub p50: Duration,
+    pub p85: Duration,
+}
+
+pub fn time_in_state(task: &Task, state: State) -> Duration {
+    let changes = task.state_changes();
+    let mut total = Duration::zero();
+
+    for (i, (current, since)) in changes.iter().enumerate() {
+        if *current == state {
+            let until = match changes.get(i + 1) {
+                Some((_, until)) => *until,
+                None => Utc::now(),
+            };
+            total = total + until.signed_duration_since(*since);
+        }
+    }
+    total
+}
+
+pub fn completed_at(task: &Task) -> Option<DateTime<Utc>> {
+    match task.state_changes().last() {
+        Some((State::DONE, date_time)) => Some(*date_time),
+        _ => None,
+    }
+}
+
+pub fn lead_time(task: &Task) -> Option<Duration> {
+    completed_at(task).map(|done| done.signed_duration_since(task.created_at))
+}
+
+pub fn cycle_time(task: &Task) -> Option<Duration> {
+    let started = task
+        .state_changes()
+        .into_iter()
+        .find(|(state, _)| *state == State::ONGOING)
+        .map(|(_, date_time)| date_time)?;
+
+    completed_at(task).map(|done| done.signed_duration_since(started))
+}
+
+pub fn summarize(mut durations: Vec<Duration>) -> Option<Summary> {
+    if durations.is_empty() {
+        return None;
+    }
+
+    durations.sort();
+    let count = durations.len();
+    let total = durations.iter().fold(Duration::zero(), |acc, d| acc + *d);
+    let percentile = |p: usize| durations[((count * p + 99) / 100).max(1) - 1];
+
+    Some(Summary {
+        count: count,
+        average: total / count as i32,
+        p50: percentile(50),
+        p85: percentile(85),
+    })
+}
+
+pub fn cycle_time_report(project: &Project) -> Vec<String> {
+    let metrics: Vec<(&str, Option<Summary>)> = vec![
+        (
+            "Lead time",
+            summarize(project.tasks.iter().filter_map(lead_time).collect()),
+        ),
+        (
+            "Cycle time",
+            summarize(project.tasks.iter().filter_map(cycle_time).collect()),
+        ),
+        (
+            "In TODO",
+            summarize(
+                project
+                    .tasks
+                    .iter()
+                    .map(|t| time_in_state(t, State::TODO))
+                    .collect(),
+            ),
+        ),
+        (
+            "In ONGOING",
+            summarize(
+                project
+                    .tasks
+                    .iter()
+                    .filter(|t| t.state_changes().iter().any(|(s, _)| *s == State::ONGOING))
+                    .map(|t| time_in_state(t, State::ONGOING))
+                    .collect(),
+            ),
+        ),
+    ];
+
+    let mut lines = vec![format!(
+        "{:<12}{:>7}{:>10}{:>10}{:>10}",
+        "", "Tasks", "Average", "p50", "p85"
+    )];
+
+    for (name, summary) in metrics {
+        lines.push(match summary {
+            Some(s) => format!(
+                "{:<12}{:>7}{:>10}{:>10}{:>10}",
+                name,
+                s.count,
+                humanize(s.average),
+                humanize(s.p50),
+                humanize(s.p85)
+            ),
+            None => format!("{:<12}{:>7}{:>10}{:>10}{:>10}", name, 0, "-", "-", "-"),
+        });
+    }
+    lines
+}
+
+pub fn quick_stats(project: &Project, now: DateTime<Utc>) -> Vec<String> {
+    let tasks: Vec<&Task> = project.tasks.iter().chain(project.archive.iter()).collect();
+    let within = |days: i64, when: DateTime<Utc>| when >= now - Duration::days(days);
+    let created = |days| tasks.iter().filter(|t| within(days, t.created_at)).count();
+    let completed = |days| {
+        tasks
+            .iter()
+            .filter_map(|t| completed_at(t))
+            .filter(|done| within(days, *done))
+            .count()
+    };
+
+    let locale = locale();
+    let mut lines = vec![
+        format!(
+            "{:<16}{:>8}{:>9}",
+            "", locale.stats_week, locale.stats_month
+        ),
+        format!(
+            "{:<16}{:>8}{:>9}",
+            locale.stats_created,
+            created(7),
+            created(30)
+        ),
+        format!(
+            "{:<16}{:>8}{:>9}",
+            locale.stats_completed,
+            completed(7),
+            completed(30)
+        ),
+        String::from(""),
+    ];
+
+    let oldest = project
+        .tasks
+        .iter()
+        .filter(|t| t.state() != State::DONE)
+        .min_by_key(|t| t.created_at);
+    lines.push(match oldest {
+        Some(task) => format!(
+            "{:<16}{} ({})",
+            locale.stats_oldest,
+            task.description(),
+            humanize(now.signed_duration_since(task.created_at))
+        ),
+        None => format!("{:<16}-", locale.stats_oldest),
+    });
+
+    let cycle = summarize(tasks.iter().filter_map(|t| cycle_time(t)).collect());
+    lines.push(match cycle {
+        Some(s) => format!(
+            "{:<16}{} {}",
+            locale.stats_cycle_time,
+            humanize(s.average),
+            fill(&locale.stats_cycle_count, &s.count.to_string())
+        ),
+        None => format!("{:<16}-", locale.stats_cycle_time),
+    });
+    lines
+}
+
+pub fn state_at(task: &Task, when: DateTime<Utc>) -> Option<State> {
+    if task.created_at > when {
+        return None;
+    }
+
+    let state = task
+        .state_changes()
+        .into_iter()
+        .take_while(|(_, date_time)| *date_time <= when)
+        .last()
+        .map(|(state, _)| state);
+    Some(state.unwrap_or(State::TODO))
+}
+
+pub struct FlowPoint {
+    pub week: NaiveDate,
+    pub todo: usize,
+    pub ongoing: usize,
+    pub waiting: usize,
+    pub done: usize,
+}
+
+pub fn cumulative_flow(project: &Project) -> Vec<FlowPoint> {
+    let first = match project.tasks.iter().map(|t| t.created_at).min() {
+        Some(first) => first.date().naive_utc(),
+        None => return vec![],
+    };
+
+    let now = Utc::now();
+    let mut week = first - Duration::days(first.weekday().num_days_from_monday() as i64);
+    let mut points = vec![];
+
+    while week <= now.date().naive_utc() {
+        let week_end = DateTime::<Utc>::from_utc(week.and_hms(0, 0, 0), Utc) + Duration::weeks(1);
+        let when = week_end.min(now);
+        let states: Vec<State> = project
+            .tasks
+            .iter()
+            .filter_map(|t| state_at(t, when))
+            .collect();
+        let count = |state: State| states.iter().filter(|s| **s == state).count();
+
+        points.push(FlowPoint {
+            week: week,
+            todo: count(State::TODO),
+            ongoing: count(State::ONGOING),
+            waiting: count(State::WAITING),
+            done: count(State::DONE),
+        });
+        week = week + Duration::weeks(1);
+    }
+    points
+}
+
+pub fn cumulative_flow_chart(project: &Project, width: usize) -> Vec<String> {
+    let points = cumulative_flow(project);
+    let max = points
+        .iter()
+        .map(|p| p.todo + p.ongoing + p.waiting + p.done)
+        .max()
+        .unwrap_or(0)
+        .max(1);
+    let scale = |count: usize| (count * width + max - 1) / max;
+
+    let mut lines = vec![format!(
+        "{:<12}{} {} {} {}",
+        "Week",
+        FormattedString::from("█ Done").fg(theme().state_color(State::DONE)),
+        FormattedString::from("█ Waiting").fg(theme().state_color(State::WAITING)),
+        FormattedString::from("█ Ongoing").fg(theme().state_color(State::ONGOING)),
+        FormattedString::from("█ To Do").fg(theme().state_color(State::TODO))
+    )];
+
+    for point in points {
+        lines.push(format!(
+            "{:<12}{}{}{}{} {}/{}/{}/{}",
+            point.week.format("%Y-%m-%d").to_string(),
+            FormattedString::from(&"█".repeat(scale(point.done)))
+                .fg(theme().state_color(State::DONE)),
+            FormattedString::from(&"█".repeat(scale(point.waiting)))
+                .fg(theme().state_color(State::WAITING)),
+            FormattedString::from(&"█".repeat(scale(point.ongoing)))
+                .fg(theme().state_color(State::ONGOING)),
+            FormattedString::from(&"█".repeat(scale(point.todo)))
+                .fg(theme().state_color(State::TODO)),
+            point.done,
+            point.waiting,
+            point.ongoing,
+            point.todo
+        ));
+    }
+    lines
+}
+
+pub fn completed_points_per_week(project: &Project) -> Vec<(NaiveDate, u32)> {
+    let completions: Vec<(NaiveDate, u32)> = project
+        .tasks
+        .iter()
+        .filter_map(|t| completed_at(t).map(|done| (done.date().naive_utc(), t.points())))
+        .filter_map(|(date, points)| points.map(|points| (date, points)))
+        .collect();
+
+    let first = match completions.iter().map(|(date, _)| *date).min() {
+        Some(first) => first - Duration::days(first.weekday().num_days_from_monday() as i64),
+        None => return vec![],
+    };
+
+    let mut weeks = vec![];
+    let mut week = first;
+    while week <= Utc::now().date().naive_utc() {
+        let next_week = week + Duration::weeks(1);
+        let points = completions
+            .iter()
+            .filter(|(date, _)| *date >= week && *date < next_week)
+            .map(|(_, points)| points)
+            .sum();
+        weeks.push((week, points));
+        week = next_week;
+    }
+    weeks
+}
+
+pub fn velocity_report(project: &Project) -> Vec<String> {
+    let total: u32 = project.tasks.iter().filter_map(|t| t.points()).sum();
+    let done: u32 = project
+        .tasks
+        .iter()
+        .filter(|t| t.state() == State::DONE)
+        .filter_map(|t| t.points())
+        .sum();
+    let weeks = completed_points_per_week(project);
+    let recent: Vec<u32> = weeks.iter().rev().take(4).map(|(_, p)| *p).collect();
+    let velocity = if recent.is_empty() {
+        0.0
+    } else {
+        recent.iter().sum::<u32>() as f64 / recent.len() as f64
+    };
+
+    let mut lines = vec![
+        format!(
+            "Points: {} total, {} done, {} remaining",
+            total,
+            done,
+            total - done
+        ),
+        format!(
+            "Velocity: {:.1} points/week (last {} weeks)",
+            velocity,
+            recent.len()
+        ),
+    ];
+    for (week, points) in weeks {
+        lines.push(format!(
+            "{:<12}{:>4}",
+            week.format("%Y-%m-%d").to_string(),
+            points
+        ));
+    }
+    lines
+}
+
+pub fn sprint_report(project: &Project) -> Vec<String> {
+    let today = Utc::now().date().naive_utc();
+    let mut lines = vec![];
+
+    for sprint in project.sprints.iter() {
+        let tasks: Vec<&Task> = project
+            .tasks
+            .iter()
+            .filter(|t| t.sprint().as_ref() == Some(&sprint.name))
+            .collect();
+        let done = tasks.iter().filter(|t| t.state() == State::DONE).count();
+        let status = if sprint.end < today {
+            "closed"
+        } else if sprint.start > today {
+            "planned"
+        } else {
+            "active"
+        };
+
+        lines.push(format!(
+            "{:<16}{} → {}  {:>3}/{:<3} {:>3}%  {}",
+            sprint.name,
+            sprint.start.format(&locale().date_format),
+            sprint.end.format(&locale().date_format),
+            done,
+            tasks.len(),
+            if tasks.is_empty() {
+                0
+            } else {
+                done * 100 / tasks.len()
+            },
+            status
+        ));
+    }
+    lines
+}
+
+pub fn daily_report(project: &Project) -> Vec<String> {
+    let daily = match &project.daily {
+        Some(daily) => daily,
+        None => return vec![],
+    };
+    let mut lines = vec![format!(
+        "Resets daily at {}",
+        daily.reset_at.format("%H:%M")
+    )];
+    for day in daily.log.iter().rev().take(14) {
+        lines.push(format!(
+            "{}  {} {:>3}/{:<3}",
+            day.date.format(&locale().date_format),
+            progress_bar(day.done, day.total, 20),
+            day.done,
+            day.total
+        ));
+    }
+    lines
+}
+
+pub fn last_event(task: &Task) -> DateTime<Utc> {
+    task.events
+        .iter()
+        .map(Event::date_time)
+        .max()
+        .unwrap_or(task.created_at)
+}
+
+pub fn stale_tasks(project: &Project, now: DateTime<Utc>, days: i64) -> Vec<(&Task, i64)> {
+    let mut stale: Vec<(&Task, i64)> = project
+        .tasks
+        .iter()
+        .filter(|task| task.state() != State::DONE)
+        .map(|task| (task, now.signed_duration_since(last_event(task)).num_days()))
+        .filter(|(_, age)| *age >= days)
+        .collect();
+    stale.sort_by_key(|(_, age)| -age);
+    stale
+}
+
+pub struct Billable {
+    pub description: String,
+    pub hours: f64,
+    pub amount: f64,
+}
+
+pub fn billable(project: &Project) -> Vec<Billable> {
+    let rate = project.rate.unwrap_or(0.0);
+    project
+        .tasks
+        .iter()
+        .chain(project.archive.iter())
+        .map(|t| (t.description(), t.tracked().num_minutes() as f64 / 60.0))
+        .filter(|(_, hours)| *hours > 0.0)
+        .map(|(description, hours)| Billable {
+            description: description,
+            hours: hours,
+            amount: hours * rate,
+        })
+        .collect()
+}
+
+pub fn billing_markdown(project: &Project) -> Vec<String> {
+    let items = billable(project);
+    let mut lines = vec![
+        format!("## {}", project.title()),
+        String::from(""),
+        format!("Rate: {:.2}/h", project.rate.unwrap_or(0.0)),
+        String::from(""),
+        String::from("| Task | Hours | Amount |"),
+        String::from("|------|------:|-------:|"),
+    ];
+    for item in items.iter() {
+        lines.push(format!(
+            "| {} | {:.2} | {:.2} |",
+            item.description.replace('|', "\\|"),
+            item.hours,
+            item.amount
+        ));
+    }
+    lines.push(format!(
+        "| **Total** | **{:.2}** | **{:.2}** |",
+        items.iter().map(|item| item.hours).sum::<f64>(),
+        items.iter().map(|item| item.amount).sum::<f64>()
+    ));
+    lines
+}
+
+pub fn progress_bar(done: usize, total: usize, width: usize) -> String {
+    let filled = if total == 0 { 0 } else { done * width / total };
+    format!(
+        "{}{}",
+        FormattedString::from(&"█".repeat(filled)).fg(GREEN),
+        FormattedString::from(&"░".repeat(width - filled)).fg(GRAY)
+    )
+}
+
+pub fn milestones_report(project: &Project) -> Vec<String> {
+    let today = Utc::now().date().naive_utc();
+    let mut lines = vec![];
+
+    for milestone in project.milestones.iter() {
+        let tasks: Vec<&Task> = project
+            .tasks
+            .iter()
+            .filter(|t| t.milestone().as_ref() == Some(&milestone.name))
+            .collect();
+        let done = tasks.iter().filter(|t| t.state() == State::DONE).count();
+        let days = milestone.target.signed_duration_since(today).num_days();
+        let remaining = match days {
+            d if d < 0 => FormattedString::from(&format!("{} days overdue", -d)).fg(RED),
+            0 => FormattedString::from("due today").fg(YELLOW),
+            d => FormattedString::from(&format!("{} days left", d)),
+        };
+
+        lines.push(format!(
+            "{:<20}{}  {} {:>3}/{:<3} {}",
+            milestone.name,
+            milestone.target.format(&locale().date_format),
+            progress_bar(done, tasks.len(), 20),
+            done,
+            tasks.len(),
+            remaining
+        ));
+    }
+    lines
+}
+
+pub fn archive_report(project: &Project) -> Vec<String> {
+    let mut archived: Vec<(&Task, Option<DateTime<Utc>>)> = project
+        .archive
+        .iter()
+        .map(|task| (task, task.state_changes().last().map(|(_, date)| *date)))
+        .collect();
+    archived.sort_by(|a, b| b.1.cmp(&a.1));
+
+    archived
+        .iter()
+        .map(|(task, closed)| {
+            let closed = match closed {
+                Some(date) => date.format(&locale().date_format).to_string(),
+                None => String::new(),
+            };
+            let reference = format!("#{}-{}", project.key(), task.number.unwrap_or_default());
+            format!(
+                "{:<12}{} {}",
+                closed,
+                FormattedString::from(&reference).fg(GRAY),
+                task.description()
+            )
+        })
+        .collect()
+}
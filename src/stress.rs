@@ -0,0 +1,169 @@
+use chrono::prelude::Utc;
+use chrono::Duration;
+use std::env;
+use std::fs;
+use std::io::{Error, ErrorKind, Result};
+use std::time::Instant;
+use uuid::Uuid;
+
+use crate::database::Database;
+use crate::filter::Filter;
+use crate::project::*;
+
+const STATES: [State; 4] = [State::TODO, State::ONGOING, State::WAITING, State::DONE];
+const WORDS: [&str; 8] = [
+    "refactor", "billing", "login", "report", "sync", "export", "layout", "cache",
+];
+
+pub struct Options {
+    pub projects: usize,
+    pub tasks: usize,
+    pub output: Option<String>,
+}
+
+impl Options {
+    pub fn parse(options: &[&str]) -> Result<Options> {
+        let mut parsed = Options {
+            projects: 200,
+            tasks: 10000,
+            output: None,
+        };
+        let mut options = options.iter();
+        while let Some(option) = options.next() {
+            let value = options.next().ok_or_else(|| {
+                Error::new(
+                    ErrorKind::InvalidInput,
+                    format!("Missing value for {}", option),
+                )
+            })?;
+            let count = || {
+                value.parse::<usize>().map_err(|_| {
+                    Error::new(
+                        ErrorKind::InvalidInput,
+                        format!("Invalid number for {}: {}", option, value),
+                    )
+                })
+            };
+            match *option {
+                "--projects" => parsed.projects = count()?.max(1),
+                "--tasks" => parsed.tasks = count()?,
+                "--output" => parsed.output = Some(value.to_string()),
+                _ => {
+                    return Err(Error::new(
+                        ErrorKind::InvalidInput,
+                        format!("Unknown option: {}", option),
+                    ))
+                }
+            }
+        }
+        Ok(parsed)
+    }
+}
+
+fn task(i: usize) -> Task {
+    let created = Utc::now() - Duration::minutes(i as i64 * 7);
+    let description = format!(
+        "Task {} {} {} @ctx{}",
+        i,
+        WORDS[i % WORDS.len()],
+        WORDS[(i / WORDS.len()) % WORDS.len()],
+        i % 5
+    );
+    let mut task = Task::new(description);
+    task.created_at = created;
+    for event in task.events.iter_mut() {
+        match event {
+            Event::State { date_time, .. } | Event::Description { date_time, .. } => {
+                *date_time = created
+            }
+            _ => {}
+        }
+    }
+    let state = STATES[i % STATES.len()];
+    if state != State::TODO {
+        task.events.push(Event::State {
+            data: state,
+            date_time: created + Duration::minutes(3),
+            actor: None,
+            host: None,
+        });
+    }
+    if i % 7 == 0 {
+        task.events.push(Event::Comment {
+            data: format!("Comment on task {}", i),
+            date_time: created + Duration::minutes(5),
+            actor: None,
+            host: None,
+        });
+    }
+    task
+}
+
+pub fn generate(projects: usize, tasks: usize) -> Vec<Project> {
+    let mut generated: Vec<Project> = (0..projects)
+        .map(|p| Project::new(format!("Project{} stress", p)))
+        .collect();
+    for i in 0..tasks {
+        generated[i % projects].tasks.push(task(i));
+    }
+    for project in generated.iter_mut() {
+        project.rank_tasks();
+        project.sort_tasks();
+    }
+    generated
+}
+
+fn timed<T>(name: &str, work: impl FnOnce() -> Result<T>) -> Result<T> {
+    let started = Instant::now();
+    let result = work()?;
+    println!(
+        "{:<10}{:>10.1} ms",
+        name,
+        started.elapsed().as_secs_f64() * 1000.0
+    );
+    Ok(result)
+}
+
+pub fn run(options: &Options) -> Result<()> {
+    let path = match &options.output {
+        Some(path) => path.clone(),
+        None => env::temp_dir()
+            .join(format!("rtasks-stress-{}.json", Uuid::new_v4()))
+            .to_string_lossy()
+            .into_owned(),
+    };
+
+    let projects = timed("generate", || Ok(generate(options.projects, options.tasks)))?;
+    timed("save", || {
+        fs::write(&path, serde_json::to_string(&projects)?)
+    })?;
+    let db = timed("load", || {
+        let projects: Vec<Project> = serde_json::from_str(&fs::read_to_string(&path)?)?;
+        Database::in_memory(projects)
+    })?;
+    let rows = timed("render", || {
+        let filter = Filter::default();
+        let mut rows = vec![];
+        for (p, project) in db.projects().enumerate() {
+            rows.push(project.view());
+            for t in db.visible_tasks(p, &filter) {
+                rows.push(db.task(p, t).view());
+            }
+        }
+        Ok(rows.len())
+    })?;
+    let hits = timed("search", || Ok(db.search("billing sync").len()))?;
+
+    println!(
+        "{} projects, {} tasks, {} rows rendered, {} search hits, {} bytes",
+        options.projects,
+        options.tasks,
+        rows,
+        hits,
+        fs::metadata(&path)?.len()
+    );
+    if options.output.is_none() {
+        fs::remove_file(&path)?;
+    }
+    Ok(())
+}
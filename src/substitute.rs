@@ -0,0 +1,64 @@
+use std::io::{Error, ErrorKind, Result};
+
+use crate::database::Database;
+use crate::project::State;
+
+const USAGE: &str = "Usage: sub /old/new/ [--all]";
+
+pub struct Substitution {
+    pub pattern: String,
+    pub replacement: String,
+    pub all: bool,
+}
+
+impl Substitution {
+    pub fn parse(arguments: &str) -> Result<Substitution> {
+        let usage = || Error::new(ErrorKind::InvalidInput, USAGE);
+        let arguments = arguments.trim();
+        let (body, all) = match arguments.strip_suffix("--all") {
+            Some(body) => (body.trim_end(), true),
+            None => (arguments, false),
+        };
+        let delimiter = body.chars().next().ok_or_else(usage)?;
+        if delimiter.is_alphanumeric() || delimiter.is_whitespace() {
+            return Err(usage());
+        }
+        let parts: Vec<&str> = body[delimiter.len_utf8()..].split(delimiter).collect();
+        match parts.as_slice() {
+            [pattern, replacement] | [pattern, replacement, ""] if !pattern.is_empty() => {
+                Ok(Substitution {
+                    pattern: pattern.to_string(),
+                    replacement: replacement.to_string(),
+                    all: all,
+                })
+            }
+            _ => Err(usage()),
+        }
+    }
+
+    pub fn apply(&self, text: &str) -> Option<String> {
+        match text.contains(&self.pattern) {
+            true => Some(text.replace(&self.pattern, &self.replacement)),
+            false => None,
+        }
+    }
+
+    pub fn matches(&self, db: &Database, project: Option<usize>) -> Vec<(usize, usize, String)> {
+        let mut matches = vec![];
+        for (p, candidate) in db.projects().enumerate() {
+            if !self.all && project != Some(p) {
+                continue;
+            }
+            for (t, task) in candidate.tasks.iter().enumerate() {
+                if task.state() == State::DONE {
+                    continue;
+                }
+                let renamed = self.apply(&task.description());
+                if let Some(renamed) = renamed.filter(|text| !text.trim().is_empty()) {
+                    matches.push((p, t, renamed));
+                }
+            }
+        }
+        matches
+    }
+}
@@ -0,0 +1,253 @@
+use serde::de;
+use serde::{Deserialize, Deserializer};
+use std::io::{Error, ErrorKind, Result};
+use std::sync::{Arc, RwLock};
+use termion::color::Rgb;
+
+use crate::formatted_string::Color;
+use crate::locale::locale;
+use crate::project::State;
+
+static THEME: RwLock<Option<Arc<Theme>>> = RwLock::new(None);
+static PALETTE: RwLock<Option<Palette>> = RwLock::new(None);
+
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum StateIcons {
+    Words,
+    Nerd,
+    Emoji,
+}
+
+impl Default for StateIcons {
+    fn default() -> StateIcons {
+        StateIcons::Words
+    }
+}
+
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum FocusStyle {
+    Background,
+    Marker,
+    Bold,
+}
+
+impl Default for FocusStyle {
+    fn default() -> FocusStyle {
+        FocusStyle::Background
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Palette {
+    Default,
+    Colorblind,
+    HighContrast,
+}
+
+impl Default for Palette {
+    fn default() -> Palette {
+        Palette::Default
+    }
+}
+
+impl<'de> Deserialize<'de> for Palette {
+    fn deserialize<D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> std::result::Result<Palette, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        Palette::from_name(&name).map_err(de::Error::custom)
+    }
+}
+
+impl Palette {
+    pub fn from_name(name: &str) -> Result<Palette> {
+        match name {
+            "default" => Ok(Palette::Default),
+            "colorblind" | "deuteranopia" | "protanopia" => Ok(Palette::Colorblind),
+            "high-contrast" => Ok(Palette::HighContrast),
+            _ => Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("Unknown theme: {}", name),
+            )),
+        }
+    }
+
+    fn state_color(self, state: State) -> Rgb {
+        match (self, state) {
+            (Palette::Default, State::TODO) => Rgb(192, 57, 43),
+            (Palette::Default, State::ONGOING) => Rgb(241, 196, 15),
+            (Palette::Default, State::WAITING) => Rgb(155, 89, 182),
+            (Palette::Default, State::DONE) => Rgb(46, 204, 113),
+            (Palette::Colorblind, State::TODO) => Rgb(213, 94, 0),
+            (Palette::Colorblind, State::ONGOING) => Rgb(240, 228, 66),
+            (Palette::Colorblind, State::WAITING) => Rgb(204, 121, 167),
+            (Palette::Colorblind, State::DONE) => Rgb(0, 114, 178),
+            (Palette::HighContrast, State::TODO) => Rgb(255, 85, 85),
+            (Palette::HighContrast, State::ONGOING) => Rgb(255, 255, 85),
+            (Palette::HighContrast, State::WAITING) => Rgb(255, 85, 255),
+            (Palette::HighContrast, State::DONE) => Rgb(85, 255, 255),
+        }
+    }
+}
+
+#[derive(Deserialize, Default, Clone, Debug)]
+#[serde(default)]
+pub struct StateStyle {
+    pub color: Option<String>,
+    pub label: Option<String>,
+}
+
+#[derive(Deserialize, Default, Clone, Debug)]
+#[serde(default)]
+pub struct Theme {
+    pub palette: Palette,
+    pub ascii: bool,
+    pub state_icons: StateIcons,
+    pub striping: bool,
+    pub wrap: bool,
+    pub done_limit: Option<usize>,
+    pub hide_scheduled: bool,
+    pub focus: FocusStyle,
+    pub todo: StateStyle,
+    pub ongoing: StateStyle,
+    pub waiting: StateStyle,
+    pub done: StateStyle,
+}
+
+impl Theme {
+    pub fn check(&self) -> Result<()> {
+        for style in [&self.todo, &self.ongoing, &self.waiting, &self.done].iter() {
+            if let Some(color) = &style.color {
+                if Color::parse(color).is_none() {
+                    return Err(Error::new(
+                        ErrorKind::InvalidData,
+                        format!("config.toml: invalid color \"{}\"", color),
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn state_style(&self, state: State) -> &StateStyle {
+        match state {
+            State::TODO => &self.todo,
+            State::ONGOING => &self.ongoing,
+            State::WAITING => &self.waiting,
+            State::DONE => &self.done,
+        }
+    }
+
+    pub fn state_color(&self, state: State) -> Color {
+        self.state_style(state)
+            .color
+            .as_ref()
+            .and_then(|color| Color::parse(color))
+            .unwrap_or_else(|| self.palette.state_color(state).into())
+    }
+
+    pub fn state_label(&self, state: State) -> String {
+        let locale = locale();
+        let default = match state {
+            State::TODO => &locale.state_todo,
+            State::ONGOING => &locale.state_ongoing,
+            State::WAITING => &locale.state_waiting,
+            State::DONE => &locale.state_done,
+        };
+        self.state_style(state)
+            .label
+            .as_ref()
+            .unwrap_or(default)
+            .clone()
+    }
+
+    pub fn vertical(&self) -> &'static str {
+        if self.ascii {
+            "|"
+        } else {
+            "┃"
+        }
+    }
+
+    pub fn scroll_track(&self) -> &'static str {
+        if self.ascii {
+            "|"
+        } else {
+            "│"
+        }
+    }
+
+    pub fn scroll_thumb(&self) -> &'static str {
+        if self.ascii {
+            "#"
+        } else {
+            "┃"
+        }
+    }
+
+    pub fn horizontal(&self) -> &'static str {
+        if self.ascii {
+            "-"
+        } else {
+            "━"
+        }
+    }
+
+    pub fn state_icon(&self, state: State) -> Option<&'static str> {
+        match (self.state_icons, state) {
+            (StateIcons::Words, _) => None,
+            (StateIcons::Nerd, State::TODO) => Some("\u{f10c}"),
+            (StateIcons::Nerd, State::ONGOING) => Some("\u{f042}"),
+            (StateIcons::Nerd, State::WAITING) => Some("\u{f017}"),
+            (StateIcons::Nerd, State::DONE) => Some("\u{f058}"),
+            (StateIcons::Emoji, State::TODO) => Some("⬜"),
+            (StateIcons::Emoji, State::ONGOING) => Some("🚧"),
+            (StateIcons::Emoji, State::WAITING) => Some("⏳"),
+            (StateIcons::Emoji, State::DONE) => Some("✅"),
+        }
+    }
+
+    pub fn state_width(&self) -> usize {
+        match self.state_icons {
+            StateIcons::Words => 7,
+            _ => 2,
+        }
+    }
+
+    pub fn cross(&self) -> &'static str {
+        if self.ascii {
+            "+"
+        } else {
+            "╋"
+        }
+    }
+
+    pub fn corners(&self) -> [&'static str; 4] {
+        if self.ascii {
+            ["+", "+", "+", "+"]
+        } else {
+            ["┏", "┓", "┗", "┛"]
+        }
+    }
+}
+
+pub fn init(mut theme: Theme) {
+    if let Some(palette) = *PALETTE.read().unwrap() {
+        theme.palette = palette;
+    }
+    *THEME.write().unwrap() = Some(Arc::new(theme));
+}
+
+pub fn override_palette(palette: Palette) {
+    *PALETTE.write().unwrap() = Some(palette);
+}
+
+pub fn theme() -> Arc<Theme> {
+    if let Some(theme) = &*THEME.read().unwrap() {
+        return theme.clone();
+    }
+    init(Theme::default());
+    theme()
+}
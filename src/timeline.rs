@@ -0,0 +1,377 @@
+use chrono::prelude::DateTime;
+use chrono::prelude::Local;
+use chrono::prelude::Utc;
+use chrono::Duration;
+use std::sync::atomic::{AtomicBool, Ordering};
+use termion::color::Rgb;
+
+use crate::formatted_string::{Color, FormattedString};
+use crate::locale::locale;
+use crate::markdown;
+use crate::project::*;
+use crate::theme::theme;
+
+static YELLOW: Rgb = Rgb(241, 196, 15);
+static GREEN: Rgb = Rgb(46, 204, 113);
+static BLUE: Rgb = Rgb(52, 152, 219);
+static PURPLE: Rgb = Rgb(214, 162, 232);
+static GRAY: Rgb = Rgb(127, 140, 141);
+static PINK: Rgb = Rgb(200, 0, 150);
+
+static RELATIVE_TIME: AtomicBool = AtomicBool::new(false);
+
+pub struct TimelineEntry {
+    index: usize,
+    event: Event,
+    correction: Option<Option<Event>>,
+    time_in_state: Option<Duration>,
+}
+
+pub fn timeline(task: &Task) -> Vec<TimelineEntry> {
+    let state_changes = task.state_changes();
+    let mut next_change = state_changes.iter().skip(1).map(|(_, date_time)| date_time);
+    let corrections = task.corrections();
+
+    task.events
+        .iter()
+        .enumerate()
+        .map(|(index, event)| {
+            let correction = corrections.get(&index).cloned();
+            let effective = match &correction {
+                Some(amended) => amended.as_ref(),
+                None => Some(event),
+            };
+            let time_in_state = match effective {
+                Some(Event::State {
+                    data, date_time, ..
+                }) => match next_change.next() {
+                    Some(until) => Some(until.signed_duration_since(*date_time)),
+                    None if *data != State::DONE => {
+                        Some(Utc::now().signed_duration_since(*date_time))
+                    }
+                    None => None,
+                },
+                _ => None,
+            };
+
+            TimelineEntry {
+                index: index,
+                event: event.clone(),
+                correction: correction,
+                time_in_state: time_in_state,
+            }
+        })
+        .collect()
+}
+
+pub fn header(task: &Task, reference: &str) -> String {
+    format!(
+        "{div_left}{when}{div}{reference} {desc}",
+        when = FormattedString::from(&locale().header_when).right(10),
+        reference = FormattedString::from(reference).fg(YELLOW),
+        desc = task.description(),
+        div_left = div().left(2),
+        div = div().center(3),
+    )
+}
+
+pub fn humanize(duration: Duration) -> String {
+    let seconds = duration.num_seconds().max(0);
+    match seconds {
+        0..=59 => format!("{}s", seconds),
+        60..=3599 => format!("{}m", seconds / 60),
+        3600..=86399 => format!("{}h {}m", seconds / 3600, seconds % 3600 / 60),
+        _ => format!("{}d {}h", seconds / 86400, seconds % 86400 / 3600),
+    }
+}
+
+pub fn parse_duration(text: &str) -> Option<Duration> {
+    let mut total = Duration::zero();
+    let mut number = String::new();
+    let mut parsed = false;
+
+    for c in text.chars().filter(|c| !c.is_whitespace()) {
+        let unit = match c {
+            '0'..='9' => {
+                number.push(c);
+                continue;
+            }
+            'd' => Duration::days(1),
+            'h' => Duration::hours(1),
+            'm' => Duration::minutes(1),
+            _ => return None,
+        };
+        total = total + unit * number.parse::<i32>().ok()?;
+        number.clear();
+        parsed = true;
+    }
+    if !number.is_empty() {
+        total = total + Duration::hours(number.parse().ok()?);
+        parsed = true;
+    }
+    if parsed {
+        Some(total)
+    } else {
+        None
+    }
+}
+
+pub fn relative(date_time: DateTime<Utc>) -> String {
+    let elapsed = Utc::now().signed_duration_since(date_time);
+    match elapsed.num_seconds() {
+        s if s < 60 => String::from("just now"),
+        s if s < 3600 => format!("{}m ago", s / 60),
+        s if s < 86400 => format!("{}h ago", s / 3600),
+        s if s < 86400 * 7 => format!("{}d ago", s / 86400),
+        s if s < 86400 * 365 => format!("{}w ago", s / (86400 * 7)),
+        s => format!("{}y ago", s / (86400 * 365)),
+    }
+}
+
+pub fn toggle_relative_time() {
+    RELATIVE_TIME.fetch_xor(true, Ordering::Relaxed);
+}
+
+pub fn timestamp(date_time: DateTime<Utc>, absolute_format: &str) -> String {
+    if RELATIVE_TIME.load(Ordering::Relaxed) {
+        relative(date_time)
+    } else {
+        date_time
+            .with_timezone(&Local)
+            .format(absolute_format)
+            .to_string()
+    }
+}
+
+fn div() -> FormattedString {
+    FormattedString::from(theme().vertical()).fg(BLUE)
+}
+
+fn state_color(state: State) -> Color {
+    theme().state_color(state)
+}
+
+impl TimelineEntry {
+    pub fn event(&self) -> &Event {
+        &self.event
+    }
+
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    pub fn correctable(&self) -> bool {
+        match self.event {
+            Event::Retract { .. } | Event::Amend { .. } => false,
+            _ => !self.retracted(),
+        }
+    }
+
+    fn retracted(&self) -> bool {
+        match self.correction {
+            Some(None) => true,
+            _ => false,
+        }
+    }
+
+    fn summary(&self) -> String {
+        summary(&self.event)
+    }
+}
+
+pub fn summary(event: &Event) -> String {
+    match event {
+        Event::Description { data, .. } => format!(
+            "{} Description set to \"{}\"",
+            FormattedString::from("✎").fg(BLUE),
+            data
+        ),
+        Event::State { data, .. } => format!(
+            "{} Moved to {}",
+            FormattedString::from("●").fg(state_color(*data)),
+            FormattedString::from(&theme().state_label(*data)).fg(state_color(*data))
+        ),
+        Event::Comment { data, .. } => format!(
+            "{} {}",
+            FormattedString::from("✉").fg(YELLOW),
+            markdown::render_inline(data)
+        ),
+        Event::Sprint {
+            data: Some(data), ..
+        } => format!(
+            "{} Assigned to sprint {}",
+            FormattedString::from("⟳").fg(BLUE),
+            data
+        ),
+        Event::Sprint { data: None, .. } => {
+            format!(
+                "{} Removed from sprint",
+                FormattedString::from("⟳").fg(BLUE)
+            )
+        }
+        Event::Milestone {
+            data: Some(data), ..
+        } => format!(
+            "{} Attached to milestone {}",
+            FormattedString::from("⚑").fg(PURPLE),
+            data
+        ),
+        Event::Milestone { data: None, .. } => format!(
+            "{} Detached from milestone",
+            FormattedString::from("⚑").fg(PURPLE)
+        ),
+        Event::Assignee {
+            data: Some(data), ..
+        } => format!(
+            "{} Assigned to {}",
+            FormattedString::from("☺").fg(PURPLE),
+            data
+        ),
+        Event::Assignee { data: None, .. } => {
+            format!("{} Unassigned", FormattedString::from("☺").fg(PURPLE))
+        }
+        Event::Attachment { data, .. } => {
+            format!("{} {}", FormattedString::from("📎").fg(BLUE), data)
+        }
+        Event::Branch { data, .. } => format!(
+            "{} Linked to branch {}",
+            FormattedString::from("⎇").fg(PURPLE),
+            data
+        ),
+        Event::Progress { data, .. } => format!(
+            "{} Progress at {}",
+            FormattedString::from("▶").fg(GREEN),
+            progress_cell(*data)
+        ),
+        Event::Checklist { data, .. } => format!(
+            "{} Checklist updated ({}/{})",
+            FormattedString::from("☑").fg(GREEN),
+            data.iter().filter(|item| item.done).count(),
+            data.len()
+        ),
+        Event::Notes { data, .. } => format!(
+            "{} Notes edited ({} lines)",
+            FormattedString::from("✎").fg(BLUE),
+            data.lines().count()
+        ),
+        Event::Someday { data: true, .. } => {
+            format!("{} Moved to Someday", FormattedString::from("☁").fg(GRAY))
+        }
+        Event::Someday { data: false, .. } => {
+            format!(
+                "{} Promoted from Someday",
+                FormattedString::from("☀").fg(YELLOW)
+            )
+        }
+        Event::Points { data, .. } => format!(
+            "{} Estimated at {} points",
+            FormattedString::from("◇").fg(PURPLE),
+            data
+        ),
+        Event::Due {
+            data: Some(due), ..
+        } => format!(
+            "{} Due on {}",
+            FormattedString::from("⚑").fg(YELLOW),
+            due.format(&locale().date_format)
+        ),
+        Event::Due { data: None, .. } => {
+            format!("{} Due date cleared", FormattedString::from("⚑").fg(GRAY))
+        }
+        Event::Start {
+            data: Some(start), ..
+        } => format!(
+            "{} Starts on {}",
+            FormattedString::from("▸").fg(BLUE),
+            start.format(&locale().date_format)
+        ),
+        Event::Start { data: None, .. } => {
+            format!("{} Start date cleared", FormattedString::from("▸").fg(GRAY))
+        }
+        Event::Waiting { data, .. } => format!(
+            "{} Waiting on {}",
+            FormattedString::from("⏳").fg(state_color(State::WAITING)),
+            data
+        ),
+        Event::Estimate { data, .. } => format!(
+            "{} Estimated at {}",
+            FormattedString::from("⧗").fg(PURPLE),
+            humanize(Duration::minutes(*data as i64))
+        ),
+        Event::Retract { data, .. } => format!(
+            "{} Removed event #{}",
+            FormattedString::from("✗").fg(PINK),
+            data + 1
+        ),
+        Event::Amend { data, .. } => format!(
+            "{} Corrected event #{}: {}",
+            FormattedString::from("✎").fg(PINK),
+            data.index + 1,
+            summary(&data.event)
+        ),
+    }
+}
+
+pub fn activity_line(
+    project: &str,
+    task: &Task,
+    date_time: DateTime<Utc>,
+    event: Option<&Event>,
+) -> String {
+    let summary = match event {
+        Some(event) => summary(event),
+        None => format!(
+            "{} {}",
+            FormattedString::from("✚").fg(GREEN),
+            locale().created
+        ),
+    };
+    format!(
+        "{} {} {} {} {}",
+        FormattedString::from(&relative(date_time))
+            .right(10)
+            .fg(GRAY),
+        FormattedString::from(project).fg(PINK),
+        task.description(),
+        FormattedString::from("·").fg(GRAY),
+        summary
+    )
+}
+
+impl Listable for TimelineEntry {
+    fn view(&self) -> String {
+        let duration = match self.time_in_state {
+            Some(duration) => FormattedString::from(&format!(" for {}", humanize(duration)))
+                .fg(GRAY)
+                .to_string(),
+            None => String::from(""),
+        };
+
+        let origin = match self.event.origin() {
+            (Some(actor), Some(host)) => format!(" by {}@{}", actor, host),
+            (Some(actor), None) => format!(" by {}", actor),
+            (None, Some(host)) => format!(" on {}", host),
+            (None, None) => String::from(""),
+        };
+
+        let (summary, mark) = match &self.correction {
+            Some(None) => (
+                FormattedString::from(&self.summary()).fg(GRAY).to_string(),
+                format!(" ({})", locale().event_removed),
+            ),
+            Some(Some(_)) => (self.summary(), format!(" ({})", locale().event_corrected)),
+            None => (self.summary(), String::from("")),
+        };
+
+        format!(
+            "{div_left}{when}{div}{summary}{duration}{origin}{mark}",
+            when = FormattedString::from(&relative(self.event.date_time())).right(10),
+            summary = summary,
+            duration = duration,
+            origin = FormattedString::from(&origin).fg(GRAY),
+            mark = FormattedString::from(&mark).fg(PINK),
+            div_left = div().left(2),
+            div = div().center(3),
+        )
+    }
+}